@@ -214,7 +214,7 @@ pub struct EventCommitment(pub Felt);
 pub struct TransactionCommitment(pub Felt);
 
 /// A Starknet transaction hash.
-#[derive(Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct TransactionHash(pub Felt);
 
 /// A Starknet transaction index.
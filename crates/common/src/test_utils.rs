@@ -3,7 +3,8 @@
 /// Metrics related test aids
 pub mod metrics {
     use metrics::{
-        Counter, CounterFn, Gauge, Histogram, Key, KeyName, Label, Recorder, SharedString, Unit,
+        Counter, CounterFn, Gauge, Histogram, HistogramFn, Key, KeyName, Label, Recorder,
+        SharedString, Unit,
     };
     use std::borrow::Cow;
     use std::collections::HashMap;
@@ -45,16 +46,22 @@ pub mod metrics {
     #[derive(Debug, Default)]
     pub struct FakeRecorder(FakeRecorderHandle);
 
-    /// Handle to the [`FakeRecorder`], which allows to get the current value of counters.
+    /// Handle to the [`FakeRecorder`], which allows to get the current value of counters and the
+    /// number of samples recorded by histograms.
     #[derive(Clone, Debug, Default)]
     pub struct FakeRecorderHandle {
         counters: Arc<RwLock<HashMap<Key, Arc<FakeCounterFn>>>>,
+        histograms: Arc<RwLock<HashMap<Key, Arc<FakeHistogramFn>>>>,
         methods: Option<&'static [&'static str]>,
     }
 
     #[derive(Debug, Default)]
     struct FakeCounterFn(AtomicU64);
 
+    /// Only counts the recorded samples.
+    #[derive(Debug, Default)]
+    struct FakeHistogramFn(AtomicU64);
+
     impl Recorder for FakeRecorder {
         fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
         fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
@@ -91,8 +98,15 @@ pub mod metrics {
         fn register_gauge(&self, _: &Key) -> Gauge {
             unimplemented!()
         }
-        fn register_histogram(&self, _: &Key) -> Histogram {
-            unimplemented!()
+        /// Registers a histogram under the same conditions as [`Self::register_counter`].
+        fn register_histogram(&self, key: &Key) -> Histogram {
+            if self.is_key_used(key) {
+                let mut write_guard = self.0.histograms.write().unwrap();
+                let histogram = write_guard.entry(key.clone()).or_insert_with(Arc::default);
+                Histogram::from_arc(histogram.clone())
+            } else {
+                Histogram::noop()
+            }
         }
     }
 
@@ -103,6 +117,7 @@ pub mod metrics {
         pub fn new_for(methods: &'static [&'static str]) -> Self {
             Self(FakeRecorderHandle {
                 counters: Arc::default(),
+                histograms: Arc::default(),
                 methods: Some(methods),
             })
         }
@@ -167,6 +182,22 @@ pub mod metrics {
                 .0
                 .load(Ordering::Relaxed)
         }
+
+        /// Returns the number of samples recorded by the unlabelled histogram `histogram_name`,
+        /// which is zero if it was never registered.
+        pub fn get_histogram_count(&self, histogram_name: &'static str) -> u64 {
+            let read_guard = self.histograms.read().unwrap();
+            read_guard
+                .get(&Key::from_name(histogram_name))
+                .map(|histogram| histogram.0.load(Ordering::Relaxed))
+                .unwrap_or_default()
+        }
+    }
+
+    impl HistogramFn for FakeHistogramFn {
+        fn record(&self, _: f64) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     impl CounterFn for FakeCounterFn {
@@ -17,6 +17,10 @@ pub struct EthereumStateUpdate {
     pub state_root: StateCommitment,
     pub block_number: BlockNumber,
     pub block_hash: BlockHash,
+    /// The L1 block at which this state was read, if known.
+    pub l1_block_number: Option<u64>,
+    /// The L1 transaction which submitted this state, if known.
+    pub l1_tx_hash: Option<H256>,
 }
 
 #[async_trait::async_trait]
@@ -47,18 +51,24 @@ impl EthereumClient {
         })
     }
 
-    async fn get_latest_block_hash(&self) -> anyhow::Result<H256> {
-        self.call_ethereum(serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "eth_getBlockByNumber",
-            "params": [
-                "latest",
-                false
-            ],
-            "id": 0
-        }))
-        .await
-        .and_then(|value| get_h256(&value["hash"]))
+    /// Returns the hash and number of the latest L1 block.
+    async fn get_latest_block(&self) -> anyhow::Result<(H256, u64)> {
+        let block = self
+            .call_ethereum(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "eth_getBlockByNumber",
+                "params": [
+                    "latest",
+                    false
+                ],
+                "id": 0
+            }))
+            .await?;
+
+        let hash = get_h256(&block["hash"])?;
+        let number = get_u256(&block["number"])?.as_u64();
+
+        Ok((hash, number))
     }
 
     async fn call_starknet_contract(
@@ -102,7 +112,7 @@ impl EthereumClient {
 #[async_trait::async_trait]
 impl EthereumApi for EthereumClient {
     async fn get_starknet_state(&self, address: &H160) -> anyhow::Result<EthereumStateUpdate> {
-        let hash = self.get_latest_block_hash().await?;
+        let (hash, l1_block_number) = self.get_latest_block().await?;
         let hash = format!("0x{}", hex::encode(hash.as_bytes()));
         let addr = format!("0x{}", hex::encode(address.as_bytes()));
         Ok(EthereumStateUpdate {
@@ -123,6 +133,10 @@ impl EthereumApi for EthereumClient {
                 .await
                 .and_then(|value| get_u256(&value))
                 .and_then(get_number)?,
+            l1_block_number: Some(l1_block_number),
+            // The state is read from the contract, which does not reveal the transaction that
+            // submitted it.
+            l1_tx_hash: None,
         })
     }
 
@@ -293,6 +307,8 @@ mod tests {
             state_root: StateCommitment(get_felt(global_root)?),
             block_number: get_number(block_number)?,
             block_hash: BlockHash(get_felt(block_hash)?),
+            l1_block_number: Some(0x1048e0e),
+            l1_tx_hash: None,
         };
 
         let addr = H160::from_slice(&core_addr::MAINNET);
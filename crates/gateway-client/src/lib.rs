@@ -52,6 +52,24 @@ pub trait GatewayApi: Sync {
         unimplemented!();
     }
 
+    /// Fetches multiple transactions using [GatewayApi::transaction], with at most `concurrency`
+    /// requests in flight at a time.
+    ///
+    /// The results are in the same order as `transaction_hashes`.
+    async fn transactions(
+        &self,
+        transaction_hashes: &[TransactionHash],
+        concurrency: usize,
+    ) -> Vec<Result<reply::Transaction, SequencerError>> {
+        use futures::StreamExt;
+
+        futures::stream::iter(transaction_hashes.iter().copied())
+            .map(|transaction_hash| self.transaction(transaction_hash))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
     async fn state_update(
         &self,
         block: BlockId,
@@ -900,6 +918,71 @@ mod tests {
         }
     }
 
+    mod transactions {
+        use super::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Records the maximum number of concurrent [GatewayApi::transaction] requests.
+        #[derive(Default)]
+        struct ConcurrencyRecorder {
+            in_flight: AtomicUsize,
+            max_in_flight: AtomicUsize,
+        }
+
+        #[async_trait::async_trait]
+        impl GatewayApi for ConcurrencyRecorder {
+            async fn transaction(
+                &self,
+                transaction_hash: TransactionHash,
+            ) -> Result<reply::Transaction, SequencerError> {
+                let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                // Abuse the block hash to tell the replies apart.
+                Ok(reply::Transaction {
+                    block_hash: Some(BlockHash(transaction_hash.0)),
+                    block_number: None,
+                    status: reply::Status::Received,
+                    transaction: None,
+                    transaction_index: None,
+                    transaction_failure_reason: None,
+                })
+            }
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn concurrency_is_bounded() {
+            let gateway = ConcurrencyRecorder::default();
+            let hashes = (0..10u64)
+                .map(|i| TransactionHash(Felt::from_u64(i)))
+                .collect::<Vec<_>>();
+
+            let replies = gateway.transactions(&hashes, 3).await;
+
+            assert_eq!(gateway.max_in_flight.load(Ordering::SeqCst), 3);
+            let block_hashes = replies
+                .into_iter()
+                .map(|reply| reply.unwrap().block_hash.unwrap().0)
+                .collect::<Vec<_>>();
+            let expected = hashes.iter().map(|hash| hash.0).collect::<Vec<_>>();
+            assert_eq!(block_hashes, expected);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn zero_concurrency_is_sequential() {
+            let gateway = ConcurrencyRecorder::default();
+            let hashes = [TransactionHash(felt!("0x1")), TransactionHash(felt!("0x2"))];
+
+            let replies = gateway.transactions(&hashes, 0).await;
+
+            assert_eq!(replies.len(), 2);
+            assert_eq!(gateway.max_in_flight.load(Ordering::SeqCst), 1);
+        }
+    }
+
     mod state_update_matches_by_hash_on {
         use super::{
             reply::state_update::{DeployedContract, StorageDiff},
@@ -1,11 +1,18 @@
 use crate::reply::{PendingBlock, PendingStateUpdate};
-use pathfinder_common::{BlockHash, BlockTimestamp};
+use pathfinder_common::{BlockHash, BlockTimestamp, TransactionHash};
+use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 struct PendingInner {
     pub block: Arc<PendingBlock>,
     pub state_update: Arc<PendingStateUpdate>,
+    /// Index of the pending block's transaction hashes, so that membership checks
+    /// don't require scanning the entire block.
+    ///
+    /// Includes the hashes of the block's receipts, as the sequencer may list a transaction's
+    /// receipt slightly before or after the transaction itself.
+    pub transactions: HashSet<TransactionHash>,
 }
 
 #[derive(Default, Clone)]
@@ -15,9 +22,22 @@ pub struct PendingData {
 
 impl PendingData {
     pub async fn set(&self, block: Arc<PendingBlock>, state_update: Arc<PendingStateUpdate>) {
+        let transactions = block
+            .transactions
+            .iter()
+            .map(|tx| tx.hash())
+            .chain(
+                block
+                    .transaction_receipts
+                    .iter()
+                    .map(|receipt| receipt.transaction_hash),
+            )
+            .collect();
+
         *self.inner.write().await = Some(PendingInner {
             block,
             state_update,
+            transactions,
         });
     }
 
@@ -33,6 +53,21 @@ impl PendingData {
             .map(|inner| inner.block.clone())
     }
 
+    /// Returns the pending block if it contains the given transaction, either in its transactions
+    /// or its receipts.
+    pub async fn block_containing(
+        &self,
+        transaction_hash: &TransactionHash,
+    ) -> Option<Arc<PendingBlock>> {
+        let g = self.inner.read().await;
+        let inner = g.as_ref()?;
+
+        inner
+            .transactions
+            .contains(transaction_hash)
+            .then(|| inner.block.clone())
+    }
+
     pub async fn state_update(&self) -> Option<Arc<PendingStateUpdate>> {
         self.inner
             .read()
@@ -54,3 +89,103 @@ impl PendingData {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reply::transaction::Transaction;
+    use stark_hash::Felt;
+    use starknet_gateway_test_fixtures::{v0_11_0, v0_9_0};
+
+    /// Creates pending data whose block contains `count` transactions.
+    async fn pending_data_with(count: u64) -> PendingData {
+        let mut block: PendingBlock = serde_json::from_str(v0_9_0::block::PENDING).unwrap();
+        let state_update: PendingStateUpdate =
+            serde_json::from_str(v0_11_0::state_update::PENDING).unwrap();
+
+        let template = match &block.transactions[0] {
+            Transaction::Deploy(tx) => tx.clone(),
+            _ => panic!("Fixture's first transaction should be a deploy"),
+        };
+        block.transactions = (0..count)
+            .map(|i| {
+                let mut tx = template.clone();
+                tx.transaction_hash = TransactionHash(Felt::from_u64(i));
+                Transaction::Deploy(tx)
+            })
+            .collect();
+
+        let pending = PendingData::default();
+        pending.set(Arc::new(block), Arc::new(state_update)).await;
+        pending
+    }
+
+    #[tokio::test]
+    async fn block_containing() {
+        let pending = PendingData::default();
+        let tx_hash = TransactionHash(Felt::from_u64(1));
+        assert!(pending.block_containing(&tx_hash).await.is_none());
+
+        let pending = pending_data_with(3).await;
+        assert!(pending.block_containing(&tx_hash).await.is_some());
+
+        let unknown = TransactionHash(Felt::from_u64(3));
+        assert!(pending.block_containing(&unknown).await.is_none());
+
+        pending.clear().await;
+        assert!(pending.block_containing(&tx_hash).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn block_containing_receipt_only() {
+        let pending = pending_data_with(3).await;
+        let mut block = (*pending.block().await.unwrap()).clone();
+        let state_update = pending.state_update().await.unwrap();
+
+        // The transaction list lags behind the receipts.
+        let receipt_only = TransactionHash(Felt::from_u64(2));
+        let mut receipt = block.transaction_receipts[0].clone();
+        receipt.transaction_hash = receipt_only;
+        block.transaction_receipts.push(receipt);
+        block.transactions.retain(|tx| tx.hash() != receipt_only);
+        assert!(block
+            .transactions
+            .iter()
+            .all(|tx| tx.hash() != receipt_only));
+
+        pending.set(Arc::new(block), state_update).await;
+        assert!(pending.block_containing(&receipt_only).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn membership_does_not_scale_with_block_size() {
+        const LOOKUPS: u64 = 10_000;
+
+        async fn time_lookups(pending: &PendingData) -> std::time::Duration {
+            let start = std::time::Instant::now();
+            for i in 0..LOOKUPS {
+                // Offset so that the lookups miss, which is the worst case for a linear scan.
+                let tx_hash = TransactionHash(Felt::from_u64(u64::MAX - i));
+                assert!(pending.block_containing(&tx_hash).await.is_none());
+            }
+            start.elapsed()
+        }
+
+        let small = pending_data_with(10).await;
+        let large = pending_data_with(10_000).await;
+
+        // Warm up.
+        time_lookups(&small).await;
+        time_lookups(&large).await;
+
+        let small = time_lookups(&small).await;
+        let large = time_lookups(&large).await;
+
+        // A linear scan would be ~1000x slower for the large block. The generous margin
+        // is to avoid spurious failures on noisy machines.
+        assert!(
+            large < small * 50,
+            "Lookups took {large:?} for the large block vs {small:?} for the small block"
+        );
+    }
+}
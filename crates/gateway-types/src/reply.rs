@@ -102,8 +102,7 @@ impl MaybePendingBlock {
 }
 
 /// Block and transaction status values.
-#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
-#[serde(deny_unknown_fields)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize)]
 pub enum Status {
     #[serde(rename = "NOT_RECEIVED")]
     NotReceived,
@@ -111,6 +110,8 @@ pub enum Status {
     Received,
     #[serde(rename = "PENDING")]
     Pending,
+    #[serde(rename = "PRE_CONFIRMED")]
+    PreConfirmed,
     #[serde(rename = "REJECTED")]
     Rejected,
     #[serde(rename = "ACCEPTED_ON_L1")]
@@ -121,6 +122,34 @@ pub enum Status {
     Reverted,
     #[serde(rename = "ABORTED")]
     Aborted,
+    /// A status which was added to the gateway after this version of pathfinder.
+    #[serde(rename = "UNKNOWN")]
+    Unknown,
+}
+
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let status = String::deserialize(deserializer)?;
+
+        Ok(match status.as_str() {
+            "NOT_RECEIVED" => Status::NotReceived,
+            "RECEIVED" => Status::Received,
+            "PENDING" => Status::Pending,
+            "PRE_CONFIRMED" => Status::PreConfirmed,
+            "REJECTED" => Status::Rejected,
+            "ACCEPTED_ON_L1" => Status::AcceptedOnL1,
+            "ACCEPTED_ON_L2" => Status::AcceptedOnL2,
+            "REVERTED" => Status::Reverted,
+            "ABORTED" => Status::Aborted,
+            other => {
+                tracing::warn!(raw_status = ?other, "Unrecognized gateway status");
+                Status::Unknown
+            }
+        })
+    }
 }
 
 impl std::fmt::Display for Status {
@@ -129,11 +158,13 @@ impl std::fmt::Display for Status {
             Status::NotReceived => write!(f, "NOT_RECEIVED"),
             Status::Received => write!(f, "RECEIVED"),
             Status::Pending => write!(f, "PENDING"),
+            Status::PreConfirmed => write!(f, "PRE_CONFIRMED"),
             Status::Rejected => write!(f, "REJECTED"),
             Status::AcceptedOnL1 => write!(f, "ACCEPTED_ON_L1"),
             Status::AcceptedOnL2 => write!(f, "ACCEPTED_ON_L2"),
             Status::Reverted => write!(f, "REVERTED"),
             Status::Aborted => write!(f, "ABORTED"),
+            Status::Unknown => write!(f, "UNKNOWN"),
         }
     }
 }
@@ -155,20 +186,22 @@ pub mod call {
 }
 
 /// Used to deserialize replies to Starknet transaction requests.
+///
+/// Serializes to the same shape, omitting the fields which the gateway omitted.
 #[serde_as]
-#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Transaction {
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub block_hash: Option<BlockHash>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub block_number: Option<BlockNumber>,
     pub status: Status,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub transaction: Option<transaction::Transaction>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub transaction_index: Option<u64>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub transaction_failure_reason: Option<transaction::Failure>,
 }
 
@@ -272,6 +305,20 @@ pub mod transaction {
         pub to_address: EthereumAddress,
     }
 
+    /// Represents the execution outcome of an L2 transaction.
+    ///
+    /// Receipts from before Starknet 0.12.1 do not contain this field and are implicitly
+    /// [ExecutionStatus::Succeeded].
+    #[derive(Copy, Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+    #[serde(deny_unknown_fields)]
+    pub enum ExecutionStatus {
+        #[default]
+        #[serde(rename = "SUCCEEDED")]
+        Succeeded,
+        #[serde(rename = "REVERTED")]
+        Reverted,
+    }
+
     /// Represents deserialized L2 transaction receipt data.
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
     #[serde(deny_unknown_fields)]
@@ -281,8 +328,13 @@ pub mod transaction {
         pub events: Vec<pathfinder_common::event::Event>,
         #[serde(skip_serializing_if = "Option::is_none")]
         pub execution_resources: Option<ExecutionResources>,
+        #[serde(default)]
+        pub execution_status: ExecutionStatus,
         pub l1_to_l2_consumed_message: Option<L1ToL2Message>,
         pub l2_to_l1_messages: Vec<L2ToL1Message>,
+        /// The reason a transaction was reverted, only present for reverted transactions.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub revert_error: Option<String>,
         pub transaction_hash: TransactionHash,
         pub transaction_index: TransactionIndex,
     }
@@ -399,6 +451,35 @@ pub mod transaction {
                 Transaction::L1Handler(t) => t.contract_address,
             }
         }
+
+        /// Returns the nonce of the transaction if it is sequenced by its sender's nonce.
+        pub fn nonce(&self) -> Option<TransactionNonce> {
+            match self {
+                Transaction::Declare(DeclareTransaction::V0(_)) => None,
+                Transaction::Declare(DeclareTransaction::V1(t)) => Some(t.nonce),
+                Transaction::Declare(DeclareTransaction::V2(t)) => Some(t.nonce),
+                Transaction::Deploy(_) => None,
+                Transaction::DeployAccount(t) => Some(t.nonce),
+                Transaction::Invoke(InvokeTransaction::V0(_)) => None,
+                Transaction::Invoke(InvokeTransaction::V1(t)) => Some(t.nonce),
+                // L1 handler nonces are L1 message nonces.
+                Transaction::L1Handler(_) => None,
+            }
+        }
+
+        /// Returns the version of the transaction, which may include the query version bit.
+        pub fn version(&self) -> TransactionVersion {
+            match self {
+                Transaction::Declare(DeclareTransaction::V0(_)) => TransactionVersion::ZERO,
+                Transaction::Declare(DeclareTransaction::V1(_)) => TransactionVersion::ONE,
+                Transaction::Declare(DeclareTransaction::V2(_)) => TransactionVersion::TWO,
+                Transaction::Deploy(t) => t.version,
+                Transaction::DeployAccount(t) => t.version,
+                Transaction::Invoke(InvokeTransaction::V0(_)) => TransactionVersion::ZERO,
+                Transaction::Invoke(InvokeTransaction::V1(_)) => TransactionVersion::ONE,
+                Transaction::L1Handler(t) => t.version,
+            }
+        }
     }
 
     #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
@@ -680,7 +761,7 @@ pub mod transaction {
     }
 
     /// Describes L2 transaction failure details.
-    #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
     #[serde(deny_unknown_fields)]
     pub struct Failure {
         pub code: String,
@@ -989,4 +1070,20 @@ mod tests {
             assert_matches::assert_matches!(tx, TransactionVariant::L1Handler(_));
         }
     }
+
+    mod status {
+        use super::super::Status;
+
+        #[test]
+        fn known() {
+            let status = serde_json::from_str::<Status>(r#""ACCEPTED_ON_L2""#).unwrap();
+            assert_eq!(status, Status::AcceptedOnL2);
+        }
+
+        #[test]
+        fn unrecognized_is_unknown() {
+            let status = serde_json::from_str::<Status>(r#""SOME_FUTURE_STATUS""#).unwrap();
+            assert_eq!(status, Status::Unknown);
+        }
+    }
 }
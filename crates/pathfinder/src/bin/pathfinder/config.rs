@@ -1,5 +1,6 @@
 use clap::{CommandFactory, Parser};
 use pathfinder_common::AllowedOrigins;
+use pathfinder_rpc::transaction_status::PendingStatusPolicy;
 use pathfinder_storage::JournalMode;
 use reqwest::Url;
 use std::collections::HashSet;
@@ -109,6 +110,144 @@ Examples:
     )]
     poll_pending: bool,
 
+    #[arg(
+        long = "rpc.gateway-pending-fallback",
+        long_help = "Query the gateway for the pending status of transactions if pending block \
+            polling is disabled. This costs a gateway query per transaction status lookup",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_RPC_GATEWAY_PENDING_FALLBACK",
+    )]
+    gateway_pending_fallback: bool,
+
+    #[arg(
+        long = "rpc.gateway-status-fallback",
+        long_help = "Query the gateway for the status of transactions which are unknown to this \
+            node. Disable this if there is no upstream sequencer, in which case such transactions \
+            are reported as NOT_RECEIVED",
+        action = clap::ArgAction::Set,
+        default_value = "true",
+        env = "PATHFINDER_RPC_GATEWAY_STATUS_FALLBACK",
+    )]
+    gateway_status_fallback: bool,
+
+    #[arg(
+        long = "rpc.status-gateway-url",
+        value_name = "URL",
+        value_hint = clap::ValueHint::Url,
+        long_help = "Base url of the gateway which is queried for the status of transactions, \
+            instead of the network's gateway. Can be used to route status lookups to a test \
+            gateway",
+        env = "PATHFINDER_RPC_STATUS_GATEWAY_URL",
+    )]
+    status_gateway: Option<Url>,
+
+    #[arg(
+        long = "rpc.fallback-gateway-urls",
+        value_name = "URL-LIST",
+        long_help = "Comma separated base urls of gateways which are queried in order for the \
+            status of transactions, while the status gateway is unavailable",
+        value_delimiter = ',',
+        env = "PATHFINDER_RPC_FALLBACK_GATEWAY_URLS"
+    )]
+    fallback_gateways: Vec<Url>,
+
+    #[arg(
+        long = "rpc.status-concurrency-limit",
+        long_help = "Maximum number of transaction status queries which are resolved concurrently. \
+            Further queries wait briefly for one of these to complete, and otherwise fail with a \
+            retryable error",
+        default_value = "64",
+        env = "PATHFINDER_RPC_STATUS_CONCURRENCY_LIMIT"
+    )]
+    status_concurrency_limit: std::num::NonZeroUsize,
+
+    #[arg(
+        long = "rpc.status-wait-jitter",
+        value_name = "MILLISECONDS",
+        long_help = "Longest random delay before a transaction status query which waits for a \
+            status re-checks it. This spreads out the database queries of waits which are woken \
+            by the same new block",
+        default_value = "250",
+        env = "PATHFINDER_RPC_STATUS_WAIT_JITTER"
+    )]
+    status_wait_jitter: u64,
+
+    #[arg(
+        long = "rpc.status-waits",
+        long_help = "Let transaction status queries wait for a status via `wait_for`. Waits can \
+            also be disabled at runtime with the `pathfinder_admin_setStatusWaits` admin method, \
+            e.g. when holding connections open is dangerous",
+        action = clap::ArgAction::Set,
+        default_value = "true",
+        env = "PATHFINDER_RPC_STATUS_WAITS"
+    )]
+    status_waits: bool,
+
+    #[arg(
+        long = "rpc.status-read-replica",
+        long_help = "Path to a read replica of the database, which is kept up to date by an \
+            external replication tool. Transaction status queries are served from the replica, \
+            and only fall back to the primary database for transactions missing from it",
+        value_name = "PATH",
+        env = "PATHFINDER_RPC_STATUS_READ_REPLICA"
+    )]
+    status_read_replica: Option<PathBuf>,
+
+    #[arg(
+        long = "rpc.pending-status",
+        long_help = "How transaction status queries report a transaction which is only part of \
+            the pending block: as 'pending', or as 'accepted-on-l2' for integrators which treat \
+            inclusion in the pending block as acceptance",
+        value_enum,
+        default_value = "pending",
+        env = "PATHFINDER_RPC_PENDING_STATUS"
+    )]
+    pending_status: PendingStatus,
+
+    #[arg(
+        long = "rpc.known-transaction-filter",
+        long_help = "Keep an in-memory filter of all stored transactions, so that status queries \
+            for transactions this node does not know skip the database. The filter is built from \
+            the database at startup, which may take a while, and takes about 10 bits of memory \
+            per transaction",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_RPC_KNOWN_TRANSACTION_FILTER",
+    )]
+    known_transaction_filter: bool,
+
+    #[arg(
+        long = "rpc.status-consistency-check",
+        long_help = "Periodically compare the status of recently accepted transactions to the \
+            gateway's, logging and counting any disagreements. Meant for catching sync bugs, at \
+            the cost of a gateway query per sampled transaction",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_RPC_STATUS_CONSISTENCY_CHECK",
+    )]
+    status_consistency_check: bool,
+
+    #[arg(
+        long = "rpc.status-consistency-check-interval",
+        value_name = "SECONDS",
+        long_help = "How often the status consistency check runs, if enabled",
+        default_value = "300",
+        env = "PATHFINDER_RPC_STATUS_CONSISTENCY_CHECK_INTERVAL"
+    )]
+    status_consistency_check_interval: std::num::NonZeroU64,
+
+    #[arg(
+        long = "rpc.admin-methods",
+        long_help = "Serve the pathfinder admin methods, such as \
+            `pathfinder_admin_prewarmTransactionStatuses`, alongside the other pathfinder methods. \
+            These can be costly, so only enable this if the RPC address is not publicly reachable",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_RPC_ADMIN_METHODS",
+    )]
+    admin_methods: bool,
+
     #[arg(
         long = "python-subprocesses",
         long_help = "Number of Python starknet VMs subprocesses to start",
@@ -185,6 +324,12 @@ enum Network {
     Custom,
 }
 
+#[derive(clap::ValueEnum, Clone)]
+enum PendingStatus {
+    Pending,
+    AcceptedOnL2,
+}
+
 impl From<Network> for clap::builder::OsStr {
     fn from(value: Network) -> Self {
         match value {
@@ -276,6 +421,18 @@ pub struct Config {
     pub monitor_address: Option<SocketAddr>,
     pub network: Option<NetworkConfig>,
     pub poll_pending: bool,
+    pub gateway_pending_fallback: bool,
+    pub gateway_status_fallback: bool,
+    pub status_gateway: Option<Url>,
+    pub fallback_gateways: Vec<Url>,
+    pub status_concurrency_limit: std::num::NonZeroUsize,
+    pub status_wait_jitter: std::time::Duration,
+    pub status_waits: bool,
+    pub status_read_replica: Option<PathBuf>,
+    pub pending_status_policy: PendingStatusPolicy,
+    pub known_transaction_filter: bool,
+    pub status_consistency_check: Option<std::time::Duration>,
+    pub admin_methods: bool,
     pub python_subprocesses: std::num::NonZeroUsize,
     pub sqlite_wal: JournalMode,
     pub max_rpc_connections: std::num::NonZeroU32,
@@ -365,6 +522,23 @@ impl Config {
             monitor_address: cli.monitor_address,
             network,
             poll_pending: cli.poll_pending,
+            gateway_pending_fallback: cli.gateway_pending_fallback,
+            gateway_status_fallback: cli.gateway_status_fallback,
+            status_gateway: cli.status_gateway,
+            fallback_gateways: cli.fallback_gateways,
+            status_concurrency_limit: cli.status_concurrency_limit,
+            status_wait_jitter: std::time::Duration::from_millis(cli.status_wait_jitter),
+            status_waits: cli.status_waits,
+            status_read_replica: cli.status_read_replica,
+            pending_status_policy: match cli.pending_status {
+                PendingStatus::Pending => PendingStatusPolicy::Pending,
+                PendingStatus::AcceptedOnL2 => PendingStatusPolicy::AcceptedOnL2,
+            },
+            known_transaction_filter: cli.known_transaction_filter,
+            status_consistency_check: cli.status_consistency_check.then(|| {
+                std::time::Duration::from_secs(cli.status_consistency_check_interval.get())
+            }),
+            admin_methods: cli.admin_methods,
             python_subprocesses: cli.python_subprocesses,
             sqlite_wal: match cli.sqlite_wal {
                 true => JournalMode::WAL,
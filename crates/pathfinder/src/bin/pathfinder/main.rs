@@ -81,6 +81,14 @@ async fn main() -> anyhow::Result<()> {
     let rpc_storage = storage_manager
         .create_pool(NonZeroU32::new(20).unwrap())
         .context("Creating database connection pool for sync")?;
+    let rpc_storage = match config.status_read_replica {
+        Some(replica) => {
+            let replica = Storage::open_replica(replica, NonZeroU32::new(20).unwrap())
+                .context("Opening database read replica")?;
+            rpc_storage.with_replica(replica)
+        }
+        None => rpc_storage,
+    };
     let p2p_storage = storage_manager
         .create_pool(NonZeroU32::new(1).unwrap())
         .context("Creating database connection pool for p2p")?;
@@ -96,6 +104,40 @@ async fn main() -> anyhow::Result<()> {
 
     let sync_state = Arc::new(SyncState::default());
     let pending_state = PendingData::default();
+    let (l1_head_tx, l1_head_rx) = tokio::sync::watch::channel(None);
+    let pending_sightings = pathfinder_rpc::transaction_status::PendingSightings::default();
+    let known_transactions = match config.known_transaction_filter {
+        true => {
+            info!("Loading known transactions");
+            let storage = sync_storage.clone();
+            tokio::task::spawn_blocking(move || {
+                pathfinder_rpc::transaction_status::KnownTransactions::load(&storage)
+            })
+            .await
+            .context("Joining known transactions task")?
+            .context("Loading known transactions")?
+        }
+        false => Default::default(),
+    };
+    let empty_storage = {
+        let storage = sync_storage.clone();
+        tokio::task::spawn_blocking(move || {
+            pathfinder_rpc::transaction_status::EmptyStorage::load(&storage)
+        })
+        .await
+        .context("Joining empty storage task")?
+        .context("Checking for empty storage")?
+    };
+    let storage_head = {
+        let storage = sync_storage.clone();
+        tokio::task::spawn_blocking(move || {
+            pathfinder_rpc::transaction_status::StorageHead::load(&storage)
+        })
+        .await
+        .context("Joining storage head task")?
+        .context("Loading storage head")?
+    };
+    let database_status_cache = pathfinder_rpc::transaction_status::DatabaseStatusCache::default();
     let pending_interval = match config.poll_pending {
         true => Some(std::time::Duration::from_secs(5)),
         false => None,
@@ -121,12 +163,48 @@ async fn main() -> anyhow::Result<()> {
         pathfinder_context.gateway.clone(),
     )
     .with_call_handling(call_handle)
-    .with_eth_gas_price(shared);
-    let context = match config.poll_pending {
-        true => context.with_pending_data(pending_state.clone()),
-        false => context,
+    .with_eth_gas_price(shared)
+    .with_l1_head(l1_head_rx)
+    .with_pending_sightings(pending_sightings.clone())
+    .with_known_transactions(known_transactions.clone())
+    .with_empty_storage(empty_storage.clone())
+    .with_storage_head(storage_head.clone())
+    .with_database_status_cache(database_status_cache.clone())
+    .with_gateway_pending_fallback(config.gateway_pending_fallback)
+    .with_gateway_status_fallback(config.gateway_status_fallback)
+    .with_status_concurrency_limit(config.status_concurrency_limit.get())
+    .with_wait_jitter(config.status_wait_jitter)
+    .with_status_waits(config.status_waits)
+    .with_pending_status_policy(config.pending_status_policy);
+    let context = match config.status_gateway {
+        Some(url) => context.with_status_gateway(
+            starknet_gateway_client::Client::with_base_url(url)
+                .context("Creating status gateway client")?,
+        ),
+        None => context,
+    };
+    let fallback_gateways = config
+        .fallback_gateways
+        .into_iter()
+        .map(starknet_gateway_client::Client::with_base_url)
+        .collect::<Result<Vec<_>, _>>()
+        .context("Creating fallback gateway clients")?;
+    let context = context.with_fallback_gateways(fallback_gateways);
+    let context = match pending_interval {
+        Some(interval) => context
+            .with_pending_data(pending_state.clone())
+            .with_pending_interval(interval),
+        None => context,
     };
 
+    if let Some(interval) = config.status_consistency_check {
+        tokio::spawn(pathfinder_rpc::status_consistency::run(
+            context.clone(),
+            interval,
+            pathfinder_rpc::status_consistency::DEFAULT_SAMPLE_SIZE,
+        ));
+    }
+
     let rpc_server = pathfinder_rpc::RpcServer::new(config.rpc_address, context);
     let rpc_server = match config.rpc_cors_domains {
         Some(allowed_origins) => rpc_server.with_cors(allowed_origins),
@@ -138,6 +216,11 @@ async fn main() -> anyhow::Result<()> {
         None => rpc_server,
     };
 
+    let rpc_server = match config.admin_methods {
+        true => rpc_server.with_admin_methods(),
+        false => rpc_server,
+    };
+
     let sync_handle = tokio::spawn(state::sync(
         sync_storage,
         ethereum.client,
@@ -149,6 +232,12 @@ async fn main() -> anyhow::Result<()> {
         state::l1::sync,
         state::l2::sync,
         pending_state,
+        l1_head_tx,
+        pending_sightings,
+        known_transactions,
+        empty_storage,
+        storage_head,
+        database_status_cache,
         pending_interval,
         state::l2::BlockValidationMode::Strict,
         rpc_server.get_ws_senders(),
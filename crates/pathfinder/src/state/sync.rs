@@ -15,6 +15,9 @@ use pathfinder_merkle_tree::{
     ClassCommitmentTree, StorageCommitmentTree,
 };
 use pathfinder_rpc::{
+    transaction_status::{
+        DatabaseStatusCache, EmptyStorage, KnownTransactions, PendingSightings, StorageHead,
+    },
     v02::types::syncing::{self, NumberedBlock, Syncing},
     websocket::types::WebsocketSenders,
     SyncState,
@@ -32,7 +35,7 @@ use starknet_gateway_types::{
 
 use std::sync::Arc;
 use std::{collections::HashMap, future::Future};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 
 use crate::state::l2::BlockChain;
 use crate::state::sync::class::{download_class, DownloadedClass};
@@ -50,6 +53,12 @@ pub async fn sync<Ethereum, SequencerClient, F1, F2, L1Sync, L2Sync>(
     mut l1_sync: L1Sync,
     l2_sync: L2Sync,
     pending_data: PendingData,
+    l1_head: watch::Sender<Option<EthereumStateUpdate>>,
+    pending_sightings: PendingSightings,
+    known_transactions: KnownTransactions,
+    empty_storage: EmptyStorage,
+    storage_head: StorageHead,
+    database_status_cache: DatabaseStatusCache,
     pending_poll_interval: Option<std::time::Duration>,
     block_validation_mode: l2::BlockValidationMode,
     websocket_txs: WebsocketSenders,
@@ -141,6 +150,9 @@ where
         tokio::select! {
             l1_event = rx_l1.recv() => match l1_event {
                 Some(update) => {
+                    // Publish the update before committing it, so that RPC can report L1
+                    // acceptance without waiting on storage.
+                    l1_head.send_replace(Some(update.clone()));
                     l1_update(&mut db_conn, &update).await?;
                     tracing::info!("L1 sync updated to block {}", update.block_number);
                 }
@@ -173,6 +185,13 @@ where
                     let block_hash = block.block_hash;
                     let storage_updates: usize = state_update.state_diff.storage_diffs.values().map(|storage_diffs| storage_diffs.len()).sum();
                     let update_t = std::time::Instant::now();
+                    // Recorded before the block is committed, so that a stored transaction is never
+                    // mistaken for an unknown one.
+                    for transaction in &block.transactions {
+                        known_transactions.insert(transaction.hash());
+                    }
+                    empty_storage.clear();
+                    storage_head.set(Some(block_hash));
                     l2_update(&mut db_conn, *block, tx_comm, ev_comm, *state_update)
                         .await
                         .with_context(|| format!("Update L2 state to {block_number}"))?;
@@ -225,10 +244,13 @@ where
                 }
                 Some(l2::Event::Reorg(reorg_tail)) => {
                     pending_data.clear().await;
+                    // Unknown until the next block, as reorgs don't report the new head's hash.
+                    storage_head.set(None);
 
                     l2_reorg(&mut db_conn, reorg_tail)
                         .await
                         .with_context(|| format!("Reorg L2 state to {reorg_tail:?}"))?;
+                    database_status_cache.invalidate_from(reorg_tail);
 
                     let new_head = match reorg_tail {
                         BlockNumber::GENESIS => None,
@@ -277,6 +299,16 @@ where
                         .await
                         .context("Downloading missing classes for pending block")?;
 
+                    // The pending block has no number of its own, but follows its parent.
+                    let parent = tokio::task::block_in_place(|| {
+                        let tx = db_conn.transaction()?;
+                        tx.block_id(block.parent_hash.into())
+                    })
+                    .context("Query pending block's parent from database")?;
+                    if let Some((parent_number, _)) = parent {
+                        pending_sightings.record(parent_number + 1, block.transactions.iter().map(|tx| tx.hash()));
+                    }
+
                     pending_data.set(block, state_update).await;
                     tracing::debug!("Updated pending data");
                 }
@@ -426,6 +458,12 @@ async fn l1_update(
             .upsert_l1_state(update)
             .context("Insert update")?;
 
+        if let Some(l1_head) = update.l1_block_number {
+            transaction
+                .update_l1_head(l1_head)
+                .context("Updating L1 head")?;
+        }
+
         let l2_hash = transaction
             .block_id(update.block_number.into())
             .context("Fetching block hash")?
@@ -433,10 +471,16 @@ async fn l1_update(
 
         if let Some(l2_hash) = l2_hash {
             if l2_hash == update.block_hash {
-                transaction
-                    .update_l1_l2_pointer(Some(update.block_number))
-                    .context("Updating L1-L2 pointer")?;
-                tracing::info!(block=?update.block_number, "Updated L1/L2 match");
+                // Updates are also emitted when only the L1 head advanced.
+                let pointer = transaction
+                    .l1_l2_pointer()
+                    .context("Querying L1-L2 pointer")?;
+                if pointer != Some(update.block_number) {
+                    transaction
+                        .update_l1_l2_pointer(Some(update.block_number))
+                        .context("Updating L1-L2 pointer")?;
+                    tracing::info!(block=?update.block_number, "Updated L1/L2 match");
+                }
             } else {
                 tracing::warn!(block_number=?update.block_number, L1=?update.block_hash, L2=?l2_hash, "L1/L2 block hash mismatch");
                 if let Some(matching_block_number) = transaction.l1_l2_pointer()? {
@@ -895,7 +939,13 @@ mod tests {
         StarknetVersion, StateCommitment, StorageCommitment,
     };
     use pathfinder_ethereum::EthereumStateUpdate;
-    use pathfinder_rpc::{websocket::types::WebsocketSenders, SyncState};
+    use pathfinder_rpc::{
+        transaction_status::{
+            DatabaseStatusCache, EmptyStorage, KnownTransactions, PendingSightings, StorageHead,
+        },
+        websocket::types::WebsocketSenders,
+        SyncState,
+    };
     use pathfinder_storage::Storage;
     use primitive_types::H160;
     use stark_hash::Felt;
@@ -981,11 +1031,15 @@ mod tests {
             block_number: BlockNumber::GENESIS,
             block_hash: BlockHash(*A),
             state_root: *STATE_COMMITMENT0,
+            l1_block_number: None,
+            l1_tx_hash: None,
         };
         pub static ref STATE_UPDATE_LOG1: pathfinder_ethereum::EthereumStateUpdate = pathfinder_ethereum::EthereumStateUpdate {
             block_number: BlockNumber::new_or_panic(1),
             block_hash: BlockHash(*B),
             state_root: *STATE_COMMITMENT1,
+            l1_block_number: None,
+            l1_tx_hash: None,
         };
         pub static ref BLOCK0: reply::Block = reply::Block {
             block_hash: BlockHash(*A),
@@ -1086,6 +1140,12 @@ mod tests {
                 l1,
                 l2_noop,
                 PendingData::default(),
+                tokio::sync::watch::channel(None).0,
+                PendingSightings::default(),
+                KnownTransactions::default(),
+                EmptyStorage::default(),
+                StorageHead::default(),
+                DatabaseStatusCache::default(),
                 None,
                 l2::BlockValidationMode::Strict,
                 websocket_txs.clone(),
@@ -1149,6 +1209,12 @@ mod tests {
             l1,
             l2_noop,
             PendingData::default(),
+            tokio::sync::watch::channel(None).0,
+            PendingSightings::default(),
+            KnownTransactions::default(),
+            EmptyStorage::default(),
+            StorageHead::default(),
+            DatabaseStatusCache::default(),
             None,
             l2::BlockValidationMode::Strict,
             websocket_txs,
@@ -1227,6 +1293,12 @@ mod tests {
                 l1_noop,
                 l2,
                 PendingData::default(),
+                tokio::sync::watch::channel(None).0,
+                PendingSightings::default(),
+                KnownTransactions::default(),
+                EmptyStorage::default(),
+                StorageHead::default(),
+                DatabaseStatusCache::default(),
                 None,
                 l2::BlockValidationMode::Strict,
                 websocket_txs.clone(),
@@ -1300,6 +1372,12 @@ mod tests {
                 l1_noop,
                 l2,
                 PendingData::default(),
+                tokio::sync::watch::channel(None).0,
+                PendingSightings::default(),
+                KnownTransactions::default(),
+                EmptyStorage::default(),
+                StorageHead::default(),
+                DatabaseStatusCache::default(),
                 None,
                 l2::BlockValidationMode::Strict,
                 websocket_txs,
@@ -1363,6 +1441,12 @@ mod tests {
             l1_noop,
             l2,
             PendingData::default(),
+            tokio::sync::watch::channel(None).0,
+            PendingSightings::default(),
+            KnownTransactions::default(),
+            EmptyStorage::default(),
+            StorageHead::default(),
+            DatabaseStatusCache::default(),
             None,
             l2::BlockValidationMode::Strict,
             websocket_txs,
@@ -1412,6 +1496,12 @@ mod tests {
             l1_noop,
             l2,
             PendingData::default(),
+            tokio::sync::watch::channel(None).0,
+            PendingSightings::default(),
+            KnownTransactions::default(),
+            EmptyStorage::default(),
+            StorageHead::default(),
+            DatabaseStatusCache::default(),
             None,
             l2::BlockValidationMode::Strict,
             websocket_txs,
@@ -1455,6 +1545,12 @@ mod tests {
             l1_noop,
             l2,
             PendingData::default(),
+            tokio::sync::watch::channel(None).0,
+            PendingSightings::default(),
+            KnownTransactions::default(),
+            EmptyStorage::default(),
+            StorageHead::default(),
+            DatabaseStatusCache::default(),
             None,
             l2::BlockValidationMode::Strict,
             websocket_txs,
@@ -10,6 +10,8 @@ use super::head_poll_interval;
 
 /// Syncs L1 state update logs. Emits [Ethereum state update](EthereumStateUpdate)
 /// which should be handled to update storage and respond to queries.
+///
+/// An update is emitted whenever the state or the L1 block it was read at changes.
 pub async fn sync<T>(
     tx_event: mpsc::Sender<EthereumStateUpdate>,
     ethereum: T,
@@ -1,10 +1,21 @@
 use crate::cairo::ext_py;
 use crate::gas_price;
+use crate::middleware::deprecation::DeprecationWarning;
+use crate::pathfinder::methods::{StatusDetail, TransactionStatusResponse};
+use crate::transaction_status::{
+    DatabaseStatusCache, EmptyStorage, GatewayCircuitBreaker, GatewayRetry, GatewayStatusCache,
+    KnownTransactions, PendingSightings, PendingStatusPolicy, ReceivedSightings,
+    ReorgRiskThresholds, SingleFlight, StatusWaits, StorageHead, WaitJitter,
+};
+use crate::websocket::types::BlockHeader;
 use crate::SyncState;
-use pathfinder_common::ChainId;
+use pathfinder_common::{ChainId, TransactionHash};
+use pathfinder_ethereum::EthereumStateUpdate;
 use pathfinder_storage::Storage;
 use starknet_gateway_types::pending::PendingData;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, watch, Semaphore};
 
 type SequencerClient = starknet_gateway_client::Client;
 
@@ -12,11 +23,69 @@ type SequencerClient = starknet_gateway_client::Client;
 pub struct RpcContext {
     pub storage: Storage,
     pub pending_data: Option<PendingData>,
+    /// How often sync polls the pending block, if it does.
+    pub pending_interval: Option<Duration>,
     pub sync_status: Arc<SyncState>,
     pub chain_id: ChainId,
     pub call_handle: Option<ext_py::Handle>,
     pub eth_gas_price: Option<gas_price::Cached>,
     pub sequencer: SequencerClient,
+    /// Queried instead of `sequencer` for transaction statuses, if set. This allows routing
+    /// status lookups to a different gateway, e.g. a test gateway for a staging node.
+    pub status_gateway: Option<SequencerClient>,
+    /// Queried in order for transaction statuses while the status gateway is unavailable.
+    pub fallback_gateways: Vec<SequencerClient>,
+    pub gateway_status_cache: GatewayStatusCache,
+    /// Statuses resolved from storage, which are reused until the L2 head changes.
+    pub database_status_cache: DatabaseStatusCache,
+    /// Upper bound on how long a gateway fallback query may take, so that a slow or
+    /// unreachable sequencer does not hold up the RPC request.
+    pub gateway_timeout: Duration,
+    /// Retry policy for gateway fallback queries which fail transiently. Retries are bounded by
+    /// `gateway_timeout`.
+    pub gateway_retry: GatewayRetry,
+    /// Makes gateway fallback queries fail fast while the gateways keep failing.
+    pub gateway_circuit_breaker: GatewayCircuitBreaker,
+    /// Query the gateway for the pending status of transactions before checking storage if
+    /// `pending_data` is not available. This costs a gateway query per status lookup.
+    pub gateway_pending_fallback: bool,
+    /// Query the gateway for the status of transactions which are unknown to this node. Should be
+    /// disabled for deployments without an upstream sequencer, in which case such transactions are
+    /// reported as not received.
+    pub gateway_status_fallback_enabled: bool,
+    pub status_single_flight:
+        SingleFlight<TransactionStatusResponse, (TransactionHash, StatusDetail)>,
+    /// The latest L1 state observed by sync, which may not have been committed to storage yet.
+    pub l1_head: watch::Receiver<Option<EthereumStateUpdate>>,
+    /// Transactions seen in recent pending blocks, as recorded by sync.
+    pub pending_sightings: PendingSightings,
+    /// Transactions known to storage, as recorded by sync. Lets status queries skip the database
+    /// for transactions it definitely does not know.
+    pub known_transactions: KnownTransactions,
+    /// Whether storage holds no blocks yet, as recorded by sync. Lets status queries on a freshly
+    /// initialized node skip the database.
+    pub empty_storage: EmptyStorage,
+    /// The latest block in storage, as recorded by sync. Lets status queries trust pending data
+    /// which is built on it without checking storage.
+    pub storage_head: StorageHead,
+    /// Transactions which the gateway reports as received, along with when they were first.
+    pub received_sightings: ReceivedSightings,
+    /// Determines the reorg risk reported for transactions accepted on L2.
+    pub reorg_risk: ReorgRiskThresholds,
+    /// Determines how transactions which are only part of the pending block are reported.
+    pub pending_status_policy: PendingStatusPolicy,
+    /// Notified of every new block, if the websocket API is served. Lets status queries which wait
+    /// for a status re-check it as soon as a new block is committed.
+    pub new_heads: Option<broadcast::Sender<BlockHeader>>,
+    /// Spreads out the status re-checks of status queries which wait for a status.
+    pub wait_jitter: WaitJitter,
+    /// Whether status queries may wait for a status, which admin methods can toggle at runtime.
+    pub status_waits: StatusWaits,
+    /// Bounds the number of transaction statuses resolved concurrently, so that a polling storm
+    /// cannot exhaust the database connection pool.
+    pub status_permits: Arc<Semaphore>,
+    /// Logs the first call of the deprecated `pathfinder_getTransactionStatus`.
+    pub status_method_deprecation: DeprecationWarning,
 }
 
 impl RpcContext {
@@ -31,9 +100,33 @@ impl RpcContext {
             sync_status,
             chain_id,
             pending_data: None,
+            pending_interval: None,
             call_handle: None,
             eth_gas_price: None,
             sequencer,
+            status_gateway: None,
+            fallback_gateways: Vec::new(),
+            gateway_status_cache: GatewayStatusCache::default(),
+            database_status_cache: DatabaseStatusCache::default(),
+            gateway_timeout: Duration::from_secs(5),
+            gateway_retry: GatewayRetry::default(),
+            gateway_circuit_breaker: GatewayCircuitBreaker::default(),
+            gateway_pending_fallback: false,
+            gateway_status_fallback_enabled: true,
+            status_single_flight: SingleFlight::default(),
+            l1_head: watch::channel(None).1,
+            pending_sightings: PendingSightings::default(),
+            known_transactions: KnownTransactions::default(),
+            empty_storage: EmptyStorage::default(),
+            storage_head: StorageHead::default(),
+            received_sightings: ReceivedSightings::default(),
+            reorg_risk: ReorgRiskThresholds::default(),
+            pending_status_policy: PendingStatusPolicy::default(),
+            new_heads: None,
+            wait_jitter: WaitJitter::default(),
+            status_waits: StatusWaits::default(),
+            status_permits: Arc::new(Semaphore::new(64)),
+            status_method_deprecation: DeprecationWarning::default(),
         }
     }
 
@@ -73,6 +166,13 @@ impl RpcContext {
         }
     }
 
+    pub fn with_pending_interval(self, pending_interval: Duration) -> Self {
+        Self {
+            pending_interval: Some(pending_interval),
+            ..self
+        }
+    }
+
     #[cfg(any(test, feature = "test-utils"))]
     pub async fn for_tests_with_pending() -> Self {
         // This is a bit silly with the arc in and out, but since its for tests the ergonomics of
@@ -82,6 +182,23 @@ impl RpcContext {
         context.with_pending_data(pending_data)
     }
 
+    /// Same as [RpcContext::for_tests_with_pending] but with the given transactions and receipts
+    /// in the pending block instead of the fixed ones.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub async fn for_tests_with_pending_transactions(
+        transactions: Vec<starknet_gateway_types::reply::transaction::Transaction>,
+        receipts: Vec<starknet_gateway_types::reply::transaction::Receipt>,
+    ) -> Self {
+        let context = Self::for_tests();
+        let pending_data = super::test_utils::create_pending_data_with_transactions(
+            context.storage.clone(),
+            transactions,
+            receipts,
+        )
+        .await;
+        context.with_pending_data(pending_data)
+    }
+
     pub fn with_call_handling(self, call_handle: ext_py::Handle) -> Self {
         Self {
             call_handle: Some(call_handle),
@@ -95,4 +212,246 @@ impl RpcContext {
             ..self
         }
     }
+
+    /// Sets how long a `NOT_RECEIVED` status reported by the gateway is cached for.
+    pub fn with_not_received_status_ttl(self, ttl: Duration) -> Self {
+        Self {
+            gateway_status_cache: GatewayStatusCache::new(ttl),
+            ..self
+        }
+    }
+
+    pub fn with_status_gateway(self, status_gateway: SequencerClient) -> Self {
+        Self {
+            status_gateway: Some(status_gateway),
+            ..self
+        }
+    }
+
+    /// Sets the gateways which are queried in order for transaction statuses while the status
+    /// gateway is unavailable.
+    pub fn with_fallback_gateways(self, fallback_gateways: Vec<SequencerClient>) -> Self {
+        Self {
+            fallback_gateways,
+            ..self
+        }
+    }
+
+    /// The gateway to query for transaction statuses.
+    pub(crate) fn status_gateway(&self) -> &SequencerClient {
+        self.status_gateway.as_ref().unwrap_or(&self.sequencer)
+    }
+
+    pub fn with_gateway_timeout(self, gateway_timeout: Duration) -> Self {
+        Self {
+            gateway_timeout,
+            ..self
+        }
+    }
+
+    /// Sets how many times a gateway fallback query is attempted in total, and the initial delay
+    /// between attempts which doubles with each retry.
+    pub fn with_gateway_retry(self, max_attempts: u32, base_delay: Duration) -> Self {
+        Self {
+            gateway_retry: GatewayRetry {
+                max_attempts,
+                base_delay,
+            },
+            ..self
+        }
+    }
+
+    /// Sets how many consecutive gateway failures within `window` stop gateway fallback queries,
+    /// and for how long they are stopped before the gateway is probed again.
+    pub fn with_gateway_circuit_breaker(
+        self,
+        failure_threshold: u32,
+        window: Duration,
+        cooldown: Duration,
+    ) -> Self {
+        Self {
+            gateway_circuit_breaker: GatewayCircuitBreaker::new(
+                failure_threshold,
+                window,
+                cooldown,
+            ),
+            ..self
+        }
+    }
+
+    pub fn with_new_heads(self, new_heads: broadcast::Sender<BlockHeader>) -> Self {
+        Self {
+            new_heads: Some(new_heads),
+            ..self
+        }
+    }
+
+    /// Sets the longest random delay before a waiting status query re-checks the status.
+    pub fn with_wait_jitter(self, max: Duration) -> Self {
+        Self {
+            wait_jitter: WaitJitter::new(max),
+            ..self
+        }
+    }
+
+    /// Sets whether status queries may wait for a status, until toggled by an admin method.
+    pub fn with_status_waits(self, enabled: bool) -> Self {
+        Self {
+            status_waits: StatusWaits::new(enabled),
+            ..self
+        }
+    }
+
+    pub fn with_l1_head(self, l1_head: watch::Receiver<Option<EthereumStateUpdate>>) -> Self {
+        Self { l1_head, ..self }
+    }
+
+    pub fn with_gateway_status_fallback(self, gateway_status_fallback_enabled: bool) -> Self {
+        Self {
+            gateway_status_fallback_enabled,
+            ..self
+        }
+    }
+
+    pub fn with_pending_sightings(self, pending_sightings: PendingSightings) -> Self {
+        Self {
+            pending_sightings,
+            ..self
+        }
+    }
+
+    pub fn with_database_status_cache(self, database_status_cache: DatabaseStatusCache) -> Self {
+        Self {
+            database_status_cache,
+            ..self
+        }
+    }
+
+    pub fn with_known_transactions(self, known_transactions: KnownTransactions) -> Self {
+        Self {
+            known_transactions,
+            ..self
+        }
+    }
+
+    pub fn with_empty_storage(self, empty_storage: EmptyStorage) -> Self {
+        Self {
+            empty_storage,
+            ..self
+        }
+    }
+
+    pub fn with_storage_head(self, storage_head: StorageHead) -> Self {
+        Self {
+            storage_head,
+            ..self
+        }
+    }
+
+    /// Sets the distance to the L2 head from which on a transaction accepted on L2 is reported as
+    /// having a low reorg risk.
+    pub fn with_reorg_risk_thresholds(self, low_risk_distance: u64) -> Self {
+        Self {
+            reorg_risk: ReorgRiskThresholds { low_risk_distance },
+            ..self
+        }
+    }
+
+    /// Sets whether transactions which are only part of the pending block are reported as pending
+    /// or as accepted on L2.
+    pub fn with_pending_status_policy(self, pending_status_policy: PendingStatusPolicy) -> Self {
+        Self {
+            pending_status_policy,
+            ..self
+        }
+    }
+
+    pub fn with_status_concurrency_limit(self, limit: usize) -> Self {
+        Self {
+            status_permits: Arc::new(Semaphore::new(limit)),
+            ..self
+        }
+    }
+
+    pub fn with_gateway_pending_fallback(self, gateway_pending_fallback: bool) -> Self {
+        Self {
+            gateway_pending_fallback,
+            ..self
+        }
+    }
+
+    /// Calls `callback` with the hash and new status of every transaction whose status this node
+    /// observes changing from now on, see [status_changes](crate::status_changes).
+    ///
+    /// The callback runs on a dedicated thread, so that a slow callback only delays the delivery
+    /// of later changes, rather than sync or the RPC server.
+    pub async fn on_transaction_status_change(
+        &self,
+        callback: crate::status_changes::StatusChangeCallback,
+    ) -> anyhow::Result<()> {
+        crate::status_changes::register(self.clone(), callback).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::{
+        felt_bytes, ContractAddress, Fee, TransactionHash, TransactionIndex, TransactionNonce,
+    };
+    use stark_hash::Felt;
+    use starknet_gateway_types::reply::transaction::{
+        ExecutionStatus, InvokeTransaction, InvokeTransactionV1, Receipt, Transaction,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn for_tests_with_pending_transactions() {
+        let transaction: Transaction = InvokeTransaction::V1(InvokeTransactionV1 {
+            calldata: vec![],
+            sender_address: ContractAddress::new_or_panic(felt_bytes!(b"custom sender")),
+            max_fee: Fee(Felt::ZERO),
+            signature: vec![],
+            nonce: TransactionNonce(Felt::ZERO),
+            transaction_hash: TransactionHash(felt_bytes!(b"custom pending tx")),
+        })
+        .into();
+        // A receipt without its transaction.
+        let receipt = Receipt {
+            actual_fee: None,
+            events: vec![],
+            execution_resources: None,
+            execution_status: ExecutionStatus::Reverted,
+            l1_to_l2_consumed_message: None,
+            l2_to_l1_messages: vec![],
+            revert_error: Some("Reverted".to_owned()),
+            transaction_hash: TransactionHash(felt_bytes!(b"receipt only tx")),
+            transaction_index: TransactionIndex::new_or_panic(0),
+        };
+
+        let context = RpcContext::for_tests_with_pending_transactions(
+            vec![transaction.clone()],
+            vec![receipt.clone()],
+        )
+        .await;
+
+        let pending = context.pending_data.as_ref().unwrap();
+        let block = pending.block().await.unwrap();
+        assert_eq!(block.transactions, vec![transaction]);
+        assert_eq!(block.transaction_receipts, vec![receipt]);
+
+        // The pending block is still linked to the latest block in storage.
+        let storage = context.storage.clone();
+        let latest = tokio::task::spawn_blocking(move || {
+            let mut db = storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.block_id(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+                .unwrap()
+                .1
+        })
+        .await
+        .unwrap();
+        assert_eq!(block.parent_hash, latest);
+    }
 }
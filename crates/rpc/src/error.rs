@@ -37,6 +37,20 @@ pub enum RpcError {
     ProofLimitExceeded { limit: u32, requested: u32 },
     #[error("Too many keys provided in a filter")]
     TooManyKeysInFilter { limit: usize, requested: usize },
+    #[error("Too many transaction hashes requested")]
+    TransactionHashLimitExceeded { limit: usize, requested: usize },
+    #[error("Too many blocks requested")]
+    BlockRangeLimitExceeded { limit: u64, requested: u64 },
+    #[error("The block range ends before it starts")]
+    InvalidBlockRange,
+    #[error("The gateway is temporarily unavailable, please retry later")]
+    GatewayUnavailable,
+    #[error("The database is busy, please retry later")]
+    DatabaseBusy,
+    #[error("The node's storage is inconsistent")]
+    InconsistentState,
+    #[error("No proof is available for the transaction's block")]
+    ProofUnavailable,
     #[error(transparent)]
     Internal(anyhow::Error),
 }
@@ -59,6 +73,13 @@ impl RpcError {
             RpcError::ContractError => 40,
             RpcError::InvalidContractClass => 50,
             RpcError::ProofLimitExceeded { .. } => 10000,
+            RpcError::TransactionHashLimitExceeded { .. } => 10001,
+            RpcError::GatewayUnavailable => 10002,
+            RpcError::DatabaseBusy => 10003,
+            RpcError::BlockRangeLimitExceeded { .. } => 10004,
+            RpcError::InconsistentState => 10005,
+            RpcError::ProofUnavailable => 10006,
+            RpcError::InvalidBlockRange => jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
             RpcError::Internal(_) => jsonrpsee::types::error::ErrorCode::InternalError.code(),
         }
     }
@@ -81,7 +102,8 @@ impl From<RpcError> for jsonrpsee::core::error::Error {
                 CallError::Custom(ErrorObject::owned(err.code(), err.to_string(), Some(data)))
                     .into()
             }
-            RpcError::TooManyKeysInFilter { limit, requested } => {
+            RpcError::TooManyKeysInFilter { limit, requested }
+            | RpcError::TransactionHashLimitExceeded { limit, requested } => {
                 #[derive(serde::Serialize)]
                 struct Data {
                     limit: usize,
@@ -93,6 +115,23 @@ impl From<RpcError> for jsonrpsee::core::error::Error {
                 CallError::Custom(ErrorObject::owned(err.code(), err.to_string(), Some(data)))
                     .into()
             }
+            RpcError::BlockRangeLimitExceeded { limit, requested } => {
+                #[derive(serde::Serialize)]
+                struct Data {
+                    limit: u64,
+                    requested: u64,
+                }
+
+                let data = Data { limit, requested };
+
+                CallError::Custom(ErrorObject::owned(err.code(), err.to_string(), Some(data)))
+                    .into()
+            }
+            RpcError::Internal(ref internal) => {
+                let data = internal.downcast_ref::<GatewayErrorDetail>();
+
+                CallError::Custom(ErrorObject::owned(err.code(), err.to_string(), data)).into()
+            }
             other => CallError::Custom(ErrorObject::owned(
                 other.code(),
                 other.to_string(),
@@ -103,6 +142,65 @@ impl From<RpcError> for jsonrpsee::core::error::Error {
     }
 }
 
+/// Details of a failed gateway query.
+///
+/// Attached as context to an [RpcError::Internal] error, these are included in the error data so
+/// that operators can tell what the gateway replied. Only the gateway's status and the start of its
+/// error reply are included, never the URL or any other detail of the request.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct GatewayErrorDetail {
+    /// The HTTP status of the gateway's reply, if known.
+    pub gateway_status: Option<u16>,
+    /// The gateway's error reply, truncated to [GatewayErrorDetail::MAX_BODY_LEN] bytes.
+    pub gateway_body: Option<String>,
+}
+
+impl GatewayErrorDetail {
+    pub const MAX_BODY_LEN: usize = 256;
+
+    pub fn new(error: &starknet_gateway_types::error::SequencerError) -> Self {
+        use starknet_gateway_types::error::SequencerError;
+
+        // Starknet errors, whether valid or not, are always replied with this status.
+        const STARKNET_ERROR_STATUS: u16 = 500;
+
+        match error {
+            SequencerError::StarknetError(e) => {
+                let mut body = serde_json::to_string(e).unwrap_or_else(|_| e.message.clone());
+                if body.len() > Self::MAX_BODY_LEN {
+                    let mut end = Self::MAX_BODY_LEN;
+                    while !body.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    body.truncate(end);
+                }
+
+                Self {
+                    gateway_status: Some(STARKNET_ERROR_STATUS),
+                    gateway_body: Some(body),
+                }
+            }
+            SequencerError::InvalidStarknetErrorVariant => Self {
+                gateway_status: Some(STARKNET_ERROR_STATUS),
+                gateway_body: None,
+            },
+            SequencerError::ReqwestError(e) => Self {
+                gateway_status: e.status().map(|status| status.as_u16()),
+                gateway_body: None,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for GatewayErrorDetail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.gateway_status {
+            Some(status) => write!(f, "Gateway replied with status {status}"),
+            None => f.write_str("Gateway query failed"),
+        }
+    }
+}
+
 /// Generates an enum subset of [RpcError] along with boilerplate for mapping the variants back to [RpcError].
 ///
 /// This is useful for RPC methods which only emit a few of the [RpcError] variants as this macro can be
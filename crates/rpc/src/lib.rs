@@ -8,8 +8,11 @@ pub mod metrics;
 pub mod middleware;
 mod module;
 mod pathfinder;
+pub mod status_changes;
+pub mod status_consistency;
 #[cfg(any(test, feature = "test-utils"))]
 pub mod test_client;
+pub mod transaction_status;
 pub mod v02;
 pub mod v03;
 pub mod websocket;
@@ -36,6 +39,7 @@ pub struct RpcServer {
     max_connections: u32,
     cors: Option<CorsLayer>,
     ws_senders: Option<WebsocketSenders>,
+    admin_methods: bool,
 }
 
 impl RpcServer {
@@ -47,6 +51,7 @@ impl RpcServer {
             max_connections: DEFAULT_MAX_CONNECTIONS,
             cors: None,
             ws_senders: None,
+            admin_methods: false,
         }
     }
 
@@ -64,6 +69,14 @@ impl RpcServer {
         }
     }
 
+    /// Also serves the pathfinder admin methods.
+    pub fn with_admin_methods(self) -> Self {
+        Self {
+            admin_methods: true,
+            ..self
+        }
+    }
+
     pub fn with_max_connections(mut self, max_connections: u32) -> Self {
         self.max_connections = max_connections;
         self
@@ -98,6 +111,8 @@ impl RpcServer {
 					}
                     middleware::versioning::prefix_rpc_method_names_with_version(result, TEN_MB).await
                 })
+                .layer(middleware::batch_connection::BatchConnectionLayer)
+                .layer(middleware::deprecation::DeprecationLayer)
             )
             .build(self.addr)
             .await
@@ -122,10 +137,18 @@ Hint: If you are looking to run two instances of pathfinder, you must configure
             })?;
         let local_addr = server.local_addr()?;
 
-        let module = crate::module::Module::new(self.context);
+        let context = match &self.ws_senders {
+            Some(ws_senders) => self.context.with_new_heads(ws_senders.new_head.0.clone()),
+            None => self.context,
+        };
+        let module = crate::module::Module::new(context);
         let module = v02::register_methods(module)?;
         let module = v03::register_methods(module)?;
         let module = pathfinder::register_methods(module)?;
+        let module = match self.admin_methods {
+            true => pathfinder::register_admin_methods(module)?,
+            false => module,
+        };
         let module = match &self.ws_senders {
             Some(ws_senders) => websocket::register_subscriptions(module, ws_senders.clone())?,
             None => module,
@@ -178,8 +201,8 @@ pub mod test_utils {
             state_update::{ReplacedClass, StorageDiff},
             transaction::{
                 execution_resources::{BuiltinInstanceCounter, EmptyBuiltinInstanceCounter},
-                DeployTransaction, EntryPointType, ExecutionResources, InvokeTransaction,
-                InvokeTransactionV0, Receipt, Transaction,
+                DeployTransaction, EntryPointType, ExecutionResources, ExecutionStatus,
+                InvokeTransaction, InvokeTransactionV0, Receipt, Transaction,
             },
         },
     };
@@ -404,8 +427,10 @@ pub mod test_utils {
                 n_memory_holes: 0,
                 n_steps: 0,
             }),
+            execution_status: ExecutionStatus::Succeeded,
             l1_to_l2_consumed_message: None,
             l2_to_l1_messages: vec![],
+            revert_error: None,
             transaction_hash: txn0_hash,
             transaction_index: TransactionIndex::new_or_panic(0),
         };
@@ -485,20 +510,6 @@ pub mod test_utils {
     /// i.e. the pending block's parent hash will be the latest block's hash from storage,
     /// and similarly for the pending state diffs state root.
     pub async fn create_pending_data(storage: Storage) -> PendingData {
-        use pathfinder_common::StorageValue;
-
-        let storage2 = storage.clone();
-        let latest = tokio::task::spawn_blocking(move || {
-            let mut db = storage2.connection().unwrap();
-            let tx = db.transaction().unwrap();
-
-            tx.block_header(BlockId::Latest)
-                .unwrap()
-                .expect("Storage should contain a latest block")
-        })
-        .await
-        .unwrap();
-
         let transactions: Vec<Transaction> = vec![
             InvokeTransaction::V0(InvokeTransactionV0 {
                 calldata: vec![],
@@ -550,8 +561,10 @@ pub mod test_utils {
                     n_memory_holes: 0,
                     n_steps: 0,
                 }),
+                execution_status: ExecutionStatus::Succeeded,
                 l1_to_l2_consumed_message: None,
                 l2_to_l1_messages: vec![],
+                revert_error: None,
                 transaction_hash: transactions[0].hash(),
                 transaction_index: TransactionIndex::new_or_panic(0),
             },
@@ -565,13 +578,39 @@ pub mod test_utils {
                     n_memory_holes: 0,
                     n_steps: 0,
                 }),
+                execution_status: ExecutionStatus::Succeeded,
                 l1_to_l2_consumed_message: None,
                 l2_to_l1_messages: vec![],
+                revert_error: None,
                 transaction_hash: transactions[1].hash(),
                 transaction_index: TransactionIndex::new_or_panic(1),
             },
         ];
 
+        create_pending_data_with_transactions(storage, transactions, transaction_receipts).await
+    }
+
+    /// Same as [create_pending_data] but with the given transactions and receipts in the pending
+    /// block, which need not match up e.g. to have a receipt without its transaction.
+    pub async fn create_pending_data_with_transactions(
+        storage: Storage,
+        transactions: Vec<Transaction>,
+        transaction_receipts: Vec<Receipt>,
+    ) -> PendingData {
+        use pathfinder_common::StorageValue;
+
+        let storage2 = storage.clone();
+        let latest = tokio::task::spawn_blocking(move || {
+            let mut db = storage2.connection().unwrap();
+            let tx = db.transaction().unwrap();
+
+            tx.block_header(BlockId::Latest)
+                .unwrap()
+                .expect("Storage should contain a latest block")
+        })
+        .await
+        .unwrap();
+
         let block = starknet_gateway_types::reply::PendingBlock {
             gas_price: GasPrice::from_be_slice(b"gas price").unwrap(),
             parent_hash: latest.hash,
@@ -1,2 +1,4 @@
+pub(crate) mod batch_connection;
 pub mod cors;
+pub(crate) mod deprecation;
 pub mod versioning;
@@ -0,0 +1,96 @@
+//! Middleware which lets the calls of a JSON-RPC batch share a single database connection, so that
+//! a large batch of status queries does not drain the connection pool.
+//!
+//! Batch requests are marked by [versioning](super::versioning), and each is served within the
+//! scope of its own [BatchConnection]. The connection is checked out by the first call which needs
+//! it, and returned to the pool once the whole batch completes.
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use anyhow::Context as _;
+use futures::future::Either;
+use hyper::{Body, Request};
+use pathfinder_storage::{Connection, Storage};
+use tokio::task::futures::TaskLocalFuture;
+
+tokio::task_local! {
+    static BATCH_CONNECTION: BatchConnection;
+}
+
+/// Marks a request as a JSON-RPC batch.
+#[derive(Clone, Copy)]
+pub(crate) struct BatchRequest;
+
+/// A database connection shared by the calls of a JSON-RPC batch.
+#[derive(Clone, Default)]
+pub(crate) struct BatchConnection(Arc<Mutex<Option<Connection>>>);
+
+impl BatchConnection {
+    /// Returns the connection of the batch currently being served, if any.
+    pub(crate) fn current() -> Option<Self> {
+        BATCH_CONNECTION.try_with(Clone::clone).ok()
+    }
+
+    /// Runs `f` within the scope of this batch.
+    pub(crate) fn scope<F: std::future::Future>(self, f: F) -> TaskLocalFuture<Self, F> {
+        BATCH_CONNECTION.scope(self, f)
+    }
+
+    /// Runs `f` with the batch's connection, checking it out of `storage` if this is the first
+    /// call to use it. Calls are serialized, as they share the connection.
+    pub(crate) fn with_connection<T>(
+        &self,
+        storage: &Storage,
+        f: impl FnOnce(&mut Connection) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let mut connection = self.0.lock().unwrap_or_else(|e| e.into_inner());
+
+        if connection.is_none() {
+            let checked_out = storage
+                .connection()
+                .context("Opening database connection")?;
+            *connection = Some(checked_out);
+        }
+
+        let connection = connection
+            .as_mut()
+            .expect("Connection was checked out above");
+        f(connection)
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct BatchConnectionLayer;
+
+impl<S> tower::Layer<S> for BatchConnectionLayer {
+    type Service = BatchConnectionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BatchConnectionService(inner)
+    }
+}
+
+/// Serves requests marked as [BatchRequest] within the scope of a new [BatchConnection], and all
+/// other requests as is.
+#[derive(Clone)]
+pub(crate) struct BatchConnectionService<S>(S);
+
+impl<S> tower::Service<Request<Body>> for BatchConnectionService<S>
+where
+    S: tower::Service<Request<Body>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<TaskLocalFuture<BatchConnection, S::Future>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        match request.extensions().get::<BatchRequest>() {
+            Some(_) => Either::Left(BatchConnection::default().scope(self.0.call(request))),
+            None => Either::Right(self.0.call(request)),
+        }
+    }
+}
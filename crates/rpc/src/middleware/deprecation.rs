@@ -0,0 +1,133 @@
+//! Signals the deprecation of RPC methods which are still served, so that their usage can be
+//! tracked before removal.
+//!
+//! Requests calling a [deprecated](DEPRECATED_METHODS) method are marked by
+//! [versioning](super::versioning), and their responses carry a `Deprecation` header.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::future::{Either, MapOk};
+use futures::TryFutureExt;
+use hyper::{Body, Request, Response};
+
+/// Methods which are deprecated in favour of their spec-standard counterparts, by their
+/// unversioned name.
+pub(crate) const DEPRECATED_METHODS: [&str; 1] = ["pathfinder_getTransactionStatus"];
+
+/// Marks a request as calling a deprecated method.
+#[derive(Clone, Copy)]
+pub(crate) struct DeprecatedRequest;
+
+/// Logs a deprecated method's first call only, so that its usage shows up without flooding the
+/// logs.
+#[derive(Clone, Default)]
+pub struct DeprecationWarning(Arc<AtomicBool>);
+
+impl DeprecationWarning {
+    /// Logs that `method` is deprecated in favour of `successor`, unless this was already logged.
+    /// Returns whether it was logged.
+    pub(crate) fn warn_once(&self, method: &str, successor: &str) -> bool {
+        if self.0.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+
+        tracing::warn!(%method, %successor, "Deprecated RPC method called, it will be removed in a future release");
+        true
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct DeprecationLayer;
+
+impl<S> tower::Layer<S> for DeprecationLayer {
+    type Service = DeprecationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DeprecationService(inner)
+    }
+}
+
+/// Adds the `Deprecation` header to the responses of requests marked as [DeprecatedRequest].
+#[derive(Clone)]
+pub(crate) struct DeprecationService<S>(S);
+
+impl<S> tower::Service<Request<Body>> for DeprecationService<S>
+where
+    S: tower::Service<Request<Body>, Response = Response<Body>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<MapOk<S::Future, fn(Response<Body>) -> Response<Body>>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        match request.extensions().get::<DeprecatedRequest>() {
+            Some(_) => Either::Left(self.0.call(request).map_ok(with_deprecation_header as _)),
+            None => Either::Right(self.0.call(request)),
+        }
+    }
+}
+
+fn with_deprecation_header(mut response: Response<Body>) -> Response<Body> {
+    response
+        .headers_mut()
+        .insert("deprecation", http::HeaderValue::from_static("true"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::middleware::versioning::test_utils::paths;
+    use crate::{RpcContext, RpcServer};
+
+    #[test]
+    fn warns_once() {
+        let warning = DeprecationWarning::default();
+
+        assert!(warning.warn_once("deprecated", "successor"));
+        assert!(!warning.warn_once("deprecated", "successor"));
+        // Clones share whether the warning was logged.
+        assert!(!warning.clone().warn_once("deprecated", "successor"));
+    }
+
+    #[tokio::test]
+    async fn deprecated_method_response_has_header() {
+        let context = RpcContext::for_tests();
+        let (_server_handle, address) = RpcServer::new("127.0.0.1:0".parse().unwrap(), context)
+            .run()
+            .await
+            .unwrap();
+
+        let deprecation = |method: &'static str| async move {
+            let response = reqwest::Client::new()
+                .post(format!("http://{address}{}", paths::PATHFINDER[0]))
+                .json(&json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "method": method,
+                    "params": { "transaction_hash": "0x1" },
+                }))
+                .send()
+                .await
+                .unwrap();
+
+            response.headers().get("deprecation").cloned()
+        };
+
+        assert_eq!(
+            deprecation("pathfinder_getTransactionStatus").await,
+            Some(http::HeaderValue::from_static("true"))
+        );
+        assert_eq!(
+            deprecation("pathfinder_getTransactionStatusCode").await,
+            None
+        );
+    }
+}
@@ -8,6 +8,9 @@ use jsonrpsee::types::error::{reject_too_big_request, ErrorCode, ErrorResponse};
 use jsonrpsee::types::Id;
 use tower::BoxError;
 
+use super::batch_connection::BatchRequest;
+use super::deprecation::{DeprecatedRequest, DEPRECATED_METHODS};
+
 #[derive(thiserror::Error, Debug)]
 enum VersioningError {
     #[error("Invalid path")]
@@ -52,7 +55,7 @@ pub(crate) async fn prefix_rpc_method_names_with_version(
     };
 
     // Retain the parts to then later recreate the request
-    let (parts, body) = request.into_parts();
+    let (mut parts, body) = request.into_parts();
 
     let (body, is_single) = match read_body(&parts.headers, body, max_request_body_size).await {
         Ok(x) => x,
@@ -69,9 +72,11 @@ pub(crate) async fn prefix_rpc_method_names_with_version(
         }
     };
 
+    let mut deprecated = false;
     let new_body = if is_single {
         match serde_json::from_slice::<jsonrpsee::types::Request<'_>>(&body) {
             Ok(mut request) => {
+                deprecated = is_deprecated(&request);
                 prefix_method(&mut request, prefixes);
                 serde_json::to_vec(&request).map(Option::Some)
             }
@@ -87,6 +92,7 @@ pub(crate) async fn prefix_rpc_method_names_with_version(
     } else {
         match serde_json::from_slice::<Vec<jsonrpsee::types::Request<'_>>>(&body) {
             Ok(mut batch) => {
+                deprecated = batch.iter().any(is_deprecated);
                 batch
                     .iter_mut()
                     .for_each(|request| prefix_method(request, prefixes));
@@ -126,6 +132,16 @@ pub(crate) async fn prefix_rpc_method_names_with_version(
         Err(_) => return Err(BoxError::from(VersioningError::Internal)),
     };
 
+    // Lets the batch's calls share a database connection.
+    if !is_single {
+        parts.extensions.insert(BatchRequest);
+    }
+
+    // Lets the response signal that the method is deprecated.
+    if deprecated {
+        parts.extensions.insert(DeprecatedRequest);
+    }
+
     let request: Request<Body> = Request::from_parts(parts, body.into());
 
     Ok(request)
@@ -143,6 +159,10 @@ pub(crate) fn try_map_errors_to_responses(
     }
 }
 
+fn is_deprecated(request: &jsonrpsee::types::Request<'_>) -> bool {
+    DEPRECATED_METHODS.contains(&request.method.as_ref())
+}
+
 fn prefix_method(request: &mut jsonrpsee::types::Request<'_>, prefixes: &[(&str, &str)]) {
     for (old, new) in prefixes {
         if request.method.starts_with(old) {
@@ -240,8 +260,24 @@ pub mod test_utils {
         ];
         pub const COMMON_FOR_ALL: [&str; 2] =
             ["pathfinder_getProof", "pathfinder_getTransactionStatus"];
-        pub const V03_ONLY: [&str; 1] = ["starknet_simulateTransaction"];
-        pub const PATHFINDER_ONLY: [&str; 1] = ["pathfinder_version"];
+        pub const V03_ONLY: [&str; 2] = [
+            "starknet_getTransactionStatus",
+            "starknet_simulateTransaction",
+        ];
+        pub const PATHFINDER_ONLY: [&str; 12] = [
+            "pathfinder_version",
+            "pathfinder_getTransactionStatusCode",
+            "pathfinder_getTransactionStatusDetails",
+            "pathfinder_getTransactionStatuses",
+            "pathfinder_getTransactionStatusesInBlockRange",
+            "pathfinder_getTransactionStatusByBlockHashAndIndex",
+            "pathfinder_transactionStatusByMessageHash",
+            "pathfinder_latestTransactionStatusForAddress",
+            "pathfinder_reconcileTransactionStatus",
+            "pathfinder_explainTransactionStatus",
+            "pathfinder_transactionIsKnown",
+            "pathfinder_blockTransactionStatusSummary",
+        ];
     }
 
     pub mod paths {
@@ -317,6 +353,38 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn admin_methods_are_opt_in() {
+        for admin_methods in [false, true] {
+            let server = RpcServer::new("127.0.0.1:0".parse().unwrap(), RpcContext::for_tests());
+            let server = match admin_methods {
+                true => server.with_admin_methods(),
+                false => server,
+            };
+            let (_server_handle, address) = server.run().await.unwrap();
+
+            let client = TestClientBuilder::default()
+                .address(address)
+                .endpoint(paths::PATHFINDER[0].into())
+                .build()
+                .unwrap();
+            let res = client
+                .request::<serde_json::Value>(
+                    "pathfinder_admin_prewarmTransactionStatuses",
+                    json!({ "transaction_hashes": [] }),
+                )
+                .await;
+
+            match res {
+                Err(Error::Call(CallError::Custom(e))) if e.code() == METHOD_NOT_FOUND_CODE => {
+                    assert!(!admin_methods, "Admin method not served when enabled")
+                }
+                Ok(_) => assert!(admin_methods, "Admin method served when not enabled"),
+                Err(e) => panic!("Unexpected error: {e}"),
+            }
+        }
+    }
+
     #[tokio::test]
     async fn invalid_path() {
         let context = RpcContext::for_tests();
@@ -13,6 +13,78 @@ pub fn register_methods(module: Module) -> anyhow::Result<Module> {
         .register_method(
             "v0.1_pathfinder_getTransactionStatus",
             methods::get_transaction_status,
+        )?
+        .register_method(
+            "v0.1_pathfinder_getTransactionStatusCode",
+            methods::get_transaction_status_code,
+        )?
+        .register_method(
+            "v0.1_pathfinder_getTransactionStatusDetails",
+            methods::get_transaction_status_details,
+        )?
+        .register_method(
+            "v0.1_pathfinder_getTransactionStatuses",
+            methods::get_transaction_statuses,
+        )?
+        .register_method(
+            "v0.1_pathfinder_getTransactionStatusesInBlockRange",
+            methods::get_transaction_statuses_in_block_range,
+        )?
+        .register_method(
+            "v0.1_pathfinder_getTransactionStatusByBlockHashAndIndex",
+            methods::get_transaction_status_by_block_hash_and_index,
+        )?
+        .register_method(
+            "v0.1_pathfinder_transactionStatusByMessageHash",
+            methods::transaction_status_by_message_hash,
+        )?
+        .register_method(
+            "v0.1_pathfinder_latestTransactionStatusForAddress",
+            methods::latest_transaction_status_for_address,
+        )?
+        .register_method(
+            "v0.1_pathfinder_reconcileTransactionStatus",
+            methods::reconcile_transaction_status,
+        )?
+        .register_method(
+            "v0.1_pathfinder_explainTransactionStatus",
+            methods::explain_transaction_status,
+        )?
+        .register_method(
+            "v0.1_pathfinder_transactionIsKnown",
+            methods::transaction_is_known,
+        )?
+        .register_method(
+            "v0.1_pathfinder_blockTransactionStatusSummary",
+            methods::block_transaction_status_summary,
+        )?;
+
+    Ok(module)
+}
+
+/// Registers the methods for the pathfinder admin RPC API, which are only served if explicitly
+/// enabled as they let callers drive the node's gateway and storage load.
+pub fn register_admin_methods(module: Module) -> anyhow::Result<Module> {
+    let module = module
+        .register_method(
+            "v0.1_pathfinder_admin_prewarmTransactionStatuses",
+            methods::prewarm_transaction_statuses,
+        )?
+        .register_method(
+            "v0.1_pathfinder_admin_stuckReceivedTransactions",
+            methods::stuck_received_transactions,
+        )?
+        .register_method(
+            "v0.1_pathfinder_admin_recentRejectedTransactions",
+            methods::recent_rejected_transactions,
+        )?
+        .register_method(
+            "v0.1_pathfinder_admin_setStatusWaits",
+            methods::set_status_waits,
+        )?
+        .register_method(
+            "v0.1_pathfinder_admin_getTransactionStatusDebug",
+            methods::get_transaction_status_debug,
         )?;
 
     Ok(module)
@@ -2,4 +2,14 @@ mod get_proof;
 mod get_transaction_status;
 
 pub(crate) use get_proof::get_proof;
-pub(crate) use get_transaction_status::get_transaction_status;
+pub(crate) use get_transaction_status::{
+    block_transaction_status_summary, explain_transaction_status, get_transaction_status,
+    get_transaction_status_by_block_hash_and_index, get_transaction_status_code,
+    get_transaction_status_debug, get_transaction_status_details, get_transaction_statuses,
+    get_transaction_statuses_in_block_range, latest_transaction_status_for_address,
+    prewarm_transaction_statuses, recent_rejected_transactions, reconcile_transaction_status,
+    resolve_status, resolve_status_details, set_status_waits, stuck_received_transactions,
+    transaction_is_known, transaction_status_by_message_hash, with_db_tx, ExecutionStatus,
+    GetGatewayTransactionError, ReorgRisk, StatusDetail, TransactionStatus,
+    TransactionStatusResponse,
+};
@@ -50,7 +50,7 @@ struct PathWrapper {
 }
 
 /// Wrapper around [`Vec<TrieNode>`] as we don't control [TrieNode] in this crate.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ProofNodes(Vec<TrieNode>);
 
 impl Serialize for ProofNodes {
@@ -106,7 +106,7 @@ impl Serialize for ProofNodes {
 }
 
 /// Holds the data and proofs for a specific contract.
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct ContractData {
     /// Required to verify the contract state hash to contract root calculation.
     class_hash: ClassHash,
@@ -124,7 +124,7 @@ pub struct ContractData {
 }
 
 /// Holds the membership/non-membership of a contract and its associated contract contract if the contract exists.
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 #[skip_serializing_none]
 pub struct GetProofOutput {
     /// The global state commitment for Starknet 0.11.0 blocks onwards, if absent the hash
@@ -174,85 +174,94 @@ pub async fn get_proof(
 
         let tx = db.transaction().context("Creating database transaction")?;
 
-        // Use internal error to indicate that the process of querying for a particular block failed,
-        // which is not the same as being sure that the block is not in the db.
-        let (storage_commitment, class_commitment) = tx
-            .block_header(block_id)
-            .context("Fetching block header")?
-            .map(|header| (header.storage_commitment, header.class_commitment))
-            .ok_or(GetProofError::BlockNotFound)?;
-
-        let (state_commitment, class_commitment) = if class_commitment == ClassCommitment::ZERO {
-            (None, None)
-        } else {
-            (
-                Some(StateCommitment::calculate(
-                    storage_commitment,
-                    class_commitment,
-                )),
-                Some(class_commitment),
-            )
-        };
+        generate_proof(&tx, block_id, input.contract_address, &input.keys)
+    });
 
-        let mut storage_commitment_tree =
-            StorageCommitmentTree::load(&tx, storage_commitment).context("Loading storage trie")?;
-
-        // Generate a proof for this contract. If the contract does not exist, this will
-        // be a "non membership" proof.
-        let contract_proof = storage_commitment_tree.get_proof(&input.contract_address)?;
-        let contract_proof = ProofNodes(contract_proof);
-
-        let contract_state_hash = match storage_commitment_tree.get(input.contract_address)? {
-            Some(contract_state_hash) => contract_state_hash,
-            None => {
-                // Contract not found: return the proof of non membership that we generated earlier.
-                return Ok(GetProofOutput {
-                    state_commitment,
-                    class_commitment,
-                    contract_proof,
-                    contract_data: None,
-                });
-            }
-        };
+    jh.await.context("Database read panic or shutting down")?
+}
 
-        let (contract_state_root, class_hash, nonce) = tx
-            .contract_state(contract_state_hash)
-            .context("Get contract state root and nonce")?
-            // Root and nonce should not be None at this stage since we have a valid block and non-zero contract state_hash.
-            .ok_or_else(|| -> GetProofError {
-                anyhow::anyhow!(
-                    "Root or nonce missing for state_hash={}",
-                    contract_state_hash
-                )
-                .into()
-            })?;
-
-        let contract_state_tree = ContractsStorageTree::load(&tx, contract_state_root);
-
-        let storage_proofs = input
-            .keys
-            .iter()
-            .map(|k| contract_state_tree.get_proof(k.view_bits()).map(ProofNodes))
-            .collect::<anyhow::Result<Vec<_>>>()
-            .context("Get proof from contract state treee")?;
-
-        let contract_data = ContractData {
-            class_hash,
-            nonce,
-            root: contract_state_root,
-            contract_state_hash_version: Felt::ZERO, // Currently, this is defined as 0. Might change in the future.
-            storage_proofs,
-        };
+/// Generates the proof of the contract's storage `keys` as of the block, see [get_proof].
+pub(crate) fn generate_proof(
+    tx: &pathfinder_storage::Transaction<'_>,
+    block_id: pathfinder_storage::BlockId,
+    contract_address: ContractAddress,
+    keys: &[StorageAddress],
+) -> Result<GetProofOutput, GetProofError> {
+    // Use internal error to indicate that the process of querying for a particular block failed,
+    // which is not the same as being sure that the block is not in the db.
+    let (storage_commitment, class_commitment) = tx
+        .block_header(block_id)
+        .context("Fetching block header")?
+        .map(|header| (header.storage_commitment, header.class_commitment))
+        .ok_or(GetProofError::BlockNotFound)?;
+
+    let (state_commitment, class_commitment) = if class_commitment == ClassCommitment::ZERO {
+        (None, None)
+    } else {
+        (
+            Some(StateCommitment::calculate(
+                storage_commitment,
+                class_commitment,
+            )),
+            Some(class_commitment),
+        )
+    };
 
-        Ok(GetProofOutput {
-            state_commitment,
-            class_commitment,
-            contract_proof,
-            contract_data: Some(contract_data),
-        })
-    });
+    let mut storage_commitment_tree =
+        StorageCommitmentTree::load(tx, storage_commitment).context("Loading storage trie")?;
+
+    // Generate a proof for this contract. If the contract does not exist, this will
+    // be a "non membership" proof.
+    let contract_proof = storage_commitment_tree.get_proof(&contract_address)?;
+    let contract_proof = ProofNodes(contract_proof);
+
+    let contract_state_hash = match storage_commitment_tree.get(contract_address)? {
+        Some(contract_state_hash) => contract_state_hash,
+        None => {
+            // Contract not found: return the proof of non membership that we generated earlier.
+            return Ok(GetProofOutput {
+                state_commitment,
+                class_commitment,
+                contract_proof,
+                contract_data: None,
+            });
+        }
+    };
 
-    jh.await.context("Database read panic or shutting down")?
+    let (contract_state_root, class_hash, nonce) = tx
+        .contract_state(contract_state_hash)
+        .context("Get contract state root and nonce")?
+        // Root and nonce should not be None at this stage since we have a valid block and non-zero contract state_hash.
+        .ok_or_else(|| -> GetProofError {
+            anyhow::anyhow!(
+                "Root or nonce missing for state_hash={}",
+                contract_state_hash
+            )
+            .into()
+        })?;
+
+    let contract_state_tree = ContractsStorageTree::load(tx, contract_state_root);
+
+    let storage_proofs = keys
+        .iter()
+        .map(|k| contract_state_tree.get_proof(k.view_bits()).map(ProofNodes))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .context("Get proof from contract state treee")?;
+
+    let contract_data = ContractData {
+        class_hash,
+        nonce,
+        root: contract_state_root,
+        contract_state_hash_version: Felt::ZERO, // Currently, this is defined as 0. Might change in the future.
+        storage_proofs,
+    };
+
+    Ok(GetProofOutput {
+        state_commitment,
+        class_commitment,
+        contract_proof,
+        contract_data: Some(contract_data),
+    })
 }
 
 #[cfg(test)]
@@ -1,171 +1,8289 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use anyhow::Context;
-use pathfinder_common::TransactionHash;
-use starknet_gateway_types::pending::PendingData;
+use pathfinder_common::{
+    BlockHash, BlockNumber, BlockTimestamp, ClassHash, ContractAddress, Fee, SequencerAddress,
+    StateCommitment, StorageAddress, TransactionHash, TransactionIndex, TransactionNonce,
+    TransactionVersion,
+};
+use pathfinder_ethereum::EthereumStateUpdate;
+use pathfinder_serde::TransactionVersionAsHexStr;
+use pathfinder_storage::{ConnectionKind, Storage};
+use primitive_types::H256;
+use starknet_gateway_client::GatewayApi;
+use starknet_gateway_types::error::SequencerError;
+use starknet_gateway_types::reply::transaction::{
+    DeclareTransaction, ExecutionStatus as GatewayExecutionStatus, Failure as GatewayFailure,
+    L2ToL1Message, Receipt, Transaction as GatewayTransaction,
+};
+use starknet_gateway_types::reply::{PendingBlock, Transaction as GatewayReply};
+use tokio::sync::broadcast::error::RecvError;
 
+use super::get_proof::{generate_proof, GetProofError, GetProofOutput};
 use crate::context::RpcContext;
+use crate::error::GatewayErrorDetail;
+use crate::middleware::batch_connection::BatchConnection;
+use crate::transaction_status::{
+    DatabaseStatusCache, GatewayRetry, GatewayStatusCache, L1Head, ReorgRiskThresholds,
+    SingleFlight,
+};
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
 pub struct GetGatewayTransactionInput {
+    #[serde(deserialize_with = "lenient_transaction_hash")]
     transaction_hash: TransactionHash,
+    /// Additionally return the times at which the transaction entered each status. Only used by
+    /// [get_transaction_status].
+    #[serde(default)]
+    include_history: bool,
+    /// The transaction's sender and nonce. If given, a transaction which is not received is
+    /// checked for having been superseded by another accepted transaction with the same sender
+    /// and nonce. Only used by [get_transaction_status].
+    #[serde(default)]
+    check_superseded: Option<SenderNonce>,
+    /// Resolve whether an accepted transaction was accepted on L1 as of this L1 block, instead of
+    /// as of now. Only used by [get_transaction_status].
+    #[serde(default)]
+    at_l1_block: Option<u64>,
+    /// Resolve an accepted transaction as `NOT_RECEIVED` if its block is newer than this L2
+    /// block, as it was not accepted yet as of it. A transaction which is not in a block yet is
+    /// `NOT_RECEIVED` as well, unless this L2 block is still to come. Only used by
+    /// [get_transaction_status].
+    #[serde(default)]
+    at_l2_block: Option<BlockNumber>,
+    /// Confirm a transaction which storage reports as accepted on L2, but which is still at risk
+    /// of a reorg, against the gateway. The gateway's status is returned if the two disagree.
+    /// Only used by [get_transaction_status].
+    #[serde(default)]
+    verify: bool,
+    /// Return the status in lowercase e.g. `accepted_on_l1`, instead of the spec's uppercase. Only
+    /// used by [get_transaction_status], for a status returned without further details.
+    #[serde(default)]
+    lowercase: bool,
+    /// Wait until the transaction reaches at least this status, e.g. `ACCEPTED_ON_L2`, or a final
+    /// status from which it cannot be reached. Only used by [get_transaction_status].
+    #[serde(default)]
+    wait_for: Option<TransactionStatus>,
+    /// How long to wait for `wait_for` at most, after which the current status is returned.
+    /// Defaults to, and is capped at, [MAX_WAIT].
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Resolve the status from the pending data and storage only, without any network I/O. The
+    /// gateway fallbacks and `verify` are skipped, so unknown transactions are `NOT_RECEIVED`.
+    /// Only used by [get_transaction_status].
+    #[serde(default)]
+    local_only: bool,
+    /// Attach a proof that the block of a transaction accepted on L1 is part of the state
+    /// committed on L1, see [BlockHashProof]. Only used by [get_transaction_status_details].
+    #[serde(default)]
+    include_proof: bool,
+    /// Resolve the status from storage and the gateway only, without checking the pending block,
+    /// e.g. for archive scans which never care about pending transactions. Only used by
+    /// [get_transaction_status].
+    #[serde(default)]
+    skip_pending: bool,
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SenderNonce {
+    sender_address: ContractAddress,
+    nonce: TransactionNonce,
+}
+
+/// Deserializes a transaction hash as copied from a block explorer, which may have an uppercase
+/// `0X` prefix. Hex digits of either case and hashes without their leading zeros are accepted by
+/// [TransactionHash] already.
+fn lenient_transaction_hash<'de, D>(deserializer: D) -> Result<TransactionHash, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+    use serde::Deserialize;
+
+    let hex = String::deserialize(deserializer)?;
+    let digits = hex
+        .strip_prefix("0x")
+        .or_else(|| hex.strip_prefix("0X"))
+        .unwrap_or(&hex);
+
+    // Unlike a felt, a transaction hash cannot be empty.
+    if digits.is_empty() {
+        return Err(D::Error::custom("Transaction hash has no hex digits"));
+    }
+
+    stark_hash::Felt::from_hex_str(digits)
+        .map(TransactionHash)
+        .map_err(|e| D::Error::custom(format!("Invalid transaction hash {hex:?}: {e}")))
 }
 
-crate::error::generate_rpc_error_subset!(GetGatewayTransactionError:);
+crate::error::generate_rpc_error_subset!(
+    GetGatewayTransactionError: GatewayUnavailable,
+    DatabaseBusy,
+    InconsistentState
+);
 
+/// Returns the status of a transaction.
+///
+/// Transactions unknown to this node are looked up on the gateway, unless
+/// [RpcContext::gateway_status_fallback_enabled] is unset. Deployments without an upstream
+/// sequencer should unset it to avoid a failing gateway query per lookup, at the cost of
+/// reporting rejected and not yet pending transactions as `NOT_RECEIVED`. The same applies to a
+/// single query with `local_only` set.
+///
+/// Deprecated in favour of `starknet_getTransactionStatus`, but still served so that its usage
+/// can be tracked before removal.
 pub async fn get_transaction_status(
     context: RpcContext,
     input: GetGatewayTransactionInput,
-) -> Result<TransactionStatus, GetGatewayTransactionError> {
-    // Check in pending block.
-    if let Some(pending) = &context.pending_data {
-        if is_pending_tx(pending, &input.transaction_hash).await {
-            return Ok(TransactionStatus::Pending);
+) -> Result<GetTransactionStatusOutput, GetGatewayTransactionError> {
+    context.status_method_deprecation.warn_once(
+        "pathfinder_getTransactionStatus",
+        "starknet_getTransactionStatus",
+    );
+
+    // A local resolution must neither join nor be joined by one which consults the gateway, and
+    // is cheap enough not to be shared at all.
+    let context = match input.local_only {
+        true => RpcContext {
+            status_single_flight: SingleFlight::default(),
+            ..context
+                .with_gateway_status_fallback(false)
+                .with_gateway_pending_fallback(false)
+        },
+        false => context,
+    };
+
+    // The gateway's pending block must not stand in for the skipped one. Neither can such a
+    // resolution be shared with one which checks the pending block.
+    let context = match input.skip_pending {
+        true => RpcContext {
+            pending_data: None,
+            status_single_flight: SingleFlight::default(),
+            ..context.with_gateway_pending_fallback(false)
+        },
+        false => context,
+    };
+
+    // Waits may be disabled under load, in which case the current status is returned right away.
+    let status = match input.wait_for {
+        Some(wait_for) if context.status_waits.is_enabled() => {
+            let timeout = input
+                .timeout_ms
+                .map_or(MAX_WAIT, Duration::from_millis)
+                .min(MAX_WAIT);
+            wait_for_status(&context, input.transaction_hash, wait_for, timeout).await
         }
+        _ => resolve_status(context.clone(), input.transaction_hash).await,
+    };
+
+    let outcome = match &status {
+        Ok(status) => status.finality_status.as_lowercase(),
+        Err(_) => "error",
+    };
+    metrics::increment_counter!(METRIC_TRANSACTION_STATUS, "status" => outcome);
+
+    let status = status?;
+
+    // Only the gateway knows of received transactions, as they are not pending yet.
+    match status.finality_status {
+        TransactionStatus::Received => context.received_sightings.record(input.transaction_hash),
+        _ => context.received_sightings.forget(&input.transaction_hash),
     }
 
-    // Check database.
-    let span = tracing::Span::current();
+    let output = |status| GetTransactionStatusOutput {
+        lowercase: input.lowercase,
+        ..GetTransactionStatusOutput::new(status)
+    };
 
-    let db_status = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
+    if let Some(reason) = status.abort_reason {
+        return Ok(GetTransactionStatusOutput {
+            abort_reason: Some(reason),
+            ..output(TransactionStatus::Aborted)
+        });
+    }
 
-        let mut db = context
-            .storage
-            .connection()
-            .context("Opening database connection")?;
-        let db_tx = db.transaction().context("Creating database transaction")?;
-        let block_hash = db_tx
-            .transaction_block_hash(input.transaction_hash)
-            .context("Fetching transaction block hash from database")?;
-
-        let Some(block_hash) = block_hash else {
-            return Ok(None);
-        };
+    if let Some(reason) = status.rejection_reason {
+        let category = RejectionReason::from(reason.as_str());
+        return Ok(GetTransactionStatusOutput {
+            rejection: Some((reason, category)),
+            ..output(TransactionStatus::Rejected)
+        });
+    }
+
+    let finality_status = match input.verify && !input.local_only {
+        true => verified_status(&context, input.transaction_hash, &status).await,
+        false => status.finality_status,
+    };
+
+    let finality_status = match input.at_l2_block {
+        Some(l2_block) => {
+            status_at_l2_block(&context, input.transaction_hash, finality_status, l2_block).await?
+        }
+        None => finality_status,
+    };
+
+    let status = match input.at_l1_block {
+        Some(l1_block) => {
+            status_at_l1_block(&context, input.transaction_hash, finality_status, l1_block).await?
+        }
+        None => finality_status,
+    };
+
+    // Only a transaction which was never received can have been superseded.
+    let check_superseded = input
+        .check_superseded
+        .filter(|_| status == TransactionStatus::NotReceived);
+    if let Some(SenderNonce {
+        sender_address,
+        nonce,
+    }) = check_superseded
+    {
+        let replacement = with_db_tx(
+            context.storage.clone(),
+            ConnectionKind::Primary,
+            move |db_tx| {
+                db_tx
+                    .accepted_transaction_for_nonce(sender_address, nonce)
+                    .context("Querying accepted transaction for nonce")
+            },
+        )
+        .await?;
+
+        if let Some(by) = replacement {
+            return Ok(GetTransactionStatusOutput {
+                superseded_by: Some(by),
+                ..output(status)
+            });
+        }
+    }
+
+    if status == TransactionStatus::NotReceived {
+        let last_seen = context.pending_sightings.last_seen(&input.transaction_hash);
+        if let Some(last_seen_pending_at) = last_seen {
+            return Ok(GetTransactionStatusOutput {
+                last_seen_pending_at: Some(last_seen_pending_at),
+                ..output(status)
+            });
+        }
+    }
+
+    // A transaction which is received but not yet pending is only known to the gateway, and is
+    // usually one which was just submitted.
+    if let (TransactionStatus::Received, Some(interval)) = (status, context.pending_interval) {
+        return Ok(GetTransactionStatusOutput {
+            retry_after_ms: Some(interval.as_millis() as u64),
+            ..output(status)
+        });
+    }
+
+    if !input.include_history {
+        return Ok(output(status));
+    }
 
-        let tx_status = db_tx
-            .block_is_l1_accepted(block_hash.into())
-            .context("Quering block's status")?;
+    let history = status_history(context, input.transaction_hash, status).await?;
 
-        anyhow::Ok(Some(tx_status))
+    Ok(GetTransactionStatusOutput {
+        history: Some(history),
+        ..output(status)
     })
-    .await
-    .context("Joining database task")??;
+}
+
+/// The longest a status query may wait for the requested status.
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// How often a waiting status query re-checks the status if this node does not poll the pending
+/// block. Rechecks also happen on every new block and L1 head.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Resolves the transaction's status once it reaches `wait_for`, or once `timeout` elapses.
+///
+/// The status is re-checked on each pending block refresh, new block and new L1 head, after a
+/// random delay of up to the context's wait jitter. Transient failures are retried until the
+/// timeout, after which they are returned. Disabling [RpcContext::status_waits] ends the wait
+/// early, just like the timeout.
+async fn wait_for_status(
+    context: &RpcContext,
+    transaction_hash: TransactionHash,
+    wait_for: TransactionStatus,
+    timeout: Duration,
+) -> Result<TransactionStatusResponse, GetGatewayTransactionError> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    // Subscribe before the first check, so that no change in between is missed.
+    let mut new_heads = context
+        .new_heads
+        .as_ref()
+        .map(|new_heads| new_heads.subscribe());
+    let mut l1_head = context.l1_head.clone();
+    l1_head.borrow_and_update();
+    let mut poll = tokio::time::interval(context.pending_interval.unwrap_or(WAIT_POLL_INTERVAL));
+    poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first tick completes immediately.
+    poll.tick().await;
+
+    loop {
+        let status = resolve_status(context.clone(), transaction_hash).await;
+        match &status {
+            Ok(status) if status.finality_status.reaches(wait_for) => return Ok(status.clone()),
+            Ok(_)
+            | Err(
+                GetGatewayTransactionError::GatewayUnavailable
+                | GetGatewayTransactionError::DatabaseBusy,
+            ) => {}
+            Err(
+                GetGatewayTransactionError::InconsistentState
+                | GetGatewayTransactionError::Internal(_),
+            ) => return status,
+        }
 
-    match db_status {
-        Some(true) => return Ok(TransactionStatus::AcceptedOnL1),
-        Some(false) => return Ok(TransactionStatus::AcceptedOnL2),
-        None => {}
+        let new_head = async {
+            match &mut new_heads {
+                // Lagging behind is fine, as only the latest state matters.
+                Some(new_heads) => {
+                    if let Err(RecvError::Closed) = new_heads.recv().await {
+                        futures::future::pending::<()>().await;
+                    }
+                }
+                None => futures::future::pending::<()>().await,
+            }
+        };
+        let new_l1_head = async {
+            if l1_head.changed().await.is_err() {
+                futures::future::pending::<()>().await;
+            }
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => return status,
+            _ = context.status_waits.disabled() => return status,
+            _ = poll.tick() => {}
+            _ = new_head => {}
+            _ = new_l1_head => {}
+        }
+
+        // Queries woken by the same event would otherwise all hit the database at once.
+        let jitter = context.wait_jitter.delay();
+        tokio::time::sleep_until(deadline.min(tokio::time::Instant::now() + jitter)).await;
     }
+}
 
-    // Check gateway for rejected transactions.
-    use starknet_gateway_client::GatewayApi;
-    context
-        .sequencer
-        .transaction(input.transaction_hash)
-        .await
-        .context("Fetching transaction from gateway")
-        .map(|tx| tx.status.into())
-        .map_err(GetGatewayTransactionError::Internal)
+/// Cross-checks a transaction which storage reports as accepted on L2 against the gateway, if it
+/// is no further behind the L2 head than the reorg risk threshold. Returns the gateway's status if
+/// it disagrees with storage, and the status as is otherwise.
+///
+/// The gateway is queried like [gateway_statuses_with_fallback] does, i.e. with retries, failing
+/// over to the fallback gateways, and not at all while the circuit breaker is open.
+///
+/// This is a best-effort check -- gateway failures are logged and the stored status is returned.
+async fn verified_status(
+    context: &RpcContext,
+    transaction_hash: TransactionHash,
+    status: &TransactionStatusResponse,
+) -> TransactionStatus {
+    if status.finality_status != TransactionStatus::AcceptedOnL2
+        || status.reorg_risk != Some(ReorgRisk::Medium)
+    {
+        return status.finality_status;
+    }
+
+    let gateway_status = match gateway_statuses_with_fallback(context, &[transaction_hash]).await {
+        Ok(statuses) => statuses[0].status,
+        Err(error) => {
+            tracing::debug!(
+                ?error,
+                "Verifying transaction status against gateway failed"
+            );
+            return status.finality_status;
+        }
+    };
+
+    if gateway_status != status.finality_status {
+        tracing::warn!(
+            %transaction_hash,
+            stored=?status.finality_status,
+            gateway=?gateway_status,
+            "Stored transaction status disagrees with the gateway"
+        );
+    }
+
+    gateway_status
 }
 
-async fn is_pending_tx(pending: &PendingData, tx_hash: &TransactionHash) -> bool {
-    pending
-        .block()
-        .await
-        .map(|block| block.transactions.iter().any(|tx| &tx.hash() == tx_hash))
-        .unwrap_or_default()
+/// Returns whether an accepted transaction was already accepted on L1 as of `l1_block`, according
+/// to the L1 state recorded at or before it.
+///
+/// All other statuses are returned as is, as their history is not stored.
+async fn status_at_l1_block(
+    context: &RpcContext,
+    transaction_hash: TransactionHash,
+    status: TransactionStatus,
+    l1_block: u64,
+) -> Result<TransactionStatus, GetGatewayTransactionError> {
+    if !matches!(
+        status,
+        TransactionStatus::AcceptedOnL2 | TransactionStatus::AcceptedOnL1
+    ) {
+        return Ok(status);
+    }
+
+    let finality = with_db_tx(
+        context.storage.clone(),
+        ConnectionKind::Primary,
+        move |db_tx| {
+            db_tx
+                .transaction_finality_at_l1_block(transaction_hash, l1_block)
+                .context("Querying transaction's finality at L1 block")
+        },
+    )
+    .await?;
+
+    Ok(match finality {
+        Some(pathfinder_storage::TransactionStatus::L1Accepted) => TransactionStatus::AcceptedOnL1,
+        Some(pathfinder_storage::TransactionStatus::L2Accepted) => TransactionStatus::AcceptedOnL2,
+        // The transaction was accepted by the gateway, but is not in storage yet.
+        None => status,
+    })
 }
 
-#[derive(Copy, Clone, Debug, serde::Serialize, PartialEq)]
-pub enum TransactionStatus {
-    #[serde(rename = "NOT_RECEIVED")]
-    NotReceived,
-    #[serde(rename = "RECEIVED")]
-    Received,
-    #[serde(rename = "PENDING")]
-    Pending,
-    #[serde(rename = "REJECTED")]
-    Rejected,
-    #[serde(rename = "ACCEPTED_ON_L1")]
-    AcceptedOnL1,
-    #[serde(rename = "ACCEPTED_ON_L2")]
-    AcceptedOnL2,
-    #[serde(rename = "REVERTED")]
-    Reverted,
-    #[serde(rename = "ABORTED")]
-    Aborted,
+/// Returns whether an accepted transaction was already accepted as of `l2_block`, i.e. whether
+/// its block is at or below it. A transaction which was not is `NOT_RECEIVED`.
+///
+/// A transaction which is not in a block yet is `NOT_RECEIVED` as well if `l2_block` is already in
+/// storage, as it was not accepted as of it. It is returned as is if `l2_block` is still to come,
+/// as are all other statuses, as their history is not stored.
+async fn status_at_l2_block(
+    context: &RpcContext,
+    transaction_hash: TransactionHash,
+    status: TransactionStatus,
+    l2_block: BlockNumber,
+) -> Result<TransactionStatus, GetGatewayTransactionError> {
+    match status {
+        TransactionStatus::AcceptedOnL2 | TransactionStatus::AcceptedOnL1 => {
+            let block = with_db_tx(
+                context.storage.clone(),
+                ConnectionKind::Primary,
+                move |db_tx| {
+                    db_tx
+                        .canonical_block_for_transaction(transaction_hash)
+                        .context("Fetching transaction's canonical block")
+                },
+            )
+            .await?;
+
+            Ok(match block {
+                Some((number, _)) if number > l2_block => TransactionStatus::NotReceived,
+                Some(_) => status,
+                // The transaction was accepted by the gateway, but is not in storage yet.
+                None => status,
+            })
+        }
+        TransactionStatus::Received
+        | TransactionStatus::Pending
+        | TransactionStatus::PreConfirmed => {
+            let latest = with_db_tx(
+                context.storage.clone(),
+                ConnectionKind::Primary,
+                move |db_tx| {
+                    db_tx
+                        .block_id(pathfinder_storage::BlockId::Latest)
+                        .context("Fetching latest block")
+                },
+            )
+            .await?;
+
+            Ok(match latest {
+                Some((latest, _)) if latest >= l2_block => TransactionStatus::NotReceived,
+                _ => status,
+            })
+        }
+        _ => Ok(status),
+    }
 }
 
-impl From<starknet_gateway_types::reply::Status> for TransactionStatus {
-    fn from(value: starknet_gateway_types::reply::Status) -> Self {
-        use starknet_gateway_types::reply::Status;
-        match value {
-            Status::NotReceived => Self::NotReceived,
-            Status::Received => Self::Received,
-            Status::Pending => Self::Pending,
-            Status::Rejected => Self::Rejected,
-            Status::AcceptedOnL1 => Self::AcceptedOnL1,
-            Status::AcceptedOnL2 => Self::AcceptedOnL2,
-            Status::Reverted => Self::Reverted,
-            Status::Aborted => Self::Aborted,
+/// Counts the outcomes of [get_transaction_status], labelled by the resolved status or `error`.
+const METRIC_TRANSACTION_STATUS: &str = "rpc_get_transaction_status_total";
+
+/// Measures each gateway query made to resolve a transaction status.
+const METRIC_GATEWAY_DURATION: &str = "rpc_get_transaction_status_gateway_duration_seconds";
+
+/// Measures each database task run to resolve a transaction status, including the time it waits
+/// for a blocking thread and a database connection.
+const METRIC_DB_DURATION: &str = "rpc_get_transaction_status_db_duration_seconds";
+
+/// The output of [get_transaction_status].
+///
+/// Serialized as just the status if none of the optional fields are set, and as an object of the
+/// status and the fields which are set otherwise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GetTransactionStatusOutput {
+    pub status: TransactionStatus,
+    /// Serializes a status returned without further details as its lowercase name, see
+    /// [LowercaseStatus].
+    pub lowercase: bool,
+    /// The gateway has received the transaction, but this node has not seen it in a pending
+    /// block yet, which it is expected to within this many milliseconds i.e. the pending block
+    /// polling interval.
+    ///
+    /// Only set if this node polls the pending block, and takes precedence over the status
+    /// history.
+    pub retry_after_ms: Option<u64>,
+    /// The transaction was never received, but this transaction with the same sender and nonce
+    /// was accepted in its stead e.g. a fee-bumped replacement. Such a transaction is reported as
+    /// `SUPERSEDED`.
+    ///
+    /// This takes precedence over the status history, as the transaction has none.
+    pub superseded_by: Option<TransactionHash>,
+    /// The transaction was never received, but was seen in the pending block which would have
+    /// become this block.
+    ///
+    /// This takes precedence over the status history, as the transaction has none.
+    pub last_seen_pending_at: Option<BlockNumber>,
+    /// Why the transaction's block was aborted by the sequencer.
+    ///
+    /// Only set if the gateway reported an abort reason, and takes precedence over the status
+    /// history.
+    pub abort_reason: Option<AbortReason>,
+    /// Why the transaction was rejected by the gateway, along with its parsed category.
+    ///
+    /// Only set if the gateway reported a rejection reason, and takes precedence over the status
+    /// history. A transaction which the gateway does not know at all is
+    /// [TransactionStatus::NotReceived] instead.
+    pub rejection: Option<(String, RejectionReason)>,
+    /// Only set if requested.
+    pub history: Option<TransactionStatusHistory>,
+}
+
+impl GetTransactionStatusOutput {
+    /// An output of just the given status.
+    pub fn new(status: TransactionStatus) -> Self {
+        Self {
+            status,
+            lowercase: false,
+            retry_after_ms: None,
+            superseded_by: None,
+            last_seen_pending_at: None,
+            abort_reason: None,
+            rejection: None,
+            history: None,
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use pathfinder_common::{felt, felt_bytes};
+impl serde::Serialize for GetTransactionStatusOutput {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        /// Superseded transactions are reported as such, although they were never received.
+        #[derive(serde::Serialize)]
+        #[serde(untagged)]
+        enum ReportedStatus {
+            Status(TransactionStatus),
+            Superseded(&'static str),
+        }
 
-    use super::*;
+        /// Both abort and rejection reasons are reported as `reason`, but never together.
+        #[derive(serde::Serialize)]
+        #[serde(untagged)]
+        enum Reason<'a> {
+            Abort(&'a AbortReason),
+            Rejection(&'a str),
+        }
 
-    #[tokio::test]
-    async fn l1_accepted() {
-        let context = RpcContext::for_tests();
-        // This transaction is in block 0 which is L1 accepted.
-        let tx_hash = TransactionHash(felt_bytes!(b"txn 0"));
-        let input = GetGatewayTransactionInput {
-            transaction_hash: tx_hash,
+        #[derive(serde::Serialize)]
+        struct Object<'a> {
+            status: ReportedStatus,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            retry_after_ms: Option<u64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            by: Option<TransactionHash>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            last_seen_pending_at: Option<BlockNumber>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            reason: Option<Reason<'a>>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            category: Option<RejectionReason>,
+            #[serde(flatten)]
+            history: Option<&'a TransactionStatusHistory>,
+        }
+
+        let object = Object {
+            status: match self.superseded_by {
+                Some(_) => ReportedStatus::Superseded("SUPERSEDED"),
+                None => ReportedStatus::Status(self.status),
+            },
+            retry_after_ms: self.retry_after_ms,
+            by: self.superseded_by,
+            last_seen_pending_at: self.last_seen_pending_at,
+            reason: match (&self.abort_reason, &self.rejection) {
+                (Some(reason), _) => Some(Reason::Abort(reason)),
+                (None, Some((reason, _))) => Some(Reason::Rejection(reason)),
+                (None, None) => None,
+            },
+            category: self.rejection.as_ref().map(|(_, category)| *category),
+            history: self.history.as_ref(),
         };
-        let status = get_transaction_status(context, input).await.unwrap();
 
-        assert_eq!(status, TransactionStatus::AcceptedOnL1);
+        let bare = self.retry_after_ms.is_none()
+            && self.superseded_by.is_none()
+            && self.last_seen_pending_at.is_none()
+            && self.abort_reason.is_none()
+            && self.rejection.is_none()
+            && self.history.is_none();
+        match (bare, self.lowercase) {
+            (true, true) => serde::Serialize::serialize(&LowercaseStatus(self.status), serializer),
+            (true, false) => serde::Serialize::serialize(&self.status, serializer),
+            (false, _) => serde::Serialize::serialize(&object, serializer),
+        }
     }
+}
 
-    #[tokio::test]
-    async fn l2_accepted() {
-        let context = RpcContext::for_tests();
-        // This transaction is in block 1 which is not L1 accepted.
-        let tx_hash = TransactionHash(felt_bytes!(b"txn 1"));
-        let input = GetGatewayTransactionInput {
-            transaction_hash: tx_hash,
-        };
-        let status = get_transaction_status(context, input).await.unwrap();
+/// The times at which a transaction entered each status.
+///
+/// Timestamps are only present if they can be derived from data known to this node.
+#[serde_with::skip_serializing_none]
+#[derive(Clone, Debug, Default, serde::Serialize, PartialEq)]
+pub struct TransactionStatusHistory {
+    /// Never available, as transactions are only observed once they are part of a block.
+    pub received_at: Option<BlockTimestamp>,
+    /// The pending block's timestamp, only available while the transaction is pending.
+    pub pending_at: Option<BlockTimestamp>,
+    /// The timestamp of the block containing the transaction.
+    pub accepted_l2_at: Option<BlockTimestamp>,
+    /// Never available, as L1 state updates are stored without a timestamp.
+    pub accepted_l1_at: Option<BlockTimestamp>,
+}
+
+/// Derives the [TransactionStatusHistory] of a transaction with the given current `status`.
+async fn status_history(
+    context: RpcContext,
+    transaction_hash: TransactionHash,
+    status: TransactionStatus,
+) -> Result<TransactionStatusHistory, GetGatewayTransactionError> {
+    let mut history = TransactionStatusHistory::default();
 
-        assert_eq!(status, TransactionStatus::AcceptedOnL2);
+    match status {
+        TransactionStatus::Pending | TransactionStatus::PreConfirmed => {
+            if let Some(pending) = &context.pending_data {
+                history.pending_at = pending
+                    .block_containing(&transaction_hash)
+                    .await
+                    .map(|block| block.timestamp);
+            }
+        }
+        TransactionStatus::AcceptedOnL2 | TransactionStatus::AcceptedOnL1 => {
+            history.accepted_l2_at =
+                with_db_tx(context.storage, ConnectionKind::Primary, move |db_tx| {
+                    let Some(block_hash) = db_tx
+                        .transaction_block_hash(transaction_hash)
+                        .context("Fetching transaction's block hash")?
+                    else {
+                        return Ok(None);
+                    };
+
+                    let header = db_tx
+                        .block_header(block_hash.into())
+                        .context("Fetching block header")?;
+
+                    Ok(header.map(|header| header.timestamp))
+                })
+                .await?;
+        }
+        _ => {}
     }
 
-    #[tokio::test]
-    async fn pending() {
-        let context = RpcContext::for_tests_with_pending().await;
-        let tx_hash = TransactionHash(felt_bytes!(b"pending tx hash 0"));
-        let input = GetGatewayTransactionInput {
-            transaction_hash: tx_hash,
-        };
-        let status = get_transaction_status(context, input).await.unwrap();
+    Ok(history)
+}
 
-        assert_eq!(status, TransactionStatus::Pending);
+crate::error::generate_rpc_error_subset!(
+    GetTransactionStatusDetailsError: GatewayUnavailable,
+    DatabaseBusy,
+    InconsistentState,
+    ProofUnavailable
+);
+
+impl From<GetGatewayTransactionError> for GetTransactionStatusDetailsError {
+    fn from(e: GetGatewayTransactionError) -> Self {
+        match e {
+            GetGatewayTransactionError::GatewayUnavailable => Self::GatewayUnavailable,
+            GetGatewayTransactionError::DatabaseBusy => Self::DatabaseBusy,
+            GetGatewayTransactionError::InconsistentState => Self::InconsistentState,
+            GetGatewayTransactionError::Internal(internal) => Self::Internal(internal),
+        }
     }
+}
 
-    #[tokio::test]
-    async fn rejected() {
-        let input = GetGatewayTransactionInput {
-            transaction_hash: TransactionHash(felt!(
-                // Transaction hash known to be rejected by the testnet gateway.
-                "0x07c64b747bdb0831e7045925625bfa6309c422fded9527bacca91199a1c8d212"
-            )),
-        };
-        let context = RpcContext::for_tests();
-        let status = get_transaction_status(context, input).await.unwrap();
+impl From<DatabaseError> for GetTransactionStatusDetailsError {
+    fn from(e: DatabaseError) -> Self {
+        GetGatewayTransactionError::from(e).into()
+    }
+}
+
+/// Same as [get_transaction_status] but additionally returns the execution status of the
+/// transaction.
+///
+/// With `include_proof` set, a transaction accepted on L1 comes with a [BlockHashProof], or fails
+/// with `ProofUnavailable` if there is none. Other transactions come without a proof.
+pub async fn get_transaction_status_details(
+    context: RpcContext,
+    input: GetGatewayTransactionInput,
+) -> Result<TransactionStatusResponse, GetTransactionStatusDetailsError> {
+    let transaction_hash = input.transaction_hash;
+    let mut status = resolve_status_details(context.clone(), transaction_hash).await?;
+
+    // Attached after resolution, so that cached statuses never carry a proof.
+    if input.include_proof && status.finality_status == TransactionStatus::AcceptedOnL1 {
+        let proof = with_db_tx(
+            context.storage.clone(),
+            ConnectionKind::Primary,
+            move |db_tx| block_hash_proof(&db_tx, transaction_hash),
+        )
+        .await?;
+
+        status.block_proof = Some(proof.ok_or(GetTransactionStatusDetailsError::ProofUnavailable)?);
+    }
+
+    Ok(status)
+}
+
+/// Proves that a transaction's block is part of the state committed on L1.
+///
+/// Starknet records the hash of each block in the storage of the block hash contract at address
+/// `0x1`, keyed by the block's number. The proof is the Merkle path of this storage slot in the
+/// latest state committed on L1, as returned by `pathfinder_getProof`.
+#[derive(Clone, Debug, serde::Serialize, PartialEq)]
+pub struct BlockHashProof {
+    /// The block whose state, as committed on L1, the proof is against.
+    pub block_number: BlockNumber,
+    pub state_commitment: StateCommitment,
+    /// The storage slot holding the block hash, i.e. the transaction's block number.
+    pub key: StorageAddress,
+    pub proof: GetProofOutput,
+}
+
+/// Returns the [BlockHashProof] of the transaction's block, or [None] if the latest state
+/// committed on L1 does not record the block's hash. This is the case for blocks produced before
+/// Starknet recorded block hashes, and for blocks too recent to be recorded yet.
+fn block_hash_proof(
+    db_tx: &pathfinder_storage::Transaction<'_>,
+    transaction_hash: TransactionHash,
+) -> anyhow::Result<Option<BlockHashProof>> {
+    let Some((block_number, block_hash)) = db_tx
+        .canonical_block_for_transaction(transaction_hash)
+        .context("Fetching transaction's block")?
+    else {
+        return Ok(None);
+    };
+
+    let Some(l1_state) = db_tx
+        .latest_l1_state()
+        .context("Fetching latest L1 state")?
+    else {
+        return Ok(None);
+    };
+    let l1_block = db_tx
+        .block_id(l1_state.block_number.into())
+        .context("Fetching L1 state's block")?;
+    if l1_state.block_number < block_number
+        || l1_block != Some((l1_state.block_number, l1_state.block_hash))
+    {
+        return Ok(None);
+    }
+
+    let contract = ContractAddress::new_or_panic(stark_hash::Felt::from_u64(1));
+    let key = StorageAddress::new_or_panic(stark_hash::Felt::from_u64(block_number.get()));
+    let recorded = db_tx
+        .storage_value(l1_state.block_number.into(), contract, key)
+        .context("Fetching recorded block hash")?;
+    if recorded.map(|value| value.0) != Some(block_hash.0) {
+        return Ok(None);
+    }
+
+    let proof = match generate_proof(db_tx, l1_state.block_number.into(), contract, &[key]) {
+        Ok(proof) => proof,
+        Err(GetProofError::Internal(error)) => return Err(error),
+        Err(GetProofError::BlockNotFound) => return Ok(None),
+        Err(GetProofError::ProofLimitExceeded { .. }) => {
+            unreachable!("A single key is within the proof limit")
+        }
+    };
+
+    Ok(Some(BlockHashProof {
+        block_number: l1_state.block_number,
+        state_commitment: l1_state.state_root,
+        key,
+        proof,
+    }))
+}
+
+/// Same as [get_transaction_status] but returns the status as its [TransactionStatus::code],
+/// which is cheaper for polling clients to compare than the status name.
+pub async fn get_transaction_status_code(
+    context: RpcContext,
+    input: GetGatewayTransactionInput,
+) -> Result<u8, GetGatewayTransactionError> {
+    let status = resolve_status(context, input.transaction_hash).await?;
+
+    Ok(status.finality_status.code())
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct TransactionIsKnownInput {
+    transaction_hash: TransactionHash,
+}
+
+/// Returns whether this node knows of the transaction, i.e. whether it is in the pending block or
+/// in storage.
+///
+/// This is cheaper than [get_transaction_status] for existence checks, as it neither determines
+/// the transaction's finality nor queries the gateway. Transactions only known to the gateway are
+/// therefore reported as unknown.
+pub async fn transaction_is_known(
+    context: RpcContext,
+    input: TransactionIsKnownInput,
+) -> Result<bool, GetGatewayTransactionError> {
+    let transaction_hash = input.transaction_hash;
+    if transaction_hash == TransactionHash::ZERO {
+        return Ok(false);
+    }
+
+    if let Some(pending) = &context.pending_data {
+        if pending.block_containing(&transaction_hash).await.is_some() {
+            return Ok(true);
+        }
+    }
+
+    let block_hash = with_db_tx(
+        context.storage.clone(),
+        ConnectionKind::Primary,
+        move |db_tx| {
+            db_tx
+                .transaction_block_hash(transaction_hash)
+                .context("Fetching transaction's block hash from database")
+        },
+    )
+    .await?;
+
+    Ok(block_hash.is_some())
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct GetTransactionStatusByBlockHashAndIndexInput {
+    block_hash: BlockHash,
+    index: usize,
+}
+
+/// Same as [get_transaction_status] but identifies the transaction by its position within a block.
+///
+/// Returns [TransactionStatus::NotReceived] if there is no such transaction in storage, or if its
+/// block was reorged away.
+pub async fn get_transaction_status_by_block_hash_and_index(
+    context: RpcContext,
+    input: GetTransactionStatusByBlockHashAndIndexInput,
+) -> Result<TransactionStatus, GetGatewayTransactionError> {
+    let transaction_hash = with_db_tx(
+        context.storage.clone(),
+        ConnectionKind::Primary,
+        move |db_tx| {
+            db_tx
+                .transaction_hash_at(input.block_hash, input.index)
+                .context("Fetching transaction hash from database")
+        },
+    )
+    .await?;
+
+    let Some(transaction_hash) = transaction_hash else {
+        return Ok(TransactionStatus::NotReceived);
+    };
+
+    resolve_status(context, transaction_hash)
+        .await
+        .map(|status| status.finality_status)
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct TransactionStatusByMessageHashInput {
+    message_hash: H256,
+}
+
+crate::error::generate_rpc_error_subset!(
+    TransactionLookupError: TxnHashNotFound,
+    GatewayUnavailable,
+    DatabaseBusy,
+    InconsistentState
+);
+
+impl From<GetGatewayTransactionError> for TransactionLookupError {
+    fn from(e: GetGatewayTransactionError) -> Self {
+        match e {
+            GetGatewayTransactionError::GatewayUnavailable => Self::GatewayUnavailable,
+            GetGatewayTransactionError::DatabaseBusy => Self::DatabaseBusy,
+            GetGatewayTransactionError::InconsistentState => Self::InconsistentState,
+            GetGatewayTransactionError::Internal(internal) => Self::Internal(internal),
+        }
+    }
+}
+
+impl From<DatabaseError> for TransactionLookupError {
+    fn from(e: DatabaseError) -> Self {
+        GetGatewayTransactionError::from(e).into()
+    }
+}
+
+/// Same as [get_transaction_status] but identifies the L1 handler transaction by the hash of the
+/// L1 to L2 message it consumed.
+///
+/// Fails with [TransactionLookupError::TxnHashNotFound] if no L1 handler transaction
+/// in storage consumed the message.
+pub async fn transaction_status_by_message_hash(
+    context: RpcContext,
+    input: TransactionStatusByMessageHashInput,
+) -> Result<TransactionStatus, TransactionLookupError> {
+    let transaction_hash = with_db_tx(
+        context.storage.clone(),
+        ConnectionKind::Primary,
+        move |db_tx| {
+            db_tx
+                .l1_handler_for_message_hash(input.message_hash)
+                .context("Fetching L1 handler transaction from database")
+        },
+    )
+    .await?;
+
+    let Some(transaction_hash) = transaction_hash else {
+        return Err(TransactionLookupError::TxnHashNotFound);
+    };
+
+    let status = resolve_status(context, transaction_hash).await?;
+
+    Ok(status.finality_status)
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct LatestTransactionStatusForAddressInput {
+    address: ContractAddress,
+}
+
+#[derive(Clone, Debug, serde::Serialize, PartialEq)]
+pub struct LatestTransactionStatus {
+    pub transaction_hash: TransactionHash,
+    pub status: TransactionStatus,
+}
+
+/// Returns the status of the latest transaction sent by `address`, preferring one in the pending
+/// block over those in storage.
+///
+/// Only transactions which are sequenced by their sender's nonce are considered. Fails with
+/// [TransactionLookupError::TxnHashNotFound] if the address sent no such transaction.
+pub async fn latest_transaction_status_for_address(
+    context: RpcContext,
+    input: LatestTransactionStatusForAddressInput,
+) -> Result<LatestTransactionStatus, TransactionLookupError> {
+    let address = input.address;
 
-        assert_eq!(status, TransactionStatus::Rejected);
+    let mut transaction_hash = None;
+    if let Some(pending) = &context.pending_data {
+        if let Some(block) = pending.block().await {
+            transaction_hash = block
+                .transactions
+                .iter()
+                .rev()
+                .find(|t| t.contract_address() == address && t.nonce().is_some())
+                .map(|t| t.hash());
+        }
+    }
+
+    if transaction_hash.is_none() {
+        transaction_hash = with_db_tx(
+            context.storage.clone(),
+            ConnectionKind::Primary,
+            move |db_tx| {
+                db_tx
+                    .latest_transaction_for_sender(address)
+                    .context("Fetching sender's latest transaction from database")
+            },
+        )
+        .await?;
+    }
+
+    let Some(transaction_hash) = transaction_hash else {
+        return Err(TransactionLookupError::TxnHashNotFound);
+    };
+
+    let status = resolve_status(context, transaction_hash).await?;
+
+    Ok(LatestTransactionStatus {
+        transaction_hash,
+        status: status.finality_status,
+    })
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct ReconcileTransactionStatusInput {
+    transaction_hash: TransactionHash,
+    expected: TransactionStatus,
+}
+
+#[derive(Clone, Debug, serde::Serialize, PartialEq)]
+pub struct ReconciledTransactionStatus {
+    pub actual: TransactionStatus,
+    pub matches: bool,
+    pub drift_direction: StatusDrift,
+}
+
+/// How a transaction's actual status relates to an expected one, according to the
+/// [ordering](Ord) of [TransactionStatus].
+#[derive(Copy, Clone, Debug, serde::Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusDrift {
+    /// The actual status is further along than the expected one.
+    Ahead,
+    /// The actual status is not yet as far along as the expected one.
+    Behind,
+    Equal,
+}
+
+/// Compares the transaction's status to the `expected` one, e.g. from a client's own ledger, and
+/// reports in which direction they drifted apart.
+pub async fn reconcile_transaction_status(
+    context: RpcContext,
+    input: ReconcileTransactionStatusInput,
+) -> Result<ReconciledTransactionStatus, GetGatewayTransactionError> {
+    let actual = resolve_status(context, input.transaction_hash)
+        .await?
+        .finality_status;
+
+    let drift_direction = match actual.cmp(&input.expected) {
+        std::cmp::Ordering::Greater => StatusDrift::Ahead,
+        std::cmp::Ordering::Less => StatusDrift::Behind,
+        std::cmp::Ordering::Equal => StatusDrift::Equal,
+    };
+
+    Ok(ReconciledTransactionStatus {
+        actual,
+        matches: actual == input.expected,
+        drift_direction,
+    })
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct GetTransactionStatusesInput {
+    transaction_hashes: Vec<TransactionHash>,
+}
+
+#[derive(Debug)]
+pub enum GetTransactionStatusesError {
+    Internal(anyhow::Error),
+    GatewayUnavailable,
+    DatabaseBusy,
+    InconsistentState,
+    TransactionHashLimitExceeded { limit: usize, requested: usize },
+}
+
+impl From<GetTransactionStatusesError> for crate::error::RpcError {
+    fn from(x: GetTransactionStatusesError) -> Self {
+        match x {
+            GetTransactionStatusesError::TransactionHashLimitExceeded { limit, requested } => {
+                Self::TransactionHashLimitExceeded { limit, requested }
+            }
+            GetTransactionStatusesError::GatewayUnavailable => Self::GatewayUnavailable,
+            GetTransactionStatusesError::DatabaseBusy => Self::DatabaseBusy,
+            GetTransactionStatusesError::InconsistentState => Self::InconsistentState,
+            GetTransactionStatusesError::Internal(internal) => Self::Internal(internal),
+        }
+    }
+}
+
+impl From<GetGatewayTransactionError> for GetTransactionStatusesError {
+    fn from(e: GetGatewayTransactionError) -> Self {
+        match e {
+            GetGatewayTransactionError::GatewayUnavailable => Self::GatewayUnavailable,
+            GetGatewayTransactionError::DatabaseBusy => Self::DatabaseBusy,
+            GetGatewayTransactionError::InconsistentState => Self::InconsistentState,
+            GetGatewayTransactionError::Internal(internal) => Self::Internal(internal),
+        }
+    }
+}
+
+impl From<anyhow::Error> for GetTransactionStatusesError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+/// Batch version of [get_transaction_status].
+///
+/// The statuses are returned in the same order as the input hashes.
+pub async fn get_transaction_statuses(
+    context: RpcContext,
+    input: GetTransactionStatusesInput,
+) -> Result<Vec<(TransactionHash, TransactionStatus)>, GetTransactionStatusesError> {
+    const MAX_TRANSACTION_HASHES: usize = 100;
+    if input.transaction_hashes.len() > MAX_TRANSACTION_HASHES {
+        return Err(GetTransactionStatusesError::TransactionHashLimitExceeded {
+            limit: MAX_TRANSACTION_HASHES,
+            requested: input.transaction_hashes.len(),
+        });
+    }
+
+    let statuses =
+        resolve_statuses(context, &input.transaction_hashes, StatusDetail::Finality).await?;
+
+    Ok(input
+        .transaction_hashes
+        .into_iter()
+        .zip(
+            statuses
+                .into_iter()
+                .map(|(_, status)| status.finality_status),
+        )
+        .collect())
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct GetTransactionStatusesInBlockRangeInput {
+    from_block: BlockNumber,
+    to_block: BlockNumber,
+}
+
+#[derive(Debug)]
+pub enum GetTransactionStatusesInBlockRangeError {
+    Internal(anyhow::Error),
+    DatabaseBusy,
+    InconsistentState,
+    BlockRangeLimitExceeded { limit: u64, requested: u64 },
+    InvalidBlockRange,
+}
+
+impl From<GetTransactionStatusesInBlockRangeError> for crate::error::RpcError {
+    fn from(x: GetTransactionStatusesInBlockRangeError) -> Self {
+        match x {
+            GetTransactionStatusesInBlockRangeError::InvalidBlockRange => Self::InvalidBlockRange,
+            GetTransactionStatusesInBlockRangeError::BlockRangeLimitExceeded {
+                limit,
+                requested,
+            } => Self::BlockRangeLimitExceeded { limit, requested },
+            GetTransactionStatusesInBlockRangeError::DatabaseBusy => Self::DatabaseBusy,
+            GetTransactionStatusesInBlockRangeError::InconsistentState => Self::InconsistentState,
+            GetTransactionStatusesInBlockRangeError::Internal(internal) => Self::Internal(internal),
+        }
+    }
+}
+
+impl From<DatabaseError> for GetTransactionStatusesInBlockRangeError {
+    fn from(e: DatabaseError) -> Self {
+        match e {
+            DatabaseError::DatabaseBusy => Self::DatabaseBusy,
+            DatabaseError::InconsistentState => Self::InconsistentState,
+            DatabaseError::Internal(internal) => Self::Internal(internal),
+        }
+    }
+}
+
+/// Returns the statuses of all transactions in the blocks `from_block` up to and including
+/// `to_block`, in block order, for explorers backfilling a range.
+///
+/// Unlike [get_transaction_statuses], the statuses are read from storage in a single query, so
+/// neither the pending block nor the gateway are consulted. Blocks which are not in storage have
+/// no transactions.
+pub async fn get_transaction_statuses_in_block_range(
+    context: RpcContext,
+    input: GetTransactionStatusesInBlockRangeInput,
+) -> Result<Vec<(TransactionHash, TransactionStatus)>, GetTransactionStatusesInBlockRangeError> {
+    const MAX_BLOCK_RANGE: u64 = 100;
+    if input.to_block < input.from_block {
+        return Err(GetTransactionStatusesInBlockRangeError::InvalidBlockRange);
+    }
+
+    let requested = input
+        .to_block
+        .get()
+        .saturating_sub(input.from_block.get())
+        .saturating_add(1);
+    if requested > MAX_BLOCK_RANGE {
+        return Err(
+            GetTransactionStatusesInBlockRangeError::BlockRangeLimitExceeded {
+                limit: MAX_BLOCK_RANGE,
+                requested,
+            },
+        );
+    }
+
+    let statuses = with_db_tx(
+        context.storage.clone(),
+        ConnectionKind::Primary,
+        move |db_tx| {
+            db_tx
+                .transaction_statuses_in_block_range(input.from_block, input.to_block)
+                .context("Querying transaction statuses in block range")
+        },
+    )
+    .await?;
+
+    Ok(statuses
+        .into_iter()
+        .map(|(hash, finality)| {
+            let status = match finality {
+                pathfinder_storage::TransactionStatus::L1Accepted => {
+                    TransactionStatus::AcceptedOnL1
+                }
+                pathfinder_storage::TransactionStatus::L2Accepted => {
+                    TransactionStatus::AcceptedOnL2
+                }
+            };
+            (hash, status)
+        })
+        .collect())
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct BlockTransactionStatusSummaryInput {
+    block_id: pathfinder_common::BlockId,
+}
+
+/// The number of transactions in a block by execution status.
+#[derive(Clone, Debug, Default, serde::Serialize, PartialEq, Eq)]
+pub struct BlockTransactionStatusSummary {
+    pub succeeded: usize,
+    pub reverted: usize,
+}
+
+impl BlockTransactionStatusSummary {
+    fn new<'a>(receipts: impl IntoIterator<Item = &'a Receipt>) -> Self {
+        let mut summary = Self::default();
+        for receipt in receipts {
+            match receipt.execution_status {
+                GatewayExecutionStatus::Succeeded => summary.succeeded += 1,
+                GatewayExecutionStatus::Reverted => summary.reverted += 1,
+            }
+        }
+        summary
+    }
+}
+
+crate::error::generate_rpc_error_subset!(
+    BlockTransactionStatusSummaryError: BlockNotFound,
+    DatabaseBusy
+);
+
+impl From<DatabaseError> for BlockTransactionStatusSummaryError {
+    fn from(e: DatabaseError) -> Self {
+        match e {
+            DatabaseError::DatabaseBusy => Self::DatabaseBusy,
+            DatabaseError::InconsistentState => {
+                Self::Internal(anyhow::anyhow!("Storage is inconsistent"))
+            }
+            DatabaseError::Internal(internal) => Self::Internal(internal),
+        }
+    }
+}
+
+/// Counts the transactions in a block by their execution status, according to their receipts.
+///
+/// The `pending` block is empty if this node does not poll it.
+pub async fn block_transaction_status_summary(
+    context: RpcContext,
+    input: BlockTransactionStatusSummaryInput,
+) -> Result<BlockTransactionStatusSummary, BlockTransactionStatusSummaryError> {
+    let block_id = match input.block_id {
+        pathfinder_common::BlockId::Pending => {
+            let block = match &context.pending_data {
+                Some(pending) => pending.block().await,
+                None => None,
+            };
+            return Ok(block.map_or_else(Default::default, |block| {
+                BlockTransactionStatusSummary::new(&block.transaction_receipts)
+            }));
+        }
+        other => other.try_into().expect("Only pending cast should fail"),
+    };
+
+    let data = with_db_tx(
+        context.storage.clone(),
+        ConnectionKind::Primary,
+        move |db_tx| {
+            db_tx
+                .transaction_data_for_block(block_id)
+                .context("Fetching block's transactions")
+        },
+    )
+    .await?
+    .ok_or(BlockTransactionStatusSummaryError::BlockNotFound)?;
+
+    Ok(BlockTransactionStatusSummary::new(
+        data.iter().map(|(_, receipt)| receipt),
+    ))
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct PrewarmTransactionStatusesInput {
+    transaction_hashes: Vec<TransactionHash>,
+}
+
+/// How the statuses warmed by [prewarm_transaction_statuses] were resolved.
+#[derive(Clone, Debug, Default, serde::Serialize, PartialEq, Eq)]
+pub struct PrewarmSummary {
+    /// Resolved from storage, and now cached until the L2 head changes.
+    pub database: usize,
+    /// Resolved from the gateway. Only rejected and not received statuses are cached.
+    pub gateway: usize,
+    /// Resolved without storage or the gateway, which needs no warming.
+    pub other: usize,
+}
+
+/// Resolves the status of each transaction ahead of time, so that the status caches are warm by
+/// the time the transactions are first queried.
+///
+/// This is an admin method, as a single call can cost up to a thousand gateway queries.
+pub async fn prewarm_transaction_statuses(
+    context: RpcContext,
+    input: PrewarmTransactionStatusesInput,
+) -> Result<PrewarmSummary, GetTransactionStatusesError> {
+    const MAX_TRANSACTION_HASHES: usize = 1000;
+    if input.transaction_hashes.len() > MAX_TRANSACTION_HASHES {
+        return Err(GetTransactionStatusesError::TransactionHashLimitExceeded {
+            limit: MAX_TRANSACTION_HASHES,
+            requested: input.transaction_hashes.len(),
+        });
+    }
+
+    // Warmed with all details, so that the cached statuses serve every kind of query.
+    let statuses = resolve_statuses(context, &input.transaction_hashes, StatusDetail::Full).await?;
+
+    let mut summary = PrewarmSummary::default();
+    for (source, _) in statuses {
+        match source {
+            StatusSource::Database => summary.database += 1,
+            StatusSource::Gateway => summary.gateway += 1,
+            StatusSource::Validation | StatusSource::Pending | StatusSource::Default => {
+                summary.other += 1
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct StuckReceivedTransactionsInput {
+    older_than_secs: u64,
+}
+
+crate::error::generate_rpc_error_subset!(StuckReceivedTransactionsError);
+
+/// Returns the transactions which [get_transaction_status] has reported as received for at least
+/// `older_than_secs`, without them reaching the pending block or storage since. These are
+/// returned longest received first.
+///
+/// This is an admin method, meant as an alerting signal for transactions stuck at the gateway.
+pub async fn stuck_received_transactions(
+    context: RpcContext,
+    input: StuckReceivedTransactionsInput,
+) -> Result<Vec<TransactionHash>, StuckReceivedTransactionsError> {
+    let older_than = Duration::from_secs(input.older_than_secs);
+
+    Ok(context.received_sightings.stuck(older_than))
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct SetStatusWaitsInput {
+    enabled: bool,
+}
+
+crate::error::generate_rpc_error_subset!(SetStatusWaitsError);
+
+/// Enables or disables waiting for a status. While disabled, status queries with `wait_for` set
+/// return the current status right away, and disabling also ends the ongoing waits.
+///
+/// This is an admin method, meant as a kill-switch for when holding connections open is
+/// dangerous. It returns whether waits were enabled before.
+pub async fn set_status_waits(
+    context: RpcContext,
+    input: SetStatusWaitsInput,
+) -> Result<bool, SetStatusWaitsError> {
+    let enabled = context.status_waits.is_enabled();
+    context.status_waits.set_enabled(input.enabled);
+    tracing::info!(enabled=%input.enabled, "Transaction status waits toggled");
+
+    Ok(enabled)
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct GetTransactionStatusDebugInput {
+    transaction_hash: TransactionHash,
+}
+
+#[derive(Clone, Debug, serde::Serialize, PartialEq)]
+pub struct TransactionStatusDebug {
+    pub status: TransactionStatus,
+    /// Whether the gateway was queried to resolve the status, rather than it being resolved
+    /// locally or served from the cache.
+    pub gateway_consulted: bool,
+    /// The gateway's reply from resolving the status, if it was consulted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway_reply: Option<GatewayReply>,
+}
+
+/// Same as [get_transaction_status], but also includes the gateway's reply, so that a
+/// transaction's status can be reproduced without querying the gateway separately.
+///
+/// The reply is the one the status was resolved from, so the gateway is never queried again. A
+/// status resolved without the gateway is reported as such.
+///
+/// This is an admin method, meant for support engineers debugging e.g. rejected transactions.
+pub async fn get_transaction_status_debug(
+    context: RpcContext,
+    input: GetTransactionStatusDebugInput,
+) -> Result<TransactionStatusDebug, GetGatewayTransactionError> {
+    let (mut statuses, mut replies) =
+        resolve_statuses_with_replies(context, &[input.transaction_hash], StatusDetail::Finality)
+            .await?;
+    let (_, status) = statuses.pop().expect("One status per transaction");
+    let gateway_reply = replies.pop().expect("One reply per transaction");
+
+    Ok(TransactionStatusDebug {
+        status: status.finality_status,
+        gateway_consulted: gateway_reply.is_some(),
+        gateway_reply,
+    })
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct RecentRejectedTransactionsInput {
+    /// The `next_cursor` of the previous page, if any.
+    #[serde(default)]
+    cursor: Option<u64>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// A transaction rejection which this node persisted.
+#[derive(serde::Serialize, Debug, PartialEq, Eq)]
+pub struct RejectedTransaction {
+    pub transaction_hash: TransactionHash,
+    /// Only available if the gateway reported why.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Seconds since the Unix epoch. Not available for rejections persisted by older versions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub observed_at: Option<u64>,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq, Eq)]
+pub struct RejectedTransactionsPage {
+    pub rejections: Vec<RejectedTransaction>,
+    /// Fetches the next page if passed as `cursor`, unless this was the last page.
+    pub next_cursor: Option<u64>,
+}
+
+/// The page size of [recent_rejected_transactions], unless requested otherwise.
+const REJECTIONS_PAGE_SIZE: usize = 100;
+const MAX_REJECTIONS_PAGE_SIZE: usize = 1000;
+
+crate::error::generate_rpc_error_subset!(
+    RecentRejectedTransactionsError: PageSizeTooBig,
+    DatabaseBusy
+);
+
+impl From<DatabaseError> for RecentRejectedTransactionsError {
+    fn from(e: DatabaseError) -> Self {
+        match e {
+            DatabaseError::DatabaseBusy => Self::DatabaseBusy,
+            DatabaseError::InconsistentState => {
+                Self::Internal(anyhow::anyhow!("Storage is inconsistent"))
+            }
+            DatabaseError::Internal(internal) => Self::Internal(internal),
+        }
+    }
+}
+
+/// Returns the transaction rejections which this node persisted, most recently observed first.
+///
+/// Pages are stable: each cursor continues from the same point, even if more rejections are
+/// persisted in between. A rejection which is observed again moves to the front though, so it is
+/// skipped by pages past its previous position.
+///
+/// This is an admin method, meant for auditing what the gateway reported as rejected.
+pub async fn recent_rejected_transactions(
+    context: RpcContext,
+    input: RecentRejectedTransactionsInput,
+) -> Result<RejectedTransactionsPage, RecentRejectedTransactionsError> {
+    let limit = input.limit.unwrap_or(REJECTIONS_PAGE_SIZE);
+    if limit > MAX_REJECTIONS_PAGE_SIZE {
+        return Err(RecentRejectedTransactionsError::PageSizeTooBig);
+    }
+
+    let mut rejections = with_db_tx(
+        context.storage.clone(),
+        ConnectionKind::Primary,
+        move |db_tx| {
+            // One more than requested, to tell whether this is the last page.
+            db_tx
+                .recent_rejected_transactions(input.cursor, limit + 1)
+                .context("Fetching rejected transactions")
+        },
+    )
+    .await?;
+
+    let next_cursor = match rejections.len() > limit {
+        true => {
+            rejections.truncate(limit);
+            rejections.last().map(|rejection| rejection.id)
+        }
+        false => None,
+    };
+    let rejections = rejections
+        .into_iter()
+        .map(|rejection| RejectedTransaction {
+            transaction_hash: rejection.hash,
+            reason: Some(rejection.reason).filter(|reason| !reason.is_empty()),
+            observed_at: rejection.observed_at,
+        })
+        .collect();
+
+    Ok(RejectedTransactionsPage {
+        rejections,
+        next_cursor,
+    })
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct ExplainTransactionStatusInput {
+    #[serde(deserialize_with = "lenient_transaction_hash")]
+    transaction_hash: TransactionHash,
+}
+
+/// A backend consulted when resolving a transaction's status.
+#[derive(Copy, Clone, Debug, serde::Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusBackend {
+    /// The transaction hash alone determines the status.
+    Validation,
+    Pending,
+    /// The gateway's view of the pending block, used if this node has no pending data.
+    GatewayPending,
+    Database,
+    GatewayCache,
+    Gateway,
+}
+
+/// The backends which [get_transaction_status] would consult for a transaction, in order.
+///
+/// Local backends are checked, so the plan ends at the first one which would resolve the status.
+/// Remote backends are not queried, and the plan continues past them as though they were missed.
+#[derive(Clone, Debug, serde::Serialize, PartialEq, Eq)]
+pub struct StatusPlan {
+    pub backends: Vec<StatusBackend>,
+}
+
+/// Explains how [get_transaction_status] would resolve a transaction's status given the node's
+/// current state, without querying the gateway.
+pub async fn explain_transaction_status(
+    context: RpcContext,
+    input: ExplainTransactionStatusInput,
+) -> Result<StatusPlan, GetGatewayTransactionError> {
+    let transaction_hash = input.transaction_hash;
+    let mut backends = Vec::new();
+
+    if transaction_hash == TransactionHash::ZERO {
+        backends.push(StatusBackend::Validation);
+        return Ok(StatusPlan { backends });
+    }
+
+    let mut in_pending = false;
+    if let Some(pending) = &context.pending_data {
+        backends.push(StatusBackend::Pending);
+        if let Some(block) = pending.block_containing(&transaction_hash).await {
+            // Only a transaction in stale pending data is checked against storage.
+            if !context.storage_head.is_stale(block.parent_hash) {
+                return Ok(StatusPlan { backends });
+            }
+            in_pending = true;
+        }
+    }
+
+    if consults_gateway_pending(&context) {
+        backends.push(StatusBackend::GatewayPending);
+    }
+
+    backends.push(StatusBackend::Database);
+    if in_pending {
+        return Ok(StatusPlan { backends });
+    }
+
+    let l1_head = context.l1_head.borrow().clone();
+    let reorg_risk = context.reorg_risk;
+    let in_database = with_db_tx(
+        context.storage.clone(),
+        ConnectionKind::Primary,
+        move |db_tx| {
+            db_tx_status(
+                &db_tx,
+                transaction_hash,
+                l1_head.as_ref(),
+                reorg_risk,
+                StatusDetail::Finality,
+            )
+            .map(|status| status.is_some())
+        },
+    )
+    .await?;
+    if in_database {
+        return Ok(StatusPlan { backends });
+    }
+
+    if !context.gateway_status_fallback_enabled {
+        return Ok(StatusPlan { backends });
+    }
+
+    backends.push(StatusBackend::GatewayCache);
+    if context
+        .gateway_status_cache
+        .get(&transaction_hash)
+        .is_none()
+    {
+        backends.push(StatusBackend::Gateway);
+    }
+
+    Ok(StatusPlan { backends })
+}
+
+/// Resolves the transaction's status with [StatusDetail::Finality], which is all that most
+/// methods report.
+pub(crate) async fn resolve_status(
+    context: RpcContext,
+    transaction_hash: TransactionHash,
+) -> Result<TransactionStatusResponse, GetGatewayTransactionError> {
+    resolve_status_with(context, transaction_hash, StatusDetail::Finality).await
+}
+
+/// Same as [resolve_status], but with [StatusDetail::Full].
+pub(crate) async fn resolve_status_details(
+    context: RpcContext,
+    transaction_hash: TransactionHash,
+) -> Result<TransactionStatusResponse, GetGatewayTransactionError> {
+    resolve_status_with(context, transaction_hash, StatusDetail::Full).await
+}
+
+#[tracing::instrument(
+    skip(context),
+    fields(status.source = tracing::field::Empty, status = tracing::field::Empty)
+)]
+async fn resolve_status_with(
+    context: RpcContext,
+    transaction_hash: TransactionHash,
+    detail: StatusDetail,
+) -> Result<TransactionStatusResponse, GetGatewayTransactionError> {
+    // Concurrent queries for the same transaction and detail share a single resolution, in which
+    // case only the query which performed it records the status source.
+    let status = context
+        .status_single_flight
+        .clone()
+        .run((transaction_hash, detail), move || async move {
+            let _permit = acquire_status_permit(&context).await?;
+
+            let (source, status) = resolve_statuses(context, &[transaction_hash], detail)
+                .await?
+                .pop()
+                .expect("One status per transaction hash");
+
+            tracing::Span::current().record("status.source", source.as_str());
+
+            Ok(status)
+        })
+        .await?;
+
+    tracing::Span::current().record("status", tracing::field::debug(status.finality_status));
+
+    Ok(status)
+}
+
+/// How long a status query waits for one of the [RpcContext::status_permits] before it is
+/// rejected as retryable.
+const STATUS_PERMIT_WAIT: Duration = Duration::from_secs(1);
+
+/// Waits for one of the [RpcContext::status_permits], failing with
+/// [GetGatewayTransactionError::DatabaseBusy] if none frees up within [STATUS_PERMIT_WAIT].
+async fn acquire_status_permit(
+    context: &RpcContext,
+) -> Result<tokio::sync::OwnedSemaphorePermit, GetGatewayTransactionError> {
+    let permit = context.status_permits.clone().acquire_owned();
+
+    match tokio::time::timeout(STATUS_PERMIT_WAIT, permit).await {
+        Ok(permit) => Ok(permit.expect("Status permits are never closed")),
+        Err(_) => {
+            tracing::debug!("Too many concurrent transaction status queries");
+            Err(GetGatewayTransactionError::DatabaseBusy)
+        }
+    }
+}
+
+/// Where a transaction's status was resolved from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum StatusSource {
+    /// The status follows from the transaction hash alone.
+    Validation,
+    Pending,
+    Database,
+    Gateway,
+    /// The gateway fallback is disabled, so an unknown transaction is reported as not received.
+    Default,
+}
+
+/// The sources of a transaction's status, from the highest precedence to the lowest.
+///
+/// A hash can be known to several sources at once, e.g. a transaction committed to storage while
+/// the stale pending data still lists it. The status of the source with the highest precedence is
+/// reported, so that the result does not depend on timing. In particular, committed finality
+/// outranks pending, as long as the pending data is checked against storage at all.
+const STATUS_PRECEDENCE: [StatusSource; 5] = [
+    StatusSource::Validation,
+    StatusSource::Database,
+    StatusSource::Pending,
+    StatusSource::Gateway,
+    StatusSource::Default,
+];
+
+impl StatusSource {
+    /// Whether a status from this source takes precedence over one from `other`, according to
+    /// [STATUS_PRECEDENCE].
+    fn outranks(self, other: StatusSource) -> bool {
+        let rank = |source| {
+            STATUS_PRECEDENCE
+                .iter()
+                .position(|s| *s == source)
+                .expect("All sources are ranked")
+        };
+        rank(self) < rank(other)
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            StatusSource::Validation => "validation",
+            StatusSource::Pending => "pending",
+            StatusSource::Database => "db",
+            StatusSource::Gateway => "gateway",
+            StatusSource::Default => "default",
+        }
+    }
+}
+
+/// Resolves the status of each transaction, in the same order as `transaction_hashes`, along
+/// with the source of each status. Stored statuses are resolved with the given `detail`.
+///
+/// A single database transaction is used for all lookups. Only transactions which are unknown
+/// to this node are queried from the gateway.
+async fn resolve_statuses(
+    context: RpcContext,
+    transaction_hashes: &[TransactionHash],
+    detail: StatusDetail,
+) -> Result<Vec<(StatusSource, TransactionStatusResponse)>, GetGatewayTransactionError> {
+    let (statuses, _) = resolve_statuses_with_replies(context, transaction_hashes, detail).await?;
+    Ok(statuses)
+}
+
+/// Same as [resolve_statuses], but also returns the gateway's reply for each transaction whose
+/// status the gateway was queried for.
+async fn resolve_statuses_with_replies(
+    context: RpcContext,
+    transaction_hashes: &[TransactionHash],
+    detail: StatusDetail,
+) -> Result<
+    (
+        Vec<(StatusSource, TransactionStatusResponse)>,
+        Vec<Option<GatewayReply>>,
+    ),
+    GetGatewayTransactionError,
+> {
+    let mut replies = vec![None; transaction_hashes.len()];
+
+    // Check in pending block.
+    //
+    // A miss is not conclusive, and neither is a hit while the pending data is stale, as sync
+    // commits a block to storage before clearing the pending data. These are therefore checked
+    // against storage below. The pending data cannot fail to be read, as its lock is never
+    // poisoned.
+    let mut statuses = Vec::with_capacity(transaction_hashes.len());
+    let mut fresh_pending = vec![false; transaction_hashes.len()];
+    for (idx, hash) in transaction_hashes.iter().enumerate() {
+        // No transaction can have a zero hash, which is usually sent by mistake. Answer it
+        // without any I/O.
+        if *hash == TransactionHash::ZERO {
+            statuses.push(Some((
+                StatusSource::Validation,
+                TransactionStatusResponse {
+                    gateway_consulted: Some(false),
+                    ..TransactionStatusResponse::new(TransactionStatus::NotReceived)
+                },
+            )));
+            continue;
+        }
+
+        let status = match &context.pending_data {
+            Some(pending) => pending.block_containing(hash).await.map(|block| {
+                fresh_pending[idx] = !context.storage_head.is_stale(block.parent_hash);
+                let status = pending_tx_status(&block, hash);
+                let status = TransactionStatusResponse {
+                    finality_status: context.pending_status_policy.apply(status.finality_status),
+                    ..status
+                };
+                (StatusSource::Pending, status)
+            }),
+            None => None,
+        };
+        statuses.push(status);
+    }
+
+    // Check the gateway's pending state instead, if so configured.
+    if consults_gateway_pending(&context) {
+        for (idx, hash) in unresolved_hashes(transaction_hashes, &statuses) {
+            let status =
+                gateway_pending_status(context.status_gateway(), context.gateway_timeout, hash)
+                    .await;
+
+            if let Some((status, reply)) = status {
+                replies[idx] = Some(reply);
+                merge_status(
+                    &mut statuses[idx],
+                    StatusSource::Gateway,
+                    TransactionStatusResponse::new(context.pending_status_policy.apply(status)),
+                );
+            }
+        }
+    }
+
+    // Check database.
+    //
+    // Storage takes precedence over stale pending data, so that a transaction never regresses
+    // from an accepted status back to a pending one while sync has committed the pending block
+    // but not yet updated the pending data. Pending data built on the latest stored block is not
+    // checked against storage. This is skipped entirely if all transactions were resolved by
+    // validation, by fresh pending data or are definitely not in storage, e.g. because storage is
+    // still empty, which avoids the cost of dispatching to the blocking thread pool.
+    let mut unresolved = outranked_hashes(transaction_hashes, &statuses, StatusSource::Database);
+    unresolved.retain(|(idx, _)| !fresh_pending[*idx]);
+    if context.empty_storage.is_empty() {
+        unresolved.clear();
+    }
+    unresolved.retain(|(_, hash)| context.known_transactions.might_contain(hash));
+    if !unresolved.is_empty() {
+        let l1_head = context.l1_head.borrow().clone();
+        let reorg_risk = context.reorg_risk;
+        let cache = context.database_status_cache.clone();
+        let lookup = |connection_kind, unresolved: Vec<(usize, TransactionHash)>| {
+            let l1_head = l1_head.clone();
+            let cache = cache.clone();
+            with_db_tx(context.storage.clone(), connection_kind, move |db_tx| {
+                stored_statuses(
+                    &db_tx,
+                    unresolved,
+                    l1_head.as_ref(),
+                    reorg_risk,
+                    detail,
+                    &cache,
+                )
+            })
+        };
+        let mut db_statuses = lookup(ConnectionKind::Replica, unresolved).await?;
+
+        // A read replica may lag behind the primary, so transactions missing from it are looked up
+        // in the primary as well.
+        if context.storage.has_replica() {
+            let missing = db_statuses
+                .iter()
+                .filter(|(_, status)| status.is_none())
+                .map(|(idx, _)| (*idx, transaction_hashes[*idx]))
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                tracing::trace!(
+                    count = missing.len(),
+                    "Looking up transactions missing from read replica in primary"
+                );
+                db_statuses.extend(lookup(ConnectionKind::Primary, missing).await?);
+            }
+        }
+
+        for (idx, status) in db_statuses {
+            if let Some(status) = status {
+                merge_status(&mut statuses[idx], StatusSource::Database, status);
+            }
+        }
+    }
+
+    // Without the gateway, transactions which are still unknown can only be assumed not received.
+    if !context.gateway_status_fallback_enabled {
+        for (idx, _) in unresolved_hashes(transaction_hashes, &statuses) {
+            merge_status(
+                &mut statuses[idx],
+                StatusSource::Default,
+                TransactionStatusResponse {
+                    gateway_consulted: Some(false),
+                    ..TransactionStatusResponse::new(TransactionStatus::NotReceived)
+                },
+            );
+        }
+    }
+
+    // Check gateway for rejected transactions.
+    let (indices, hashes): (Vec<_>, Vec<_>) = unresolved_hashes(transaction_hashes, &statuses)
+        .into_iter()
+        .unzip();
+    let results = gateway_statuses_with_fallback(&context, &hashes).await?;
+
+    let mut rejections = Vec::new();
+    for ((idx, hash), gateway) in indices.into_iter().zip(hashes).zip(results) {
+        replies[idx] = gateway.reply;
+
+        let rejection_reason = gateway
+            .rejection
+            .clone()
+            .filter(|reason| !reason.is_empty());
+        if let Some(reason) = gateway.rejection {
+            // Recorded before the rejection is persisted, so that the filter never misses it.
+            context.known_transactions.insert(hash);
+            context.empty_storage.clear();
+            rejections.push((hash, reason));
+        }
+
+        merge_status(
+            &mut statuses[idx],
+            StatusSource::Gateway,
+            TransactionStatusResponse {
+                rejection_reason,
+                gateway_consulted: (gateway.status == TransactionStatus::NotReceived)
+                    .then_some(true),
+                abort_reason: gateway.abort_reason,
+                ..TransactionStatusResponse::new(gateway.status)
+            },
+        );
+    }
+
+    // Persist rejections so that they can be served locally from now on.
+    if !rejections.is_empty() {
+        with_db_tx(
+            context.storage.clone(),
+            ConnectionKind::Primary,
+            move |db_tx| {
+                for (hash, reason) in rejections {
+                    db_tx
+                        .insert_rejected_transaction(hash, &reason)
+                        .context("Inserting rejected transaction")?;
+                }
+
+                db_tx.commit().context("Committing database transaction")
+            },
+        )
+        .await?;
+    }
+
+    let statuses = statuses
+        .into_iter()
+        .map(|status| status.expect("All statuses are resolved"))
+        .collect();
+    Ok((statuses, replies))
+}
+
+/// Whether the gateway's pending block stands in for this node's missing pending data.
+fn consults_gateway_pending(context: &RpcContext) -> bool {
+    context.pending_data.is_none() && context.gateway_pending_fallback
+}
+
+crate::error::generate_rpc_error_subset!(DatabaseError: DatabaseBusy, InconsistentState);
+
+impl From<DatabaseError> for GetGatewayTransactionError {
+    fn from(e: DatabaseError) -> Self {
+        match e {
+            DatabaseError::DatabaseBusy => Self::DatabaseBusy,
+            DatabaseError::InconsistentState => Self::InconsistentState,
+            DatabaseError::Internal(internal) => Self::Internal(internal),
+        }
+    }
+}
+
+/// Runs `f` with a new database transaction on the blocking thread pool.
+///
+/// Fails with [DatabaseError::DatabaseBusy] if the database is locked by another connection, so
+/// that clients know to retry, and with [DatabaseError::InconsistentState] if a transaction is
+/// stored without its block.
+///
+/// Within a JSON-RPC batch, the database connection is shared with the batch's other calls, unless
+/// `connection_kind` routes this to a read replica.
+///
+/// Dropping the returned future e.g. because the client disconnected cancels the database task,
+/// so that it returns its connection to the pool promptly. The task is skipped entirely if it has
+/// not acquired a connection yet, and interrupts its running query otherwise.
+pub(crate) async fn with_db_tx<T, F>(
+    storage: Storage,
+    connection_kind: ConnectionKind,
+    f: F,
+) -> Result<T, DatabaseError>
+where
+    T: Send + 'static,
+    F: FnOnce(pathfinder_storage::Transaction<'_>) -> anyhow::Result<T> + Send + 'static,
+{
+    let span = tracing::Span::current();
+
+    let cancellation = DbTaskCancellation::default();
+    let _cancel_on_drop = cancellation.cancel_on_drop();
+
+    let batch = BatchConnection::current();
+
+    let start = std::time::Instant::now();
+    let result = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        let run = |db: &mut pathfinder_storage::Connection| {
+            let _registration = cancellation.register(db)?;
+            let db_tx = db.transaction().context("Creating database transaction")?;
+
+            f(db_tx)
+        };
+
+        let replica = connection_kind == ConnectionKind::Replica && storage.has_replica();
+        match batch {
+            Some(batch) if !replica => batch.with_connection(&storage, run),
+            _ => {
+                let mut db = storage
+                    .connection_of_kind(connection_kind)
+                    .context("Opening database connection")?;
+                run(&mut db)
+            }
+        }
+    })
+    .await;
+    metrics::histogram!(METRIC_DB_DURATION, start.elapsed());
+
+    let result = result.context("Joining database task")?;
+    result.map_err(|e| {
+        if pathfinder_storage::is_busy(&e) {
+            tracing::debug!(error=%e, "Database is busy");
+            DatabaseError::DatabaseBusy
+        } else if e.downcast_ref::<DanglingTransaction>().is_some() {
+            DatabaseError::InconsistentState
+        } else {
+            DatabaseError::Internal(e)
+        }
+    })
+}
+
+/// Lets [with_db_tx] cancel its database task once the request it serves is dropped.
+#[derive(Clone, Default)]
+struct DbTaskCancellation(Arc<Mutex<DbTaskState>>);
+
+#[derive(Default)]
+struct DbTaskState {
+    cancelled: bool,
+    /// Interrupts the task's connection, which is only set while the task is using it.
+    interrupt: Option<pathfinder_storage::InterruptHandle>,
+}
+
+impl DbTaskCancellation {
+    fn lock(&self) -> std::sync::MutexGuard<'_, DbTaskState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Cancels the task once the returned guard is dropped.
+    fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+
+    /// Lets cancellation interrupt `connection` until the returned guard is dropped, which must
+    /// happen before the connection is returned to the pool. Fails if the task was already
+    /// cancelled.
+    fn register(
+        &self,
+        connection: &pathfinder_storage::Connection,
+    ) -> anyhow::Result<DbTaskRegistration> {
+        let mut state = self.lock();
+        anyhow::ensure!(!state.cancelled, "Database task was cancelled");
+        state.interrupt = Some(connection.interrupt_handle());
+
+        Ok(DbTaskRegistration(self.clone()))
+    }
+}
+
+struct CancelOnDrop(DbTaskCancellation);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.cancelled = true;
+        if let Some(interrupt) = state.interrupt.take() {
+            interrupt.interrupt();
+        }
+    }
+}
+
+struct DbTaskRegistration(DbTaskCancellation);
+
+impl Drop for DbTaskRegistration {
+    fn drop(&mut self) {
+        self.0.lock().interrupt = None;
+    }
+}
+
+/// Returns the status of each of the `unresolved` transactions which is in storage with the given
+/// `detail`, along with its index. Statuses are served from `cache` where possible.
+fn stored_statuses(
+    db_tx: &pathfinder_storage::Transaction<'_>,
+    unresolved: Vec<(usize, TransactionHash)>,
+    l1_head: Option<&EthereumStateUpdate>,
+    reorg_risk: ReorgRiskThresholds,
+    detail: StatusDetail,
+    cache: &DatabaseStatusCache,
+) -> anyhow::Result<Vec<(usize, Option<TransactionStatusResponse>)>> {
+    let l2_head = db_tx
+        .block_id(pathfinder_storage::BlockId::Latest)
+        .context("Fetching latest block")?;
+    let cache_l1_head = L1Head {
+        l2_block: db_tx.l1_l2_pointer().context("Fetching L1-L2 head")?,
+        l1_block: db_tx.l1_head().context("Fetching L1 head")?,
+        latest: l1_head.map(|head| (head.block_number, head.block_hash)),
+    };
+
+    unresolved
+        .into_iter()
+        .map(|(idx, hash)| {
+            let cached = l2_head.and_then(|head| cache.get(hash, head, cache_l1_head, detail));
+            if let Some(status) = cached {
+                return Ok((idx, Some(status)));
+            }
+
+            let status = db_tx_status(db_tx, hash, l1_head, reorg_risk, detail)?;
+            // Transactions which are not in storage yet are not cached, as they may be rejected
+            // without the head changing.
+            if let (Some(status), Some(head)) = (&status, l2_head) {
+                cache.insert(hash, head, cache_l1_head, detail, status.clone());
+            }
+            Ok((idx, status))
+        })
+        .collect()
+}
+
+/// Returns the transaction hashes and their indices which do not have a status yet.
+fn unresolved_hashes(
+    transaction_hashes: &[TransactionHash],
+    statuses: &[Option<(StatusSource, TransactionStatusResponse)>],
+) -> Vec<(usize, TransactionHash)> {
+    transaction_hashes
+        .iter()
+        .zip(statuses)
+        .enumerate()
+        .filter_map(|(idx, (hash, status))| status.is_none().then_some((idx, *hash)))
+        .collect()
+}
+
+/// Same as [unresolved_hashes] but also includes transactions whose status is from a source
+/// which `source` outranks, as it would supersede their status.
+fn outranked_hashes(
+    transaction_hashes: &[TransactionHash],
+    statuses: &[Option<(StatusSource, TransactionStatusResponse)>],
+    source: StatusSource,
+) -> Vec<(usize, TransactionHash)> {
+    transaction_hashes
+        .iter()
+        .zip(statuses)
+        .enumerate()
+        .filter_map(|(idx, (hash, status))| match status {
+            Some((existing, _)) if !source.outranks(*existing) => None,
+            _ => Some((idx, *hash)),
+        })
+        .collect()
+}
+
+/// Records `status` from `source` in `slot`, unless the slot already holds a status from a source
+/// which `source` does not outrank.
+///
+/// Every source after the pending data is merged through this, so that the reported status only
+/// depends on which sources know the transaction and not on the order they are consulted in.
+fn merge_status<T>(slot: &mut Option<(StatusSource, T)>, source: StatusSource, status: T) {
+    match slot {
+        Some((existing, _)) if !source.outranks(*existing) => {}
+        _ => *slot = Some((source, status)),
+    }
+}
+
+/// A transaction which is stored without the header of its block, which sync never commits.
+#[derive(Debug, thiserror::Error)]
+#[error("Transaction {transaction_hash} is stored without the header of its block {block_hash}")]
+struct DanglingTransaction {
+    transaction_hash: TransactionHash,
+    block_hash: BlockHash,
+}
+
+/// How much of a stored transaction's status is resolved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StatusDetail {
+    /// Only what a single query yields, i.e. the finality status, the transaction's block and
+    /// index, its reorg risk and the details of its L1 state update.
+    Finality,
+    /// Also the details derived from the transaction, its receipt and its block's header.
+    Full,
+}
+
+/// Returns the status of the transaction if it is in storage, with the given `detail`.
+///
+/// A transaction which storage considers accepted on L2 is reported as accepted on L1 if the
+/// in-memory `l1_head` already covers its block. Otherwise its reorg risk is derived from its
+/// block's distance to the L2 head, according to `reorg_risk`.
+///
+/// Fails with [DanglingTransaction] if the transaction's block header is missing, which is only
+/// noticed with [StatusDetail::Full].
+fn db_tx_status(
+    db_tx: &pathfinder_storage::Transaction<'_>,
+    transaction_hash: TransactionHash,
+    l1_head: Option<&EthereumStateUpdate>,
+    reorg_risk: ReorgRiskThresholds,
+    detail: StatusDetail,
+) -> anyhow::Result<Option<TransactionStatusResponse>> {
+    let stored = db_tx
+        .stored_transaction_status(transaction_hash)
+        .context("Querying transaction's status")?;
+
+    let Some(stored) = stored else {
+        let rejected = db_tx
+            .rejected_transaction_status(transaction_hash)
+            .context("Fetching rejected transaction from database")?;
+
+        // Rejections without a known reason are stored with an empty one.
+        return Ok(rejected.map(|reason| TransactionStatusResponse {
+            rejection_reason: Some(reason).filter(|reason| !reason.is_empty()),
+            ..TransactionStatusResponse::new(TransactionStatus::Rejected)
+        }));
+    };
+
+    let finality_status = match (stored.finality, l1_head) {
+        (pathfinder_storage::TransactionStatus::L1Accepted, _) => TransactionStatus::AcceptedOnL1,
+        (pathfinder_storage::TransactionStatus::L2Accepted, Some(l1_head))
+            if l1_head_covers(db_tx, l1_head, stored.block_number)? =>
+        {
+            TransactionStatus::AcceptedOnL1
+        }
+        (pathfinder_storage::TransactionStatus::L2Accepted, _) => TransactionStatus::AcceptedOnL2,
+    };
+
+    let (l1_confirmations, proven, l1_tx_hash) = match stored.finality {
+        pathfinder_storage::TransactionStatus::L1Accepted => {
+            (stored.l1_confirmations, stored.proven, stored.l1_tx_hash)
+        }
+        pathfinder_storage::TransactionStatus::L2Accepted => (None, None, None),
+    };
+
+    let unsafe_head_distance = match finality_status {
+        TransactionStatus::AcceptedOnL2 => Some(
+            stored
+                .latest_block_number
+                .get()
+                .saturating_sub(stored.block_number.get()),
+        ),
+        _ => None,
+    };
+
+    let status = TransactionStatusResponse {
+        l1_confirmations,
+        proven,
+        l1_tx_hash,
+        unsafe_head_distance,
+        reorg_risk: unsafe_head_distance.map(|distance| reorg_risk.risk(distance)),
+        transaction_index: Some(stored.index),
+        block: Some(TransactionBlock::Accepted {
+            hash: stored.block_hash,
+            number: stored.block_number,
+        }),
+        ..TransactionStatusResponse::new(finality_status)
+    };
+
+    match detail {
+        StatusDetail::Finality => Ok(Some(status)),
+        StatusDetail::Full => with_stored_details(db_tx, transaction_hash, status).map(Some),
+    }
+}
+
+/// Adds the details derived from the stored transaction, its receipt and its block's header to
+/// its `status`.
+///
+/// Fails with [DanglingTransaction] if the transaction's block header is missing.
+fn with_stored_details(
+    db_tx: &pathfinder_storage::Transaction<'_>,
+    transaction_hash: TransactionHash,
+    status: TransactionStatusResponse,
+) -> anyhow::Result<TransactionStatusResponse> {
+    let Some((transaction, receipt, block_hash)) = db_tx
+        .transaction_with_receipt(transaction_hash)
+        .context("Fetching transaction from database")?
+    else {
+        return Ok(status);
+    };
+
+    let Some(header) = db_tx
+        .block_header(block_hash.into())
+        .context("Fetching transaction's block header")?
+    else {
+        tracing::warn!(
+            %transaction_hash,
+            %block_hash,
+            "Transaction is stored without its block header"
+        );
+        return Err(DanglingTransaction {
+            transaction_hash,
+            block_hash,
+        }
+        .into());
+    };
+
+    // Blocks prior to Starknet 0.8 have no sequencer address, which sync stores as zero.
+    let sequencer_address =
+        Some(header.sequencer_address).filter(|address| *address != SequencerAddress::ZERO);
+
+    Ok(TransactionStatusResponse {
+        execution_status: Some(receipt.execution_status.into()),
+        revert_reason: revert_reason(&receipt),
+        actual_fee: receipt.actual_fee,
+        l2_to_l1_message_hashes: Some(
+            receipt
+                .l2_to_l1_messages
+                .iter()
+                .map(l2_to_l1_message_hash)
+                .collect(),
+        ),
+        transaction_version: Some(executed_version(&transaction)),
+        declared_class_hash: declared_class_hash(&transaction),
+        deployed_contract_address: deployed_contract_address(&transaction),
+        block_timestamp: Some(header.timestamp),
+        sequencer_address,
+        ..status
+    })
+}
+
+/// Returns true if `l1_head` includes the transaction's canonical block `block_number`, which is
+/// the case if the block is at or below the L1 head and the L1 head is part of this node's chain.
+fn l1_head_covers(
+    db_tx: &pathfinder_storage::Transaction<'_>,
+    l1_head: &EthereumStateUpdate,
+    block_number: BlockNumber,
+) -> anyhow::Result<bool> {
+    if block_number > l1_head.block_number {
+        return Ok(false);
+    }
+
+    let l2_hash = db_tx
+        .block_id(l1_head.block_number.into())
+        .context("Fetching L1 head's block hash")?
+        .map(|(_, hash)| hash);
+
+    Ok(l2_hash == Some(l1_head.block_hash))
+}
+
+/// A transaction status as reported by the gateway.
+#[derive(Debug)]
+struct GatewayStatus {
+    status: TransactionStatus,
+    /// The rejection reason if the gateway was queried and reported the transaction as rejected.
+    rejection: Option<String>,
+    /// The abort reason if the gateway was queried and reported the transaction as aborted.
+    abort_reason: Option<AbortReason>,
+    /// The gateway's reply, unless the status was served from the cache.
+    reply: Option<GatewayReply>,
+}
+
+/// The maximum number of concurrent gateway requests made by [gateway_statuses].
+const GATEWAY_CONCURRENCY: usize = 8;
+
+/// Queries the gateway for the statuses of transactions which are unknown to this node.
+///
+/// Stable results are served from the `cache` so that repeated queries for the same
+/// hash don't hit the gateway each time. The remaining transactions are fetched concurrently, and
+/// the query fails if the gateway does not respond within `timeout`.
+///
+/// Transient gateway failures are retried according to `retry`, as long as `timeout` allows.
+/// If they persist, they are reported as [GetGatewayTransactionError::GatewayUnavailable] so
+/// that clients know to retry later.
+async fn gateway_statuses(
+    gateway: &impl GatewayApi,
+    cache: &GatewayStatusCache,
+    timeout: Duration,
+    retry: GatewayRetry,
+    transaction_hashes: &[TransactionHash],
+) -> Result<Vec<GatewayStatus>, GetGatewayTransactionError> {
+    let mut statuses = transaction_hashes
+        .iter()
+        .map(|hash| {
+            cache.get(hash).map(|status| GatewayStatus {
+                status,
+                rejection: None,
+                abort_reason: None,
+                reply: None,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut attempt = 0;
+    loop {
+        let (indices, hashes): (Vec<_>, Vec<_>) = transaction_hashes
+            .iter()
+            .zip(&statuses)
+            .enumerate()
+            .filter_map(|(idx, (hash, status))| status.is_none().then_some((idx, *hash)))
+            .unzip();
+        if hashes.is_empty() {
+            break;
+        }
+
+        let start = std::time::Instant::now();
+        let results =
+            tokio::time::timeout_at(deadline, gateway.transactions(&hashes, GATEWAY_CONCURRENCY))
+                .await;
+        metrics::histogram!(METRIC_GATEWAY_DURATION, start.elapsed());
+
+        let results = results.map_err(|_| {
+            anyhow::anyhow!("Gateway did not respond within {}s", timeout.as_secs_f32())
+        })?;
+
+        let mut transient = None;
+        for ((idx, hash), result) in indices.into_iter().zip(hashes).zip(results) {
+            match result {
+                Ok(transaction) => statuses[idx] = Some(reply_status(cache, hash, transaction)),
+                Err(e) if is_transient(&e) => transient = Some(e),
+                // A reply which is truncated, or lacks fields such as the status, is not something
+                // a retry can be expected to fix.
+                Err(SequencerError::ReqwestError(e)) if e.is_decode() => {
+                    return Err(GetGatewayTransactionError::Internal(
+                        anyhow::Error::new(e)
+                            .context(format!("Malformed gateway reply for transaction {hash}")),
+                    ))
+                }
+                Err(e) => {
+                    let detail = GatewayErrorDetail::new(&e);
+                    return Err(GetGatewayTransactionError::Internal(
+                        anyhow::Error::new(e)
+                            .context(detail)
+                            .context("Fetching transaction from gateway"),
+                    ));
+                }
+            }
+        }
+
+        // Only the failed queries are retried.
+        let Some(e) = transient else {
+            break;
+        };
+        attempt += 1;
+        match retry.delay(attempt) {
+            // Rather give up now than sleep past the deadline.
+            Some(delay) if tokio::time::Instant::now() + delay < deadline => {
+                tracing::debug!(error=%e, %attempt, "Retrying gateway transaction query");
+                tokio::time::sleep(delay).await;
+            }
+            _ => return Err(GetGatewayTransactionError::GatewayUnavailable),
+        }
+    }
+
+    Ok(statuses
+        .into_iter()
+        .map(|status| status.expect("All statuses are fetched"))
+        .collect())
+}
+
+/// Same as [gateway_statuses] for the status gateway, but fails over to the context's fallback
+/// gateways in order while the gateway queried last is unavailable.
+///
+/// Definitive answers, including rejections, are never failed over. Each gateway is granted the
+/// full `gateway_timeout`.
+///
+/// Queries fail fast with [GetGatewayTransactionError::GatewayUnavailable] while the context's
+/// circuit breaker is open, i.e. after all gateways kept failing. This includes queries which
+/// could have been served from the cache.
+async fn gateway_statuses_with_fallback(
+    context: &RpcContext,
+    transaction_hashes: &[TransactionHash],
+) -> Result<Vec<GatewayStatus>, GetGatewayTransactionError> {
+    if transaction_hashes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let breaker = &context.gateway_circuit_breaker;
+    if !breaker.allow() {
+        return Err(GetGatewayTransactionError::GatewayUnavailable);
+    }
+
+    let gateways = std::iter::once(context.status_gateway()).chain(&context.fallback_gateways);
+    let mut result = Err(GetGatewayTransactionError::GatewayUnavailable);
+    for (idx, gateway) in gateways.enumerate() {
+        if idx > 0 {
+            tracing::debug!(fallback=%idx, "Status gateway unavailable, failing over");
+        }
+        result = gateway_statuses(
+            gateway,
+            &context.gateway_status_cache,
+            context.gateway_timeout,
+            context.gateway_retry,
+            transaction_hashes,
+        )
+        .await;
+        if !matches!(result, Err(GetGatewayTransactionError::GatewayUnavailable)) {
+            break;
+        }
+    }
+
+    match result {
+        Ok(_) => breaker.record_success(),
+        Err(_) => breaker.record_failure(),
+    }
+    result
+}
+
+/// Maps the gateway's reply for a transaction to its [GatewayStatus], caching it if stable.
+fn reply_status(
+    cache: &GatewayStatusCache,
+    transaction_hash: TransactionHash,
+    transaction: starknet_gateway_types::reply::Transaction,
+) -> GatewayStatus {
+    let status: TransactionStatus = transaction.status.into();
+
+    cache.insert(transaction_hash, status);
+
+    // Only a genuine REJECTED reply is a rejection. The gateway replies NOT_RECEIVED for hashes it
+    // does not know at all, which must never be recorded as rejected.
+    let failure = transaction.transaction_failure_reason.clone();
+    let (rejection, abort_reason) = match status {
+        TransactionStatus::Rejected => (
+            Some(
+                failure
+                    .map(|failure| failure.error_message)
+                    .unwrap_or_default(),
+            ),
+            None,
+        ),
+        TransactionStatus::Aborted => (None, failure.map(AbortReason::from)),
+        _ => (None, None),
+    };
+
+    GatewayStatus {
+        status,
+        rejection,
+        abort_reason,
+        reply: Some(transaction),
+    }
+}
+
+/// Returns the transaction's status along with the gateway's reply if the gateway reports it as
+/// not yet included in a block.
+///
+/// This is a best-effort check -- gateway failures are logged and treated as if the transaction
+/// was not pending.
+async fn gateway_pending_status(
+    gateway: &impl GatewayApi,
+    timeout: Duration,
+    transaction_hash: TransactionHash,
+) -> Option<(TransactionStatus, GatewayReply)> {
+    let start = std::time::Instant::now();
+    let transaction = tokio::time::timeout(timeout, gateway.transaction(transaction_hash)).await;
+    metrics::histogram!(METRIC_GATEWAY_DURATION, start.elapsed());
+
+    let transaction = match transaction {
+        Ok(Ok(transaction)) => transaction,
+        Ok(Err(error)) => {
+            tracing::debug!(%error, "Querying gateway for pending transaction failed");
+            return None;
+        }
+        Err(_) => {
+            tracing::debug!("Querying gateway for pending transaction timed out");
+            return None;
+        }
+    };
+
+    match TransactionStatus::from(transaction.status) {
+        status @ (TransactionStatus::Received
+        | TransactionStatus::Pending
+        | TransactionStatus::PreConfirmed) => Some((status, transaction)),
+        _ => None,
+    }
+}
+
+/// Returns true if the gateway error is likely to go away when retried i.e. the gateway
+/// is overloaded or could not be reached.
+fn is_transient(e: &SequencerError) -> bool {
+    use http::StatusCode;
+
+    match e {
+        SequencerError::ReqwestError(e) => {
+            e.is_connect()
+                || e.is_timeout()
+                || e.status().map_or(false, |status| {
+                    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+                })
+        }
+        SequencerError::StarknetError(_) | SequencerError::InvalidStarknetErrorVariant => false,
+    }
+}
+
+/// Returns the status of a transaction which is part of the pending block.
+///
+/// The pending block is the only block which may not yet be finalized by the sequencer -- all
+/// blocks in storage are. Its transactions are therefore [TransactionStatus::PreConfirmed] if the
+/// sequencer has marked the block as such, and [TransactionStatus::Pending] otherwise.
+fn pending_tx_status(block: &PendingBlock, tx_hash: &TransactionHash) -> TransactionStatusResponse {
+    let finality_status = match block.status {
+        starknet_gateway_types::reply::Status::PreConfirmed => TransactionStatus::PreConfirmed,
+        _ => TransactionStatus::Pending,
+    };
+
+    let receipt = block
+        .transaction_receipts
+        .iter()
+        .find(|receipt| &receipt.transaction_hash == tx_hash);
+    let position = block
+        .transactions
+        .iter()
+        .position(|transaction| &transaction.hash() == tx_hash);
+    let transaction = position.map(|position| &block.transactions[position]);
+
+    TransactionStatusResponse {
+        execution_status: receipt.map(|receipt| receipt.execution_status.into()),
+        revert_reason: receipt.and_then(revert_reason),
+        actual_fee: receipt.and_then(|receipt| receipt.actual_fee),
+        transaction_version: transaction.map(executed_version),
+        declared_class_hash: transaction.and_then(declared_class_hash),
+        deployed_contract_address: transaction.and_then(deployed_contract_address),
+        block_timestamp: Some(block.timestamp),
+        sequencer_address: Some(block.sequencer_address),
+        transaction_index: position.and_then(|position| TransactionIndex::new(position as u64)),
+        block: Some(TransactionBlock::Pending {
+            parent_hash: block.parent_hash,
+        }),
+        ..TransactionStatusResponse::new(finality_status)
+    }
+}
+
+/// Returns the version the transaction was executed as, i.e. without the query version bit which
+/// only transactions meant for simulation carry.
+fn executed_version(transaction: &GatewayTransaction) -> TransactionVersion {
+    let mut version = [0u8; 32];
+    version[16..].copy_from_slice(&transaction.version().without_query_version().to_be_bytes());
+    TransactionVersion(H256(version))
+}
+
+fn declared_class_hash(transaction: &GatewayTransaction) -> Option<ClassHash> {
+    match transaction {
+        GatewayTransaction::Declare(DeclareTransaction::V0(t) | DeclareTransaction::V1(t)) => {
+            Some(t.class_hash)
+        }
+        GatewayTransaction::Declare(DeclareTransaction::V2(t)) => Some(t.class_hash),
+        _ => None,
+    }
+}
+
+fn deployed_contract_address(transaction: &GatewayTransaction) -> Option<ContractAddress> {
+    match transaction {
+        GatewayTransaction::Deploy(t) => Some(t.contract_address),
+        GatewayTransaction::DeployAccount(t) => Some(t.contract_address),
+        _ => None,
+    }
+}
+
+/// Returns the hash of an L2 to L1 message, as computed by the Starknet core contract on L1:
+/// the keccak256 of the sender, recipient, payload length and payload, each as a 32 byte word.
+fn l2_to_l1_message_hash(message: &L2ToL1Message) -> H256 {
+    use sha3::{Digest, Keccak256};
+
+    let mut to_address = [0u8; 32];
+    to_address[12..].copy_from_slice(message.to_address.0.as_bytes());
+    let mut payload_len = [0u8; 32];
+    payload_len[24..].copy_from_slice(&(message.payload.len() as u64).to_be_bytes());
+
+    let mut keccak = Keccak256::default();
+    keccak.update(message.from_address.get().as_be_bytes());
+    keccak.update(to_address);
+    keccak.update(payload_len);
+    for element in &message.payload {
+        keccak.update(element.0.as_be_bytes());
+    }
+
+    H256(keccak.finalize().into())
+}
+
+/// Returns the receipt's revert reason, but only if the transaction was actually reverted.
+fn revert_reason(receipt: &Receipt) -> Option<String> {
+    match receipt.execution_status {
+        GatewayExecutionStatus::Reverted => receipt.revert_error.clone(),
+        GatewayExecutionStatus::Succeeded => None,
+    }
+}
+
+/// A transaction's finality and execution status.
+#[serde_with::serde_as]
+#[serde_with::skip_serializing_none]
+#[derive(Clone, Debug, serde::Serialize, PartialEq)]
+pub struct TransactionStatusResponse {
+    pub finality_status: TransactionStatus,
+    /// Only available for transactions which have been executed i.e. those which are
+    /// pending or in storage.
+    pub execution_status: Option<ExecutionStatus>,
+    /// Only available for reverted transactions.
+    pub revert_reason: Option<String>,
+    /// The number of L1 blocks which have confirmed the state update containing the transaction.
+    /// Only available for transactions accepted on L1.
+    pub l1_confirmations: Option<u64>,
+    /// Whether the proof of the state update containing the transaction has been verified on L1.
+    /// Only available for transactions accepted on L1, if this node tracks their proof status.
+    pub proven: Option<bool>,
+    /// The hash of the L1 transaction which submitted the state update containing the
+    /// transaction. Only available for transactions accepted on L1, if this node persisted it.
+    pub l1_tx_hash: Option<H256>,
+    /// Only available with `include_proof` set, for transactions accepted on L1.
+    pub block_proof: Option<BlockHashProof>,
+    /// The number of blocks by which the L2 head is ahead of the transaction's block. Only
+    /// available for transactions accepted on L2 which are in storage.
+    pub unsafe_head_distance: Option<u64>,
+    /// Derived from `unsafe_head_distance`, and only available alongside it.
+    pub reorg_risk: Option<ReorgRisk>,
+    /// Only available for rejected transactions, if the gateway reported why.
+    pub rejection_reason: Option<String>,
+    /// Whether the gateway confirmed that a `NOT_RECEIVED` transaction is unknown, as opposed to
+    /// this node only checking its local state e.g. as the gateway fallback is disabled. Only
+    /// available for transactions which are not received.
+    pub gateway_consulted: Option<bool>,
+    /// Only available for aborted transactions, if the gateway reported why.
+    pub abort_reason: Option<AbortReason>,
+    /// The fee paid for the transaction. Only available for transactions which are in storage, or
+    /// pending if the sequencer has already reported it.
+    pub actual_fee: Option<Fee>,
+    /// The hashes of the L2 to L1 messages sent by the transaction, as which the messages can be
+    /// consumed on L1. Only available for transactions which are in storage.
+    pub l2_to_l1_message_hashes: Option<Vec<H256>>,
+    /// The version of the transaction, without the query version bit. Only available for
+    /// transactions which are pending or in storage.
+    #[serde_as(as = "Option<TransactionVersionAsHexStr>")]
+    pub transaction_version: Option<TransactionVersion>,
+    /// The class declared by the transaction. Only available for declare transactions which are
+    /// pending or in storage.
+    pub declared_class_hash: Option<ClassHash>,
+    /// The address of the contract deployed by the transaction. Only available for deploy and
+    /// deploy account transactions which are pending or in storage.
+    pub deployed_contract_address: Option<ContractAddress>,
+    /// The timestamp of the block containing the transaction, which for pending transactions is
+    /// the pending block's. Only available alongside `block`.
+    pub block_timestamp: Option<BlockTimestamp>,
+    /// The address of the sequencer which produced the block containing the transaction. Only
+    /// available alongside `block`, and not for blocks which predate sequencer addresses.
+    pub sequencer_address: Option<SequencerAddress>,
+    /// The position of the transaction within its block. Only available alongside `block`.
+    pub transaction_index: Option<TransactionIndex>,
+    /// The block containing the transaction. Only available for transactions which are pending
+    /// or in storage.
+    pub block: Option<TransactionBlock>,
+}
+
+impl TransactionStatusResponse {
+    /// A response with the given finality status and none of the optional details.
+    pub fn new(finality_status: TransactionStatus) -> Self {
+        Self {
+            finality_status,
+            execution_status: None,
+            revert_reason: None,
+            l1_confirmations: None,
+            proven: None,
+            l1_tx_hash: None,
+            block_proof: None,
+            unsafe_head_distance: None,
+            reorg_risk: None,
+            rejection_reason: None,
+            gateway_consulted: None,
+            abort_reason: None,
+            actual_fee: None,
+            l2_to_l1_message_hashes: None,
+            transaction_version: None,
+            declared_class_hash: None,
+            deployed_contract_address: None,
+            block_timestamp: None,
+            sequencer_address: None,
+            transaction_index: None,
+            block: None,
+        }
+    }
+}
+
+/// The block containing a transaction.
+#[derive(Clone, Debug, serde::Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum TransactionBlock {
+    Accepted {
+        hash: BlockHash,
+        number: BlockNumber,
+    },
+    /// The pending block has neither a hash nor a number yet.
+    Pending { parent_hash: BlockHash },
+}
+
+/// A coarse indicator of how likely an L2 accepted transaction's block is to be reorged before
+/// it is accepted on L1.
+#[derive(Copy, Clone, Debug, serde::Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReorgRisk {
+    /// The block is buried deep enough below the L2 head.
+    Low,
+    /// The block is close to the L2 head.
+    Medium,
+}
+
+/// Why the sequencer aborted a transaction's block.
+#[derive(Clone, Debug, serde::Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AbortReason {
+    /// The block was reorged away.
+    Reorg,
+    /// The block failed validation.
+    InvalidBlock,
+    /// Any other reason, as described by the gateway.
+    Other(String),
+}
+
+impl From<GatewayFailure> for AbortReason {
+    fn from(failure: GatewayFailure) -> Self {
+        match failure.code.as_str() {
+            "REORG" => Self::Reorg,
+            "INVALID_BLOCK" => Self::InvalidBlock,
+            _ => Self::Other(failure.error_message),
+        }
+    }
+}
+
+/// Why the gateway rejected a transaction, as categorized from its rejection reason.
+#[derive(Copy, Clone, Debug, serde::Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RejectionReason {
+    /// The max fee does not cover the transaction's minimal cost.
+    InsufficientMaxFee,
+    /// The account's balance does not cover the max fee.
+    InsufficientAccountBalance,
+    /// The nonce is not the account's current one.
+    InvalidNonce,
+    /// The account's `__validate__` entry point failed.
+    ValidationFailure,
+    /// Any other reason, including reasons of unknown format.
+    Other,
+}
+
+impl From<&str> for RejectionReason {
+    /// The gateway's messages are free form, so this matches the phrases they have in common
+    /// across Starknet versions. A balance rejection mentions the max fee, so fee rejections are
+    /// matched on how they describe the fee falling short instead. Fee rejections do mention
+    /// validation though, so they are checked before validation failures.
+    fn from(reason: &str) -> Self {
+        let reason = reason.to_lowercase();
+        if reason.contains("balance") {
+            Self::InsufficientAccountBalance
+        } else if reason.contains("too low")
+            || reason.contains("minimum fee")
+            || reason.contains("minimal transaction cost")
+        {
+            Self::InsufficientMaxFee
+        } else if reason.contains("nonce") {
+            Self::InvalidNonce
+        } else if reason.contains("validat") {
+            Self::ValidationFailure
+        } else {
+            Self::Other
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, serde::Serialize, PartialEq)]
+pub enum ExecutionStatus {
+    #[serde(rename = "SUCCEEDED")]
+    Succeeded,
+    #[serde(rename = "REVERTED")]
+    Reverted,
+}
+
+impl From<starknet_gateway_types::reply::transaction::ExecutionStatus> for ExecutionStatus {
+    fn from(value: starknet_gateway_types::reply::transaction::ExecutionStatus) -> Self {
+        use starknet_gateway_types::reply::transaction::ExecutionStatus;
+        match value {
+            ExecutionStatus::Succeeded => Self::Succeeded,
+            ExecutionStatus::Reverted => Self::Reverted,
+        }
+    }
+}
+
+/// A transaction's status, which is serialized as its uppercase name as required by the spec.
+/// See [LowercaseStatus] for the lowercase name, both of which are accepted when deserializing.
+///
+/// Each status also has a stable [code](TransactionStatus::code), which is its discriminant.
+#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TransactionStatus {
+    #[serde(rename = "NOT_RECEIVED", alias = "not_received")]
+    NotReceived = 0,
+    #[serde(rename = "RECEIVED", alias = "received")]
+    Received = 1,
+    #[serde(rename = "PENDING", alias = "pending")]
+    Pending = 2,
+    #[serde(rename = "PRE_CONFIRMED", alias = "pre_confirmed")]
+    PreConfirmed = 3,
+    #[serde(rename = "REJECTED", alias = "rejected")]
+    Rejected = 4,
+    #[serde(rename = "ACCEPTED_ON_L1", alias = "accepted_on_l1")]
+    AcceptedOnL1 = 5,
+    #[serde(rename = "ACCEPTED_ON_L2", alias = "accepted_on_l2")]
+    AcceptedOnL2 = 6,
+    #[serde(rename = "REVERTED", alias = "reverted")]
+    Reverted = 7,
+    #[serde(rename = "ABORTED", alias = "aborted")]
+    Aborted = 8,
+    /// The gateway reported a status which this version of pathfinder does not recognize.
+    #[serde(rename = "UNKNOWN", alias = "unknown")]
+    Unknown = 9,
+}
+
+/// Orders statuses by their [finality rank](TransactionStatus::finality_rank).
+impl Ord for TransactionStatus {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.finality_rank().cmp(&other.finality_rank())
+    }
+}
+
+impl PartialOrd for TransactionStatus {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Serializes a [TransactionStatus] as its lowercase name e.g. `accepted_on_l1`, for tooling which
+/// expects it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LowercaseStatus(pub TransactionStatus);
+
+impl serde::Serialize for LowercaseStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_lowercase())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for LowercaseStatus {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <TransactionStatus as serde::Deserialize>::deserialize(deserializer).map(Self)
+    }
+}
+
+impl TransactionStatus {
+    /// The status' numeric code, as returned by [get_transaction_status_code]:
+    ///
+    /// | code | status           |
+    /// |------|------------------|
+    /// | 0    | `NOT_RECEIVED`   |
+    /// | 1    | `RECEIVED`       |
+    /// | 2    | `PENDING`        |
+    /// | 3    | `PRE_CONFIRMED`  |
+    /// | 4    | `REJECTED`       |
+    /// | 5    | `ACCEPTED_ON_L1` |
+    /// | 6    | `ACCEPTED_ON_L2` |
+    /// | 7    | `REVERTED`       |
+    /// | 8    | `ABORTED`        |
+    /// | 9    | `UNKNOWN`        |
+    ///
+    /// Codes are never reassigned, so that clients can rely on them across versions.
+    pub fn code(&self) -> u8 {
+        *self as u8
+    }
+
+    /// How far along a transaction with this status is, which is how statuses are [ordered](Ord):
+    ///
+    /// | rank | status           | tier     |
+    /// |------|------------------|----------|
+    /// | 0    | `UNKNOWN`        | unknown  |
+    /// | 1    | `NOT_RECEIVED`   | progress |
+    /// | 2    | `RECEIVED`       | progress |
+    /// | 3    | `PENDING`        | progress |
+    /// | 4    | `PRE_CONFIRMED`  | progress |
+    /// | 5    | `ACCEPTED_ON_L2` | progress |
+    /// | 6    | `ACCEPTED_ON_L1` | progress |
+    /// | 7    | `REVERTED`       | terminal |
+    /// | 8    | `REJECTED`       | terminal |
+    /// | 9    | `ABORTED`        | terminal |
+    ///
+    /// [TransactionStatus::Unknown] says nothing about the transaction, so it ranks lowest. The
+    /// terminal statuses end a transaction's life instead of progressing it, and rank above all
+    /// others as no other status follows them. Their order among each other carries no meaning,
+    /// but is fixed so that sorting statuses is deterministic.
+    ///
+    /// Unlike [codes](TransactionStatus::code), ranks may change between versions.
+    pub fn finality_rank(&self) -> u8 {
+        match self {
+            TransactionStatus::Unknown => 0,
+            TransactionStatus::NotReceived => 1,
+            TransactionStatus::Received => 2,
+            TransactionStatus::Pending => 3,
+            TransactionStatus::PreConfirmed => 4,
+            TransactionStatus::AcceptedOnL2 => 5,
+            TransactionStatus::AcceptedOnL1 => 6,
+            TransactionStatus::Reverted => 7,
+            TransactionStatus::Rejected => 8,
+            TransactionStatus::Aborted => 9,
+        }
+    }
+
+    /// Whether a transaction with this status has reached `wanted`, or can no longer reach it as
+    /// its status is final. Statuses which are not part of a transaction's acceptance, such as
+    /// [TransactionStatus::NotReceived], are considered reached right away.
+    fn reaches(self, wanted: TransactionStatus) -> bool {
+        fn progress(status: TransactionStatus) -> Option<u8> {
+            match status {
+                TransactionStatus::Received => Some(1),
+                TransactionStatus::Pending => Some(2),
+                TransactionStatus::PreConfirmed => Some(3),
+                TransactionStatus::AcceptedOnL2 => Some(4),
+                TransactionStatus::AcceptedOnL1 => Some(5),
+                _ => None,
+            }
+        }
+
+        let is_final = matches!(
+            self,
+            TransactionStatus::AcceptedOnL1
+                | TransactionStatus::Rejected
+                | TransactionStatus::Reverted
+                | TransactionStatus::Aborted
+        );
+
+        match (progress(self), progress(wanted)) {
+            _ if is_final => true,
+            (_, None) => true,
+            (Some(progress), Some(wanted)) => progress >= wanted,
+            (None, Some(_)) => false,
+        }
+    }
+
+    /// The status' lowercase name, which is also used as its metric label.
+    fn as_lowercase(&self) -> &'static str {
+        match self {
+            TransactionStatus::NotReceived => "not_received",
+            TransactionStatus::Received => "received",
+            TransactionStatus::Pending => "pending",
+            TransactionStatus::PreConfirmed => "pre_confirmed",
+            TransactionStatus::Rejected => "rejected",
+            TransactionStatus::AcceptedOnL1 => "accepted_on_l1",
+            TransactionStatus::AcceptedOnL2 => "accepted_on_l2",
+            TransactionStatus::Reverted => "reverted",
+            TransactionStatus::Aborted => "aborted",
+            TransactionStatus::Unknown => "unknown",
+        }
+    }
+
+    /// The inverse of the conversion from the gateway's status, for mocking gateway replies with
+    /// a given status.
+    ///
+    /// [TransactionStatus::Unknown] has no inverse, as it stands for any status reported by the
+    /// gateway which this version of pathfinder does not recognize.
+    #[cfg(test)]
+    fn as_gateway_status(&self) -> Option<starknet_gateway_types::reply::Status> {
+        use starknet_gateway_types::reply::Status;
+        let status = match self {
+            Self::NotReceived => Status::NotReceived,
+            Self::Received => Status::Received,
+            Self::Pending => Status::Pending,
+            Self::PreConfirmed => Status::PreConfirmed,
+            Self::Rejected => Status::Rejected,
+            Self::AcceptedOnL1 => Status::AcceptedOnL1,
+            Self::AcceptedOnL2 => Status::AcceptedOnL2,
+            Self::Reverted => Status::Reverted,
+            Self::Aborted => Status::Aborted,
+            Self::Unknown => return None,
+        };
+
+        Some(status)
+    }
+}
+
+impl From<starknet_gateway_types::reply::Status> for TransactionStatus {
+    fn from(value: starknet_gateway_types::reply::Status) -> Self {
+        use starknet_gateway_types::reply::Status;
+        match value {
+            Status::NotReceived => Self::NotReceived,
+            Status::Received => Self::Received,
+            Status::Pending => Self::Pending,
+            Status::PreConfirmed => Self::PreConfirmed,
+            Status::Rejected => Self::Rejected,
+            Status::AcceptedOnL1 => Self::AcceptedOnL1,
+            Status::AcceptedOnL2 => Self::AcceptedOnL2,
+            Status::Reverted => Self::Reverted,
+            Status::Aborted => Self::Aborted,
+            // The raw status is only known, and logged, when deserializing the gateway's reply.
+            Status::Unknown => Self::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::{felt, felt_bytes};
+
+    use super::*;
+
+    const NO_RETRY: GatewayRetry = GatewayRetry {
+        max_attempts: 1,
+        base_delay: Duration::ZERO,
+    };
+
+    /// An input for `transaction_hash` with all flags unset, which tests override as needed.
+    fn input(transaction_hash: TransactionHash) -> GetGatewayTransactionInput {
+        GetGatewayTransactionInput {
+            transaction_hash,
+            include_history: false,
+            check_superseded: None,
+            at_l1_block: None,
+            at_l2_block: None,
+            verify: false,
+            lowercase: false,
+            wait_for: None,
+            timeout_ms: None,
+            local_only: false,
+            include_proof: false,
+            skip_pending: false,
+        }
+    }
+
+    /// Forwards only [GatewayApi::transaction], so that the default [GatewayApi::transactions]
+    /// is used even for mocked gateways which only expect the former.
+    struct Unbatched<'a, G>(&'a G);
+
+    #[async_trait::async_trait]
+    impl<G: GatewayApi> GatewayApi for Unbatched<'_, G> {
+        async fn transaction(
+            &self,
+            transaction_hash: TransactionHash,
+        ) -> Result<starknet_gateway_types::reply::Transaction, SequencerError> {
+            self.0.transaction(transaction_hash).await
+        }
+    }
+
+    /// Queries the gateway for the status of a single transaction.
+    async fn gateway_status(
+        gateway: &impl GatewayApi,
+        cache: &GatewayStatusCache,
+        timeout: Duration,
+        retry: GatewayRetry,
+        transaction_hash: TransactionHash,
+    ) -> Result<GatewayStatus, GetGatewayTransactionError> {
+        let gateway = Unbatched(gateway);
+        let mut statuses =
+            gateway_statuses(&gateway, cache, timeout, retry, &[transaction_hash]).await?;
+        Ok(statuses.pop().expect("One status per transaction hash"))
+    }
+
+    #[tokio::test]
+    async fn deprecated_method_warns_once_per_context() {
+        const METHOD: &str = "pathfinder_getTransactionStatus";
+        const SUCCESSOR: &str = "starknet_getTransactionStatus";
+
+        let context = RpcContext::for_tests();
+        let input = || input(TransactionHash(felt_bytes!(b"txn 1")));
+
+        // The deprecated method keeps working.
+        for _ in 0..2 {
+            let status = get_transaction_status(context.clone(), input())
+                .await
+                .unwrap();
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+
+        // The calls above already logged the warning, whereas another context still would.
+        assert!(!context
+            .status_method_deprecation
+            .warn_once(METHOD, SUCCESSOR));
+        assert!(RpcContext::for_tests()
+            .status_method_deprecation
+            .warn_once(METHOD, SUCCESSOR));
+    }
+
+    #[tokio::test]
+    async fn committed_pending_block_defers_to_database() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let tx_hash = TransactionHash(felt_bytes!(b"pending tx hash 0"));
+        let input = || input(tx_hash);
+
+        let status = get_transaction_status(context.clone(), input())
+            .await
+            .unwrap();
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput::new(TransactionStatus::Pending)
+        );
+
+        // As if sync had committed the pending block to storage but not yet updated the pending
+        // data.
+        let pending = context.pending_data.as_ref().unwrap();
+        let pending = pending.block().await.unwrap();
+        let storage = context.storage.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut db = storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let header = db_tx
+                .block_header(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+                .unwrap()
+                .child_builder()
+                .finalize_with_hash(BlockHash(felt_bytes!(b"committed pending")));
+            db_tx.insert_block_header(&header).unwrap();
+
+            let data = pending
+                .transactions
+                .iter()
+                .cloned()
+                .zip(pending.transaction_receipts.iter().cloned())
+                .collect::<Vec<_>>();
+            db_tx
+                .insert_transaction_data(header.hash, header.number, &data)
+                .unwrap();
+            db_tx.commit().unwrap();
+        })
+        .await
+        .unwrap();
+
+        let status = get_transaction_status(context, input()).await.unwrap();
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+        );
+    }
+
+    #[tokio::test]
+    async fn committed_reversion_outranks_pending_resubmission() {
+        use starknet_gateway_types::reply::transaction::ExecutionStatus as GatewayStatus;
+
+        let context = RpcContext::for_tests_with_pending().await;
+        let block_hash = BlockHash(felt_bytes!(b"block 1"));
+        let tx_hash = TransactionHash(felt_bytes!(b"txn 1"));
+
+        // Revert the transaction in its committed block, which is not the latest one.
+        let mut db = context.storage.connection().unwrap();
+        let db_tx = db.transaction().unwrap();
+        let mut data = db_tx
+            .transaction_data_for_block(block_hash.into())
+            .unwrap()
+            .unwrap();
+        let (transaction, receipt) = data
+            .iter_mut()
+            .find(|(tx, _)| tx.hash() == tx_hash)
+            .unwrap();
+        receipt.execution_status = GatewayStatus::Reverted;
+        let resubmission = (transaction.clone(), receipt.clone());
+        db_tx
+            .insert_transaction_data(block_hash, BlockNumber::new_or_panic(1), &data)
+            .unwrap();
+        db_tx.commit().unwrap();
+
+        // Resubmit it under the same hash to the pending block.
+        let pending = context.pending_data.as_ref().unwrap();
+        let block = pending.block().await.unwrap();
+        let state_update = pending.state_update().await.unwrap();
+        let mut block = (*block).clone();
+        block.transactions.push(resubmission.0);
+        block.transaction_receipts.push(Receipt {
+            execution_status: GatewayStatus::Succeeded,
+            ..resubmission.1
+        });
+        pending.set(block.into(), state_update).await;
+
+        let input = input(tx_hash);
+        let status = get_transaction_status_details(context, input)
+            .await
+            .unwrap();
+
+        assert_eq!(status.finality_status, TransactionStatus::AcceptedOnL2);
+        assert_eq!(status.execution_status, Some(ExecutionStatus::Reverted));
+        assert_eq!(
+            status.block,
+            Some(TransactionBlock::Accepted {
+                hash: block_hash,
+                number: BlockNumber::new_or_panic(1),
+            })
+        );
+    }
+
+    #[test]
+    fn status_precedence_ranks_all_sources() {
+        let all = [
+            StatusSource::Validation,
+            StatusSource::Pending,
+            StatusSource::Database,
+            StatusSource::Gateway,
+            StatusSource::Default,
+        ];
+        for source in all {
+            assert!(STATUS_PRECEDENCE.contains(&source));
+        }
+        assert!(StatusSource::Database.outranks(StatusSource::Pending));
+        assert!(!StatusSource::Pending.outranks(StatusSource::Database));
+    }
+
+    /// Property tests of status precedence, driven by generated sequences of state transitions.
+    ///
+    /// The sequences are generated from fixed seeds, so that a failure can be reproduced from the
+    /// seed in its message.
+    mod precedence_properties {
+        use super::*;
+        use pathfinder_common::TransactionIndex;
+        use pathfinder_storage::BlockId;
+        use rand::rngs::StdRng;
+        use rand::seq::SliceRandom;
+        use rand::{Rng, SeedableRng};
+        use starknet_gateway_types::reply::transaction as gateway;
+        use starknet_gateway_types::reply::PendingStateUpdate;
+
+        const SEEDS: std::ops::Range<u64> = 0..32;
+        const STEPS: usize = 10;
+
+        fn hash() -> TransactionHash {
+            TransactionHash(felt_bytes!(b"generated tx"))
+        }
+
+        /// The stages a transaction goes through, in order.
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+        enum Stage {
+            NotReceived,
+            Pending,
+            Committed,
+            L1Accepted,
+        }
+
+        impl Stage {
+            fn next(self) -> Self {
+                match self {
+                    Stage::NotReceived => Stage::Pending,
+                    Stage::Pending => Stage::Committed,
+                    Stage::Committed | Stage::L1Accepted => Stage::L1Accepted,
+                }
+            }
+
+            fn expected(self) -> TransactionStatus {
+                match self {
+                    Stage::NotReceived => TransactionStatus::NotReceived,
+                    Stage::Pending => TransactionStatus::Pending,
+                    Stage::Committed => TransactionStatus::AcceptedOnL2,
+                    Stage::L1Accepted => TransactionStatus::AcceptedOnL1,
+                }
+            }
+        }
+
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        struct Transition {
+            stage: Stage,
+            /// Whether the pending block still lists the transaction once it is committed, as it
+            /// does until sync replaces the pending data.
+            stale_pending: bool,
+        }
+
+        /// Generates transitions which may stay at a stage or skip some, but never go back.
+        fn transitions(rng: &mut StdRng) -> Vec<Transition> {
+            let mut stage = Stage::NotReceived;
+            (0..STEPS)
+                .map(|_| {
+                    for _ in 0..rng.gen_range(0..=2) {
+                        stage = stage.next();
+                    }
+                    Transition {
+                        stage,
+                        stale_pending: stage >= Stage::Committed && rng.gen_bool(0.5),
+                    }
+                })
+                .collect()
+        }
+
+        /// A node whose storage and pending data are brought to each generated transition.
+        struct Node {
+            context: RpcContext,
+            pending_block: PendingBlock,
+            pending_state_update: Arc<PendingStateUpdate>,
+            transaction: (gateway::Transaction, gateway::Receipt),
+            stage: Stage,
+        }
+
+        impl Node {
+            async fn new() -> Self {
+                let context = RpcContext::for_tests_with_pending()
+                    .await
+                    .with_gateway_status_fallback(false);
+                let pending = context.pending_data.as_ref().unwrap();
+                let pending_block = (*pending.block().await.unwrap()).clone();
+                let pending_state_update = pending.state_update().await.unwrap();
+
+                let transaction = gateway::Transaction::Invoke(gateway::InvokeTransaction::V1(
+                    gateway::InvokeTransactionV1 {
+                        calldata: vec![],
+                        sender_address: ContractAddress::new_or_panic(felt_bytes!(b"sender")),
+                        max_fee: Fee(felt!("0x100")),
+                        signature: vec![],
+                        nonce: TransactionNonce(felt!("0x0")),
+                        transaction_hash: hash(),
+                    },
+                ));
+                let receipt = gateway::Receipt {
+                    actual_fee: None,
+                    events: vec![],
+                    execution_resources: None,
+                    execution_status: gateway::ExecutionStatus::Succeeded,
+                    l1_to_l2_consumed_message: None,
+                    l2_to_l1_messages: vec![],
+                    revert_error: None,
+                    transaction_hash: hash(),
+                    transaction_index: TransactionIndex::new_or_panic(0),
+                };
+
+                Self {
+                    context,
+                    pending_block,
+                    pending_state_update,
+                    transaction: (transaction, receipt),
+                    stage: Stage::NotReceived,
+                }
+            }
+
+            /// Storage only ever moves forward, while the pending block is replaced every time.
+            async fn apply(&mut self, transition: Transition) {
+                let mut block = self.pending_block.clone();
+                if transition.stage == Stage::Pending || transition.stale_pending {
+                    block.transactions.push(self.transaction.0.clone());
+                    block.transaction_receipts.push(self.transaction.1.clone());
+                }
+                self.context
+                    .pending_data
+                    .as_ref()
+                    .unwrap()
+                    .set(block.into(), self.pending_state_update.clone())
+                    .await;
+
+                let mut db = self.context.storage.connection().unwrap();
+                let db_tx = db.transaction().unwrap();
+                while self.stage < transition.stage {
+                    self.stage = self.stage.next();
+                    let latest = db_tx.block_header(BlockId::Latest).unwrap().unwrap();
+                    match self.stage {
+                        Stage::Committed => {
+                            let header = latest
+                                .child_builder()
+                                .finalize_with_hash(BlockHash(felt_bytes!(b"generated block")));
+                            db_tx.insert_block_header(&header).unwrap();
+                            db_tx
+                                .insert_transaction_data(
+                                    header.hash,
+                                    header.number,
+                                    &[self.transaction.clone()],
+                                )
+                                .unwrap();
+                        }
+                        Stage::L1Accepted => {
+                            db_tx.update_l1_l2_pointer(Some(latest.number)).unwrap();
+                            // Cached statuses are only invalidated by a new head, which L1 would
+                            // not catch up to the transaction's block without.
+                            let header = latest
+                                .child_builder()
+                                .finalize_with_hash(BlockHash(felt_bytes!(b"next block")));
+                            db_tx.insert_block_header(&header).unwrap();
+                        }
+                        Stage::NotReceived | Stage::Pending => {}
+                    }
+                }
+                db_tx.commit().unwrap();
+            }
+
+            async fn resolve(&self) -> TransactionStatus {
+                resolve_status(self.context.clone(), hash())
+                    .await
+                    .unwrap()
+                    .finality_status
+            }
+        }
+
+        /// Resolves the status after each transition of the sequence generated from `seed`.
+        async fn resolve_sequence(seed: u64) -> Vec<(Transition, TransactionStatus)> {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut node = Node::new().await;
+
+            let mut resolved = Vec::new();
+            for transition in transitions(&mut rng) {
+                node.apply(transition).await;
+                resolved.push((transition, node.resolve().await));
+            }
+            resolved
+        }
+
+        #[tokio::test]
+        async fn finality_is_monotonic() {
+            for seed in SEEDS {
+                let resolved = resolve_sequence(seed).await;
+                for pair in resolved.windows(2) {
+                    assert!(pair[0].1 <= pair[1].1, "seed {seed}: {resolved:?}");
+                }
+            }
+        }
+
+        #[tokio::test]
+        async fn resolution_is_deterministic() {
+            for seed in SEEDS {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut node = Node::new().await;
+
+                for transition in transitions(&mut rng) {
+                    node.apply(transition).await;
+                    let status = node.resolve().await;
+
+                    // Once more from the cache, and once without it.
+                    assert_eq!(node.resolve().await, status, "seed {seed}: {transition:?}");
+                    let uncached = node
+                        .context
+                        .clone()
+                        .with_database_status_cache(Default::default());
+                    let uncached = resolve_status(uncached, hash())
+                        .await
+                        .unwrap()
+                        .finality_status;
+                    assert_eq!(uncached, status, "seed {seed}: {transition:?}");
+                }
+
+                assert_eq!(
+                    resolve_sequence(seed).await,
+                    resolve_sequence(seed).await,
+                    "seed {seed}"
+                );
+            }
+        }
+
+        /// In particular, committed finality outranks a stale pending block.
+        #[tokio::test]
+        async fn furthest_stage_is_reported() {
+            for seed in SEEDS {
+                for (transition, status) in resolve_sequence(seed).await {
+                    assert_eq!(
+                        status,
+                        transition.stage.expected(),
+                        "seed {seed}: {transition:?}"
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn merging_is_order_independent() {
+            for seed in SEEDS {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut sources = STATUS_PRECEDENCE.to_vec();
+                sources.shuffle(&mut rng);
+                sources.truncate(rng.gen_range(1..=sources.len()));
+
+                let mut slot = None;
+                for source in &sources {
+                    merge_status(&mut slot, *source, source.as_str());
+                }
+
+                let highest = STATUS_PRECEDENCE
+                    .into_iter()
+                    .find(|source| sources.contains(source))
+                    .unwrap();
+                assert_eq!(
+                    slot,
+                    Some((highest, highest.as_str())),
+                    "seed {seed}: {sources:?}"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn zero_hash_skips_io() {
+        let context = RpcContext::for_tests();
+
+        let storage = context.storage.clone();
+        let before = storage.acquired_connections();
+        let input = input(TransactionHash::ZERO);
+        let status = get_transaction_status(context, input).await.unwrap();
+
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+        );
+        assert_eq!(storage.acquired_connections(), before);
+    }
+
+    #[tokio::test]
+    async fn l1_accepted() {
+        let context = RpcContext::for_tests();
+        // This transaction is in block 0 which is L1 accepted.
+        let tx_hash = TransactionHash(felt_bytes!(b"txn 0"));
+        let input = input(tx_hash);
+        let status = get_transaction_status(context, input).await.unwrap();
+
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL1)
+        );
+    }
+
+    #[tokio::test]
+    async fn l2_accepted() {
+        let context = RpcContext::for_tests();
+        // This transaction is in block 1 which is not L1 accepted.
+        let tx_hash = TransactionHash(felt_bytes!(b"txn 1"));
+        let input = input(tx_hash);
+        let status = get_transaction_status(context, input).await.unwrap();
+
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+        );
+    }
+
+    #[tokio::test]
+    async fn pending() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let tx_hash = TransactionHash(felt_bytes!(b"pending tx hash 0"));
+        let input = input(tx_hash);
+        let status = get_transaction_status(context, input).await.unwrap();
+
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput::new(TransactionStatus::Pending)
+        );
+    }
+
+    #[tokio::test]
+    async fn fresh_pending_skips_database() {
+        use crate::transaction_status::StorageHead;
+
+        let context = RpcContext::for_tests_with_pending().await;
+        let storage_head = StorageHead::load(&context.storage).unwrap();
+        let context = context.with_storage_head(storage_head);
+
+        let storage = context.storage.clone();
+        let before = storage.acquired_connections();
+        let status = resolve_status(context, TransactionHash(felt_bytes!(b"pending tx hash 0")))
+            .await
+            .unwrap();
+
+        assert_eq!(status.finality_status, TransactionStatus::Pending);
+        assert_eq!(storage.acquired_connections(), before);
+    }
+
+    #[tokio::test]
+    async fn pending_miss_falls_through_to_database() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let tx_hash = TransactionHash(felt_bytes!(b"txn 1"));
+        let input = input(tx_hash);
+        let status = get_transaction_status(context, input).await.unwrap();
+
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+        );
+    }
+
+    #[tokio::test]
+    async fn skip_pending() {
+        let context = RpcContext::for_tests_with_pending()
+            .await
+            .with_gateway_status_fallback(false);
+        let input = GetGatewayTransactionInput {
+            skip_pending: true,
+            ..input(TransactionHash(felt_bytes!(b"pending tx hash 0")))
+        };
+        let status = get_transaction_status(context, input).await.unwrap();
+
+        // The transaction is only part of the pending block.
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+        );
+    }
+
+    #[tokio::test]
+    async fn pre_confirmed() {
+        let context = RpcContext::for_tests_with_pending().await;
+
+        // Mark the pending block as not yet finalized by the sequencer.
+        let pending = context.pending_data.as_ref().unwrap();
+        let mut block = (*pending.block().await.unwrap()).clone();
+        block.status = starknet_gateway_types::reply::Status::PreConfirmed;
+        let state_update = pending.state_update().await.unwrap();
+        pending.set(std::sync::Arc::new(block), state_update).await;
+
+        let tx_hash = TransactionHash(felt_bytes!(b"pending tx hash 0"));
+        let status = get_transaction_status(context.clone(), input(tx_hash))
+            .await
+            .unwrap();
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput::new(TransactionStatus::PreConfirmed)
+        );
+
+        // Transactions from finalized blocks are unaffected.
+        let tx_hash = TransactionHash(felt_bytes!(b"txn 1"));
+        let status = get_transaction_status(context, input(tx_hash))
+            .await
+            .unwrap();
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+        );
+    }
+
+    mod l1_head {
+        use super::*;
+
+        /// Returns the status of "txn 1", which storage only knows as accepted on L2, given the
+        /// in-memory L1 head.
+        async fn status_with_l1_head(number: u64, hash: &[u8]) -> GetTransactionStatusOutput {
+            let l1_head = EthereumStateUpdate {
+                block_number: BlockNumber::new_or_panic(number),
+                block_hash: BlockHash(felt_bytes!(hash)),
+                ..Default::default()
+            };
+            let context =
+                RpcContext::for_tests().with_l1_head(tokio::sync::watch::channel(Some(l1_head)).1);
+            let input = input(TransactionHash(felt_bytes!(b"txn 1")));
+
+            get_transaction_status(context, input).await.unwrap()
+        }
+
+        #[tokio::test]
+        async fn ahead_of_storage() {
+            let status = status_with_l1_head(1, b"block 1").await;
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL1)
+            );
+
+            let status = status_with_l1_head(2, b"latest").await;
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL1)
+            );
+        }
+
+        #[tokio::test]
+        async fn behind_block() {
+            let status = status_with_l1_head(0, b"genesis").await;
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+
+        #[tokio::test]
+        async fn other_chain() {
+            let status = status_with_l1_head(1, b"other block 1").await;
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+    }
+
+    mod at_l2_block {
+        use super::*;
+
+        async fn status_at(hash: &[u8], l2_block: u64) -> GetTransactionStatusOutput {
+            let input = GetGatewayTransactionInput {
+                at_l2_block: Some(BlockNumber::new_or_panic(l2_block)),
+                ..input(TransactionHash(felt_bytes!(hash)))
+            };
+
+            get_transaction_status(RpcContext::for_tests(), input)
+                .await
+                .unwrap()
+        }
+
+        #[tokio::test]
+        async fn at_transactions_block() {
+            let status = status_at(b"txn 1", 1).await;
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+
+        #[tokio::test]
+        async fn after_transactions_block() {
+            let status = status_at(b"txn 1", 2).await;
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+
+        #[tokio::test]
+        async fn before_transactions_block() {
+            let status = status_at(b"txn 1", 0).await;
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+            );
+        }
+
+        #[tokio::test]
+        async fn pending_at_stored_block() {
+            let input = GetGatewayTransactionInput {
+                at_l2_block: Some(BlockNumber::GENESIS),
+                ..input(TransactionHash(felt_bytes!(b"pending tx hash 0")))
+            };
+            let context = RpcContext::for_tests_with_pending().await;
+
+            let status = get_transaction_status(context, input).await.unwrap();
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+            );
+        }
+
+        #[tokio::test]
+        async fn pending_at_future_block() {
+            let input = GetGatewayTransactionInput {
+                at_l2_block: Some(BlockNumber::new_or_panic(1000)),
+                ..input(TransactionHash(felt_bytes!(b"pending tx hash 0")))
+            };
+            let context = RpcContext::for_tests_with_pending().await;
+
+            let status = get_transaction_status(context, input).await.unwrap();
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::Pending)
+            );
+        }
+    }
+
+    mod at_l1_block {
+        use super::*;
+
+        /// Returns the status of `hash` as of `l1_block`, with genesis reaching L1 at L1 block 100
+        /// and "block 1" at 200.
+        async fn status_at(hash: &[u8], l1_block: u64) -> GetTransactionStatusOutput {
+            let context = RpcContext::for_tests();
+
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            for (number, l1_block_number) in [(0, 100), (1, 200)] {
+                let header = db_tx
+                    .block_header(BlockNumber::new_or_panic(number).into())
+                    .unwrap()
+                    .unwrap();
+                db_tx
+                    .upsert_l1_state(&EthereumStateUpdate {
+                        state_root: header.state_commitment,
+                        block_number: header.number,
+                        block_hash: header.hash,
+                        l1_block_number: Some(l1_block_number),
+                        l1_tx_hash: None,
+                    })
+                    .unwrap();
+            }
+            db_tx
+                .update_l1_l2_pointer(Some(BlockNumber::new_or_panic(1)))
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            let input = GetGatewayTransactionInput {
+                at_l1_block: Some(l1_block),
+                ..input(TransactionHash(felt_bytes!(hash)))
+            };
+
+            get_transaction_status(context, input).await.unwrap()
+        }
+
+        #[tokio::test]
+        async fn before_block_reached_l1() {
+            let status = status_at(b"txn 1", 150).await;
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+
+        #[tokio::test]
+        async fn after_block_reached_l1() {
+            let status = status_at(b"txn 1", 200).await;
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL1)
+            );
+        }
+
+        #[tokio::test]
+        async fn before_any_l1_state() {
+            let status = status_at(b"txn 0", 50).await;
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+
+        #[tokio::test]
+        async fn later_block_not_yet_on_l1() {
+            // "latest" never reached L1.
+            let status = status_at(b"txn 3", 200).await;
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+    }
+
+    mod reconcile {
+        use super::*;
+
+        async fn reconcile(expected: TransactionStatus) -> ReconciledTransactionStatus {
+            let input = ReconcileTransactionStatusInput {
+                transaction_hash: TransactionHash(felt_bytes!(b"txn 1")),
+                expected,
+            };
+            reconcile_transaction_status(RpcContext::for_tests(), input)
+                .await
+                .unwrap()
+        }
+
+        #[tokio::test]
+        async fn ahead() {
+            assert_eq!(
+                reconcile(TransactionStatus::Pending).await,
+                ReconciledTransactionStatus {
+                    actual: TransactionStatus::AcceptedOnL2,
+                    matches: false,
+                    drift_direction: StatusDrift::Ahead,
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn behind() {
+            assert_eq!(
+                reconcile(TransactionStatus::AcceptedOnL1).await,
+                ReconciledTransactionStatus {
+                    actual: TransactionStatus::AcceptedOnL2,
+                    matches: false,
+                    drift_direction: StatusDrift::Behind,
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn equal() {
+            assert_eq!(
+                reconcile(TransactionStatus::AcceptedOnL2).await,
+                ReconciledTransactionStatus {
+                    actual: TransactionStatus::AcceptedOnL2,
+                    matches: true,
+                    drift_direction: StatusDrift::Equal,
+                }
+            );
+        }
+
+        #[test]
+        fn ordering_follows_progress_rather_than_codes() {
+            assert!(TransactionStatus::AcceptedOnL1 > TransactionStatus::AcceptedOnL2);
+            assert!(TransactionStatus::Received > TransactionStatus::NotReceived);
+            assert!(TransactionStatus::Rejected > TransactionStatus::AcceptedOnL1);
+        }
+    }
+
+    mod read_replica {
+        use super::*;
+
+        /// Returns a context whose status queries are routed to an empty read replica, as if it
+        /// lagged behind the primary.
+        fn lagging_replica() -> (RpcContext, Storage) {
+            let replica = Storage::in_memory().unwrap();
+            let context = RpcContext::for_tests().with_gateway_status_fallback(false);
+            let storage = context.storage.clone().with_replica(replica.clone());
+            (context.with_storage(storage), replica)
+        }
+
+        #[tokio::test]
+        async fn missing_transaction_falls_back_to_primary() {
+            let (context, _replica) = lagging_replica();
+
+            let status = resolve_status(context, TransactionHash(felt_bytes!(b"txn 1")))
+                .await
+                .unwrap();
+            assert_eq!(status.finality_status, TransactionStatus::AcceptedOnL2);
+        }
+
+        #[tokio::test]
+        async fn served_from_replica() {
+            let (context, replica) = lagging_replica();
+
+            // Only the replica knows of the rejection.
+            let hash = TransactionHash(felt_bytes!(b"rejected on replica"));
+            let mut db = replica.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            db_tx.insert_rejected_transaction(hash, "").unwrap();
+            db_tx.commit().unwrap();
+
+            let status = resolve_status(context, hash).await.unwrap();
+            assert_eq!(status.finality_status, TransactionStatus::Rejected);
+        }
+    }
+
+    mod database_status_cache {
+        use super::*;
+
+        async fn status(context: &RpcContext) -> TransactionStatus {
+            resolve_status(context.clone(), TransactionHash(felt_bytes!(b"txn 1")))
+                .await
+                .unwrap()
+                .finality_status
+        }
+
+        #[tokio::test]
+        async fn new_l1_head_invalidates_cached_status() {
+            let context = RpcContext::for_tests();
+            assert_eq!(status(&context).await, TransactionStatus::AcceptedOnL2);
+
+            // "block 1" reaches L1, but the L2 head stays the same.
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            db_tx
+                .update_l1_l2_pointer(Some(BlockNumber::new_or_panic(1)))
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            assert_eq!(status(&context).await, TransactionStatus::AcceptedOnL1);
+        }
+
+        #[tokio::test]
+        async fn new_in_memory_l1_head_invalidates_cached_status() {
+            let (sender, receiver) = tokio::sync::watch::channel(None);
+            let context = RpcContext::for_tests().with_l1_head(receiver);
+            assert_eq!(status(&context).await, TransactionStatus::AcceptedOnL2);
+
+            // L1 sync reports "block 1" before storage is updated.
+            sender.send_replace(Some(EthereumStateUpdate {
+                block_number: BlockNumber::new_or_panic(1),
+                block_hash: BlockHash(felt_bytes!(b"block 1")),
+                ..Default::default()
+            }));
+
+            assert_eq!(status(&context).await, TransactionStatus::AcceptedOnL1);
+        }
+
+        #[tokio::test]
+        async fn new_head_invalidates_cached_status() {
+            let context = RpcContext::for_tests();
+            let distance = || async {
+                resolve_status(context.clone(), TransactionHash(felt_bytes!(b"txn 1")))
+                    .await
+                    .unwrap()
+                    .unsafe_head_distance
+            };
+            assert_eq!(distance().await, Some(1));
+
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let header = db_tx
+                .block_header(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+                .unwrap()
+                .child_builder()
+                .finalize_with_hash(BlockHash(felt_bytes!(b"new head")));
+            db_tx.insert_block_header(&header).unwrap();
+            db_tx.commit().unwrap();
+
+            assert_eq!(distance().await, Some(2));
+        }
+    }
+
+    mod details {
+        use super::*;
+
+        #[tokio::test]
+        async fn l1_accepted() {
+            let context = RpcContext::for_tests();
+            let input = input(TransactionHash(felt_bytes!(b"txn 0")));
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                status,
+                TransactionStatusResponse {
+                    execution_status: Some(ExecutionStatus::Succeeded),
+                    l2_to_l1_message_hashes: Some(vec![]),
+                    transaction_version: Some(TransactionVersion::ZERO),
+                    block_timestamp: Some(BlockTimestamp::new_or_panic(0)),
+                    transaction_index: Some(TransactionIndex::new_or_panic(0)),
+                    block: Some(TransactionBlock::Accepted {
+                        hash: BlockHash(felt_bytes!(b"genesis")),
+                        number: BlockNumber::GENESIS,
+                    }),
+                    ..TransactionStatusResponse::new(TransactionStatus::AcceptedOnL1)
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn l2_accepted() {
+            let context = RpcContext::for_tests();
+            let input = input(TransactionHash(felt_bytes!(b"txn 1")));
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                status,
+                TransactionStatusResponse {
+                    execution_status: Some(ExecutionStatus::Succeeded),
+                    unsafe_head_distance: Some(1),
+                    reorg_risk: Some(ReorgRisk::Medium),
+                    l2_to_l1_message_hashes: Some(vec![]),
+                    transaction_version: Some(TransactionVersion::ZERO),
+                    block_timestamp: Some(BlockTimestamp::new_or_panic(1)),
+                    sequencer_address: Some(SequencerAddress(felt_bytes!(&[1u8]))),
+                    transaction_index: Some(TransactionIndex::new_or_panic(0)),
+                    block: Some(TransactionBlock::Accepted {
+                        hash: BlockHash(felt_bytes!(b"block 1")),
+                        number: BlockNumber::new_or_panic(1),
+                    }),
+                    ..TransactionStatusResponse::new(TransactionStatus::AcceptedOnL2)
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn l2_to_l1_message_hashes() {
+            use pathfinder_common::{EthereumAddress, L2ToL1MessagePayloadElem};
+            use primitive_types::H160;
+
+            let context = RpcContext::for_tests();
+            let block_hash = BlockHash(felt_bytes!(b"block 1"));
+            let tx_hash = TransactionHash(felt_bytes!(b"txn 1"));
+
+            // Add two messages to the transaction's receipt.
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let mut data = db_tx
+                .transaction_data_for_block(block_hash.into())
+                .unwrap()
+                .unwrap();
+            let (_, receipt) = data
+                .iter_mut()
+                .find(|(tx, _)| tx.hash() == tx_hash)
+                .unwrap();
+            let message = |payload: Vec<u64>| L2ToL1Message {
+                from_address: ContractAddress::new_or_panic(felt!("0x1234")),
+                payload: payload
+                    .into_iter()
+                    .map(|element| L2ToL1MessagePayloadElem(stark_hash::Felt::from_u64(element)))
+                    .collect(),
+                to_address: EthereumAddress(H160::from_low_u64_be(0xabcd)),
+            };
+            receipt.l2_to_l1_messages = vec![message(vec![1, 2]), message(vec![])];
+            db_tx
+                .insert_transaction_data(block_hash, BlockNumber::new_or_panic(1), &data)
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            let input = input(tx_hash);
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            let hash = |hex: &str| H256::from_slice(&hex::decode(hex).unwrap());
+            assert_eq!(
+                status.l2_to_l1_message_hashes,
+                Some(vec![
+                    hash("5ad3ecbb5ddf39c85be973f23ca2ad9bd01f3c766c22ca0655182285d3891235"),
+                    hash("f6cb05743ad8fb7585902ceabb9441a917f2b7abdda1944f50a4dbdd1f11e5b6"),
+                ])
+            );
+        }
+
+        #[tokio::test]
+        async fn no_l2_to_l1_message_hashes_while_pending() {
+            let context = RpcContext::for_tests_with_pending().await;
+            let input = input(TransactionHash(felt_bytes!(b"pending tx hash 0")));
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(status.finality_status, TransactionStatus::Pending);
+            assert_eq!(status.l2_to_l1_message_hashes, None);
+        }
+
+        #[tokio::test]
+        async fn sequencer_address() {
+            let context = RpcContext::for_tests();
+            let sequencer_address = |hash: &'static [u8]| {
+                let context = context.clone();
+                async move {
+                    let input = input(TransactionHash(felt_bytes!(hash)));
+                    get_transaction_status_details(context, input)
+                        .await
+                        .unwrap()
+                        .sequencer_address
+                }
+            };
+
+            assert_eq!(
+                sequencer_address(b"txn 3").await,
+                Some(SequencerAddress(felt_bytes!(&[2u8])))
+            );
+            // The genesis block has no recorded sequencer address.
+            assert_eq!(sequencer_address(b"txn 0").await, None);
+        }
+
+        #[tokio::test]
+        async fn transaction_index() {
+            let context = RpcContext::for_tests_with_pending()
+                .await
+                .with_gateway_status_fallback(false);
+            let transaction_index = |hash: &'static [u8]| {
+                let context = context.clone();
+                async move {
+                    let input = input(TransactionHash(felt_bytes!(hash)));
+                    get_transaction_status_details(context, input)
+                        .await
+                        .unwrap()
+                        .transaction_index
+                }
+            };
+
+            let seeded: [&[u8]; 6] = [
+                b"txn 1",
+                b"txn 2",
+                b"txn 3",
+                b"txn 4 ",
+                b"txn 5",
+                b"pending tx hash 1",
+            ];
+            let expected = [0, 1, 0, 1, 2, 1];
+            for (hash, index) in seeded.into_iter().zip(expected) {
+                assert_eq!(
+                    transaction_index(hash).await,
+                    Some(TransactionIndex::new_or_panic(index))
+                );
+            }
+            assert_eq!(transaction_index(b"unknown").await, None);
+        }
+
+        #[tokio::test]
+        async fn block_timestamp() {
+            let context = RpcContext::for_tests();
+            let input = input(TransactionHash(felt_bytes!(b"txn 3")));
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                status.block,
+                Some(TransactionBlock::Accepted {
+                    hash: BlockHash(felt_bytes!(b"latest")),
+                    number: BlockNumber::new_or_panic(2),
+                })
+            );
+            assert_eq!(
+                status.block_timestamp,
+                Some(BlockTimestamp::new_or_panic(2))
+            );
+        }
+
+        #[tokio::test]
+        async fn actual_fee() {
+            let context = RpcContext::for_tests();
+            let block_hash = BlockHash(felt_bytes!(b"block 1"));
+            let tx_hash = TransactionHash(felt_bytes!(b"txn 1"));
+            let fee = Fee(felt!("0x1234"));
+
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let mut data = db_tx
+                .transaction_data_for_block(block_hash.into())
+                .unwrap()
+                .unwrap();
+            let (_, receipt) = data
+                .iter_mut()
+                .find(|(tx, _)| tx.hash() == tx_hash)
+                .unwrap();
+            receipt.actual_fee = Some(fee);
+            db_tx
+                .insert_transaction_data(block_hash, BlockNumber::new_or_panic(1), &data)
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            let input = input(tx_hash);
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(status.finality_status, TransactionStatus::AcceptedOnL2);
+            assert_eq!(status.actual_fee, Some(fee));
+        }
+
+        #[tokio::test]
+        async fn pending_without_actual_fee() {
+            let context = RpcContext::for_tests_with_pending().await;
+            let input = input(TransactionHash(felt_bytes!(b"pending tx hash 0")));
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(status.finality_status, TransactionStatus::Pending);
+            assert_eq!(status.actual_fee, None);
+        }
+
+        #[tokio::test]
+        async fn l1_confirmations() {
+            let context = RpcContext::for_tests();
+
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let genesis = db_tx
+                .block_header(pathfinder_common::BlockNumber::GENESIS.into())
+                .unwrap()
+                .unwrap();
+            db_tx
+                .upsert_l1_state(&pathfinder_ethereum::EthereumStateUpdate {
+                    state_root: genesis.state_commitment,
+                    block_number: genesis.number,
+                    block_hash: genesis.hash,
+                    l1_block_number: Some(10),
+                    l1_tx_hash: None,
+                })
+                .unwrap();
+            db_tx.update_l1_head(15).unwrap();
+            db_tx.commit().unwrap();
+
+            let input = input(TransactionHash(felt_bytes!(b"txn 0")));
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(status.finality_status, TransactionStatus::AcceptedOnL1);
+            assert_eq!(status.l1_confirmations, Some(5));
+        }
+
+        #[tokio::test]
+        async fn proven() {
+            let context = RpcContext::for_tests();
+            let input = || input(TransactionHash(felt_bytes!(b"txn 0")));
+
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let genesis = db_tx
+                .block_header(pathfinder_common::BlockNumber::GENESIS.into())
+                .unwrap()
+                .unwrap();
+            db_tx
+                .upsert_l1_state(&pathfinder_ethereum::EthereumStateUpdate {
+                    state_root: genesis.state_commitment,
+                    block_number: genesis.number,
+                    block_hash: genesis.hash,
+                    l1_block_number: Some(10),
+                    l1_tx_hash: None,
+                })
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            // The proof status is not tracked.
+            let status = get_transaction_status_details(context.clone(), input())
+                .await
+                .unwrap();
+            assert_eq!(status.finality_status, TransactionStatus::AcceptedOnL1);
+            assert_eq!(status.proven, None);
+
+            let db_tx = db.transaction().unwrap();
+            db_tx.set_l1_state_proven(genesis.number, false).unwrap();
+            db_tx.commit().unwrap();
+            let status = get_transaction_status_details(context.clone(), input())
+                .await
+                .unwrap();
+            assert_eq!(status.proven, Some(false));
+
+            // Use a new head, as statuses resolved from storage are cached until it changes.
+            let db_tx = db.transaction().unwrap();
+            db_tx.set_l1_state_proven(genesis.number, true).unwrap();
+            let header = db_tx
+                .block_header(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+                .unwrap()
+                .child_builder()
+                .finalize_with_hash(BlockHash(felt_bytes!(b"new head")));
+            db_tx.insert_block_header(&header).unwrap();
+            db_tx.commit().unwrap();
+            let status = get_transaction_status_details(context, input())
+                .await
+                .unwrap();
+            assert_eq!(status.proven, Some(true));
+        }
+
+        #[tokio::test]
+        async fn l1_tx_hash() {
+            let context = RpcContext::for_tests();
+            let l1_tx_hash = H256::from_low_u64_be(0xabcdef);
+
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let genesis = db_tx
+                .block_header(pathfinder_common::BlockNumber::GENESIS.into())
+                .unwrap()
+                .unwrap();
+            db_tx
+                .upsert_l1_state(&pathfinder_ethereum::EthereumStateUpdate {
+                    state_root: genesis.state_commitment,
+                    block_number: genesis.number,
+                    block_hash: genesis.hash,
+                    l1_block_number: Some(10),
+                    l1_tx_hash: Some(l1_tx_hash),
+                })
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            let input = input(TransactionHash(felt_bytes!(b"txn 0")));
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(status.finality_status, TransactionStatus::AcceptedOnL1);
+            assert_eq!(status.l1_tx_hash, Some(l1_tx_hash));
+        }
+
+        #[tokio::test]
+        async fn l2_accepted_is_not_proven() {
+            let context = RpcContext::for_tests();
+            let input = input(TransactionHash(felt_bytes!(b"txn 1")));
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(status.finality_status, TransactionStatus::AcceptedOnL2);
+            assert_eq!(status.proven, None);
+            assert_eq!(status.l1_tx_hash, None);
+        }
+
+        #[tokio::test]
+        async fn pending_reverted() {
+            use starknet_gateway_types::reply::transaction::ExecutionStatus as GatewayStatus;
+
+            let context = RpcContext::for_tests_with_pending().await;
+            let tx_hash = TransactionHash(felt_bytes!(b"pending tx hash 0"));
+
+            let pending = context.pending_data.as_ref().unwrap();
+            let mut block = (*pending.block().await.unwrap()).clone();
+            let receipt = block
+                .transaction_receipts
+                .iter_mut()
+                .find(|receipt| receipt.transaction_hash == tx_hash)
+                .unwrap();
+            receipt.execution_status = GatewayStatus::Reverted;
+            receipt.revert_error = Some("Out of gas".to_owned());
+            let state_update = pending.state_update().await.unwrap();
+            pending.set(std::sync::Arc::new(block), state_update).await;
+
+            let input = input(tx_hash);
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                status,
+                TransactionStatusResponse {
+                    execution_status: Some(ExecutionStatus::Reverted),
+                    revert_reason: Some("Out of gas".to_owned()),
+                    transaction_version: Some(TransactionVersion::ZERO),
+                    block_timestamp: Some(BlockTimestamp::new_or_panic(1234567)),
+                    sequencer_address: Some(SequencerAddress(felt_bytes!(
+                        b"pending sequencer address"
+                    ))),
+                    transaction_index: Some(TransactionIndex::new_or_panic(0)),
+                    block: Some(TransactionBlock::Pending {
+                        parent_hash: BlockHash(felt_bytes!(b"latest")),
+                    }),
+                    ..TransactionStatusResponse::new(TransactionStatus::Pending)
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn reverted() {
+            use starknet_gateway_types::reply::transaction::ExecutionStatus as GatewayStatus;
+
+            let context = RpcContext::for_tests();
+            let block_hash = BlockHash(felt_bytes!(b"block 1"));
+            let tx_hash = TransactionHash(felt_bytes!(b"txn 1"));
+
+            // Replace the transaction's receipt with a reverted one.
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let mut data = db_tx
+                .transaction_data_for_block(block_hash.into())
+                .unwrap()
+                .unwrap();
+            let (_, receipt) = data
+                .iter_mut()
+                .find(|(tx, _)| tx.hash() == tx_hash)
+                .unwrap();
+            receipt.execution_status = GatewayStatus::Reverted;
+            receipt.revert_error = Some("Out of gas".to_owned());
+            db_tx
+                .insert_transaction_data(block_hash, BlockNumber::new_or_panic(1), &data)
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            let input = input(tx_hash);
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                status,
+                TransactionStatusResponse {
+                    execution_status: Some(ExecutionStatus::Reverted),
+                    revert_reason: Some("Out of gas".to_owned()),
+                    unsafe_head_distance: Some(1),
+                    reorg_risk: Some(ReorgRisk::Medium),
+                    l2_to_l1_message_hashes: Some(vec![]),
+                    transaction_version: Some(TransactionVersion::ZERO),
+                    block_timestamp: Some(BlockTimestamp::new_or_panic(1)),
+                    sequencer_address: Some(SequencerAddress(felt_bytes!(&[1u8]))),
+                    transaction_index: Some(TransactionIndex::new_or_panic(0)),
+                    block: Some(TransactionBlock::Accepted {
+                        hash: block_hash,
+                        number: BlockNumber::new_or_panic(1),
+                    }),
+                    ..TransactionStatusResponse::new(TransactionStatus::AcceptedOnL2)
+                }
+            );
+        }
+
+        #[test]
+        fn serialization() {
+            let status = TransactionStatusResponse {
+                execution_status: Some(ExecutionStatus::Reverted),
+                revert_reason: Some("Out of gas".to_owned()),
+                unsafe_head_distance: Some(12),
+                reorg_risk: Some(ReorgRisk::Low),
+                transaction_version: Some(TransactionVersion::ONE),
+                block: Some(TransactionBlock::Accepted {
+                    hash: BlockHash(felt!("0x1")),
+                    number: BlockNumber::new_or_panic(2),
+                }),
+                ..TransactionStatusResponse::new(TransactionStatus::AcceptedOnL2)
+            };
+            let json = serde_json::to_value(status).unwrap();
+            assert_eq!(
+                json,
+                serde_json::json!({
+                    "finality_status": "ACCEPTED_ON_L2",
+                    "execution_status": "REVERTED",
+                    "revert_reason": "Out of gas",
+                    "unsafe_head_distance": 12,
+                    "reorg_risk": "low",
+                    "transaction_version": "0x1",
+                    "block": { "hash": "0x1", "number": 2 },
+                })
+            );
+
+            let status = TransactionStatusResponse {
+                execution_status: Some(ExecutionStatus::Succeeded),
+                block_timestamp: Some(BlockTimestamp::new_or_panic(1234567)),
+                block: Some(TransactionBlock::Pending {
+                    parent_hash: BlockHash(felt!("0x1")),
+                }),
+                ..TransactionStatusResponse::new(TransactionStatus::Pending)
+            };
+            let json = serde_json::to_value(status).unwrap();
+            assert_eq!(
+                json,
+                serde_json::json!({
+                    "finality_status": "PENDING",
+                    "execution_status": "SUCCEEDED",
+                    "block_timestamp": 1234567,
+                    "block": { "parent_hash": "0x1" },
+                })
+            );
+
+            let status = TransactionStatusResponse::new(TransactionStatus::Rejected);
+            let json = serde_json::to_value(status).unwrap();
+            assert_eq!(json, serde_json::json!({ "finality_status": "REJECTED" }));
+        }
+    }
+
+    mod transaction_version {
+        use super::*;
+        use pathfinder_common::{CasmHash, ClassHash, ContractAddressSalt, TransactionIndex};
+        use pathfinder_storage::BlockId;
+        use starknet_gateway_types::reply::transaction as gateway;
+
+        const INVOKE_V1: &[u8] = b"invoke v1";
+        pub(super) const DECLARE_V2: &[u8] = b"declare v2";
+        pub(super) const DEPLOY_ACCOUNT_QUERY: &[u8] = b"deploy account query";
+
+        fn sender() -> ContractAddress {
+            ContractAddress::new_or_panic(felt_bytes!(b"sender"))
+        }
+
+        fn receipt(hash: &[u8], index: u64) -> gateway::Receipt {
+            gateway::Receipt {
+                actual_fee: None,
+                events: vec![],
+                execution_resources: None,
+                execution_status: gateway::ExecutionStatus::Succeeded,
+                l1_to_l2_consumed_message: None,
+                l2_to_l1_messages: vec![],
+                revert_error: None,
+                transaction_hash: TransactionHash(felt_bytes!(hash)),
+                transaction_index: TransactionIndex::new_or_panic(index),
+            }
+        }
+
+        /// Returns a context with a block containing a transaction of each of the hashes above.
+        pub(super) fn setup() -> RpcContext {
+            let context = RpcContext::for_tests();
+
+            let invoke = gateway::Transaction::Invoke(gateway::InvokeTransaction::V1(
+                gateway::InvokeTransactionV1 {
+                    calldata: vec![],
+                    sender_address: sender(),
+                    max_fee: Fee(felt!("0x100")),
+                    signature: vec![],
+                    nonce: TransactionNonce(felt!("0x0")),
+                    transaction_hash: TransactionHash(felt_bytes!(INVOKE_V1)),
+                },
+            ));
+            let declare = gateway::Transaction::Declare(gateway::DeclareTransaction::V2(
+                gateway::DeclareTransactionV2 {
+                    class_hash: ClassHash(felt_bytes!(b"class")),
+                    max_fee: Fee(felt!("0x100")),
+                    nonce: TransactionNonce(felt!("0x1")),
+                    sender_address: sender(),
+                    signature: vec![],
+                    transaction_hash: TransactionHash(felt_bytes!(DECLARE_V2)),
+                    compiled_class_hash: CasmHash(felt_bytes!(b"casm")),
+                },
+            ));
+            let deploy_account =
+                gateway::Transaction::DeployAccount(gateway::DeployAccountTransaction {
+                    contract_address: ContractAddress::new_or_panic(felt_bytes!(b"account")),
+                    transaction_hash: TransactionHash(felt_bytes!(DEPLOY_ACCOUNT_QUERY)),
+                    max_fee: Fee(felt!("0x100")),
+                    version: TransactionVersion::ONE.with_query_version(),
+                    signature: vec![],
+                    nonce: TransactionNonce(felt!("0x0")),
+                    contract_address_salt: ContractAddressSalt(felt_bytes!(b"salt")),
+                    constructor_calldata: vec![],
+                    class_hash: ClassHash(felt_bytes!(b"class")),
+                });
+            let data = [
+                (invoke, receipt(INVOKE_V1, 0)),
+                (declare, receipt(DECLARE_V2, 1)),
+                (deploy_account, receipt(DEPLOY_ACCOUNT_QUERY, 2)),
+            ];
+
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let header = db_tx
+                .block_header(BlockId::Latest)
+                .unwrap()
+                .unwrap()
+                .child_builder()
+                .finalize_with_hash(BlockHash(felt_bytes!(b"versioned block")));
+            db_tx.insert_block_header(&header).unwrap();
+            db_tx
+                .insert_transaction_data(header.hash, header.number, &data)
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            context
+        }
+
+        async fn version(context: RpcContext, hash: &[u8]) -> Option<TransactionVersion> {
+            let input = input(TransactionHash(felt_bytes!(hash)));
+            get_transaction_status_details(context, input)
+                .await
+                .unwrap()
+                .transaction_version
+        }
+
+        #[tokio::test]
+        async fn legacy_v0() {
+            let version = version(RpcContext::for_tests(), b"txn 1").await;
+            assert_eq!(version, Some(TransactionVersion::ZERO));
+        }
+
+        #[tokio::test]
+        async fn v1() {
+            let version = version(setup(), INVOKE_V1).await;
+            assert_eq!(version, Some(TransactionVersion::ONE));
+        }
+
+        #[tokio::test]
+        async fn v2() {
+            let version = version(setup(), DECLARE_V2).await;
+            assert_eq!(version, Some(TransactionVersion::TWO));
+        }
+
+        #[tokio::test]
+        async fn query_version_bit_is_stripped() {
+            let version = version(setup(), DEPLOY_ACCOUNT_QUERY).await;
+            assert_eq!(version, Some(TransactionVersion::ONE));
+        }
+
+        #[tokio::test]
+        async fn pending() {
+            let context = RpcContext::for_tests_with_pending().await;
+            let version = version(context, b"pending tx hash 1").await;
+            assert_eq!(version, Some(TransactionVersion::ZERO));
+        }
+
+        #[tokio::test]
+        async fn unavailable_from_gateway() {
+            let context = RpcContext::for_tests();
+            let hash = TransactionHash(felt_bytes!(b"unknown tx"));
+            context
+                .gateway_status_cache
+                .insert(hash, TransactionStatus::NotReceived);
+
+            let version = version(context, b"unknown tx").await;
+            assert_eq!(version, None);
+        }
+    }
+
+    mod declared_and_deployed {
+        use super::transaction_version::{setup, DECLARE_V2, DEPLOY_ACCOUNT_QUERY};
+        use super::*;
+
+        async fn status(context: RpcContext, hash: &[u8]) -> TransactionStatusResponse {
+            let input = input(TransactionHash(felt_bytes!(hash)));
+            get_transaction_status_details(context, input)
+                .await
+                .unwrap()
+        }
+
+        #[tokio::test]
+        async fn declare() {
+            let status = status(setup(), DECLARE_V2).await;
+            assert_eq!(
+                status.declared_class_hash,
+                Some(ClassHash(felt_bytes!(b"class")))
+            );
+            assert_eq!(status.deployed_contract_address, None);
+        }
+
+        #[tokio::test]
+        async fn deploy_account() {
+            let status = status(setup(), DEPLOY_ACCOUNT_QUERY).await;
+            assert_eq!(status.declared_class_hash, None);
+            assert_eq!(
+                status.deployed_contract_address,
+                Some(ContractAddress::new_or_panic(felt_bytes!(b"account")))
+            );
+        }
+
+        #[tokio::test]
+        async fn pending_deploy() {
+            let context = RpcContext::for_tests_with_pending().await;
+            let status = status(context, b"pending tx hash 1").await;
+            assert_eq!(status.declared_class_hash, None);
+            assert_eq!(
+                status.deployed_contract_address,
+                Some(ContractAddress::new_or_panic(felt!("0x1122355")))
+            );
+        }
+
+        #[tokio::test]
+        async fn invoke() {
+            let status = status(RpcContext::for_tests(), b"txn 1").await;
+            assert_eq!(status.declared_class_hash, None);
+            assert_eq!(status.deployed_contract_address, None);
+        }
+    }
+
+    mod reorg_risk {
+        use super::*;
+
+        async fn reorg_risk(context: RpcContext, hash: &[u8]) -> Option<ReorgRisk> {
+            let input = input(TransactionHash(felt_bytes!(hash)));
+
+            get_transaction_status_details(context, input)
+                .await
+                .unwrap()
+                .reorg_risk
+        }
+
+        #[tokio::test]
+        async fn low_at_threshold() {
+            let context = RpcContext::for_tests().with_reorg_risk_thresholds(2);
+
+            // "block 1" is two blocks below "latest".
+            assert_eq!(reorg_risk(context, b"txn 1").await, Some(ReorgRisk::Low));
+        }
+
+        #[tokio::test]
+        async fn medium_below_threshold() {
+            let context = RpcContext::for_tests().with_reorg_risk_thresholds(3);
+
+            assert_eq!(reorg_risk(context, b"txn 1").await, Some(ReorgRisk::Medium));
+        }
+
+        #[tokio::test]
+        async fn head_block_is_medium() {
+            let context = RpcContext::for_tests().with_reorg_risk_thresholds(1);
+
+            assert_eq!(reorg_risk(context, b"txn 3").await, Some(ReorgRisk::Medium));
+        }
+
+        #[tokio::test]
+        async fn absent_once_l1_accepted() {
+            let context = RpcContext::for_tests().with_reorg_risk_thresholds(0);
+
+            assert_eq!(reorg_risk(context, b"txn 0").await, None);
+        }
+    }
+
+    mod history {
+        use super::*;
+
+        async fn history(context: RpcContext, hash: &[u8]) -> GetTransactionStatusOutput {
+            let input = GetGatewayTransactionInput {
+                include_history: true,
+                ..input(TransactionHash(felt_bytes!(hash)))
+            };
+            get_transaction_status(context, input).await.unwrap()
+        }
+
+        #[tokio::test]
+        async fn l1_accepted() {
+            let context = RpcContext::for_tests();
+            let history = history(context, b"txn 0").await;
+
+            assert_eq!(
+                history,
+                GetTransactionStatusOutput {
+                    history: Some(TransactionStatusHistory {
+                        accepted_l2_at: Some(BlockTimestamp::new_or_panic(0)),
+                        ..Default::default()
+                    }),
+                    ..GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL1)
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn l2_accepted() {
+            let context = RpcContext::for_tests();
+            let history = history(context, b"txn 1").await;
+
+            assert_eq!(
+                history,
+                GetTransactionStatusOutput {
+                    history: Some(TransactionStatusHistory {
+                        accepted_l2_at: Some(BlockTimestamp::new_or_panic(1)),
+                        ..Default::default()
+                    }),
+                    ..GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn pending() {
+            let context = RpcContext::for_tests_with_pending().await;
+            let history = history(context, b"pending tx hash 0").await;
+
+            assert_eq!(
+                history,
+                GetTransactionStatusOutput {
+                    history: Some(TransactionStatusHistory {
+                        pending_at: Some(BlockTimestamp::new_or_panic(1234567)),
+                        ..Default::default()
+                    }),
+                    ..GetTransactionStatusOutput::new(TransactionStatus::Pending)
+                }
+            );
+        }
+
+        #[test]
+        fn flag_defaults_to_false() {
+            let input: GetGatewayTransactionInput = serde_json::from_value(serde_json::json!({
+                "transaction_hash": "0x1",
+            }))
+            .unwrap();
+
+            assert!(!input.include_history);
+        }
+
+        mod lenient_hash {
+            use super::*;
+
+            fn parse(hash: &str) -> Result<TransactionHash, serde_json::Error> {
+                serde_json::from_value::<GetGatewayTransactionInput>(serde_json::json!({
+                    "transaction_hash": hash,
+                }))
+                .map(|input| input.transaction_hash)
+            }
+
+            #[test]
+            fn uppercase() {
+                let expected = TransactionHash(felt!("0xabcdef"));
+
+                assert_eq!(parse("0xABCDEF").unwrap(), expected);
+                assert_eq!(parse("0XABCDEF").unwrap(), expected);
+                assert_eq!(parse("0XabCDef").unwrap(), expected);
+            }
+
+            #[test]
+            fn short() {
+                assert_eq!(parse("0x1").unwrap(), TransactionHash(felt!("0x1")));
+                assert_eq!(parse("1").unwrap(), TransactionHash(felt!("0x1")));
+            }
+
+            #[test]
+            fn overlong() {
+                let overlong = format!("0x{}", "0".repeat(65));
+
+                let error = parse(&overlong).unwrap_err().to_string();
+
+                assert!(error.contains("More than 64 digits"), "{error}");
+            }
+
+            #[test]
+            fn malformed() {
+                for hash in ["", "0x", "0X", "0xg", "0x 1", "x1"] {
+                    assert!(parse(hash).is_err(), "{hash:?}");
+                }
+            }
+        }
+
+        #[test]
+        fn serialization() {
+            let output = GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2);
+            let json = serde_json::to_value(output).unwrap();
+            assert_eq!(json, serde_json::json!("ACCEPTED_ON_L2"));
+
+            let output = GetTransactionStatusOutput {
+                history: Some(TransactionStatusHistory {
+                    accepted_l2_at: Some(BlockTimestamp::new_or_panic(1)),
+                    ..Default::default()
+                }),
+                ..GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            };
+            let json = serde_json::to_value(output).unwrap();
+            assert_eq!(
+                json,
+                serde_json::json!({
+                    "status": "ACCEPTED_ON_L2",
+                    "accepted_l2_at": 1,
+                })
+            );
+        }
+    }
+
+    mod by_block_hash_and_index {
+        use super::*;
+
+        #[tokio::test]
+        async fn valid_index() {
+            let context = RpcContext::for_tests();
+            // The second transaction of block 1, which is L2 accepted.
+            let input = GetTransactionStatusByBlockHashAndIndexInput {
+                block_hash: BlockHash(felt_bytes!(b"block 1")),
+                index: 1,
+            };
+            let status = get_transaction_status_by_block_hash_and_index(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(status, TransactionStatus::AcceptedOnL2);
+        }
+
+        #[tokio::test]
+        async fn out_of_range_index() {
+            let context = RpcContext::for_tests();
+            let input = GetTransactionStatusByBlockHashAndIndexInput {
+                block_hash: BlockHash(felt_bytes!(b"block 1")),
+                index: 2,
+            };
+            let status = get_transaction_status_by_block_hash_and_index(context, input)
+                .await
+                .unwrap();
+
+            assert_eq!(status, TransactionStatus::NotReceived);
+        }
+    }
+
+    mod dropped_from_pending {
+        use super::*;
+
+        const DROPPED: &[u8] = b"dropped tx";
+
+        fn input() -> GetGatewayTransactionInput {
+            super::input(TransactionHash(felt_bytes!(DROPPED)))
+        }
+
+        fn context() -> RpcContext {
+            let context = RpcContext::for_tests();
+            // Avoid querying the gateway.
+            context.gateway_status_cache.insert(
+                TransactionHash(felt_bytes!(DROPPED)),
+                TransactionStatus::NotReceived,
+            );
+            context
+        }
+
+        #[tokio::test]
+        async fn seen_in_pending() {
+            let context = context();
+            context.pending_sightings.record(
+                BlockNumber::new_or_panic(3),
+                [TransactionHash(felt_bytes!(DROPPED))],
+            );
+
+            let status = get_transaction_status(context, input()).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput {
+                    last_seen_pending_at: Some(BlockNumber::new_or_panic(3)),
+                    ..GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn never_seen() {
+            let status = get_transaction_status(context(), input()).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+            );
+        }
+
+        #[tokio::test]
+        async fn only_annotates_not_received() {
+            let context = RpcContext::for_tests();
+            let tx_hash = TransactionHash(felt_bytes!(b"txn 1"));
+            context
+                .pending_sightings
+                .record(BlockNumber::new_or_panic(1), [tx_hash]);
+
+            let status = get_transaction_status(context, super::input(tx_hash))
+                .await
+                .unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+
+        #[test]
+        fn serialization() {
+            let output = GetTransactionStatusOutput {
+                last_seen_pending_at: Some(BlockNumber::new_or_panic(3)),
+                ..GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+            };
+            let json = serde_json::to_value(output).unwrap();
+            assert_eq!(
+                json,
+                serde_json::json!({ "status": "NOT_RECEIVED", "last_seen_pending_at": 3 })
+            );
+        }
+    }
+
+    mod superseded {
+        use super::*;
+        use pathfinder_common::{ContractNonce, Fee, TransactionIndex};
+        use pathfinder_storage::types::state_update::{Nonce, StateDiff};
+        use pathfinder_storage::BlockId;
+        use starknet_gateway_types::reply::transaction as gateway;
+
+        const REPLACED: &[u8] = b"replaced tx";
+        const REPLACEMENT: &[u8] = b"replacement tx";
+
+        fn sender() -> ContractAddress {
+            ContractAddress::new_or_panic(felt_bytes!(b"sender"))
+        }
+
+        /// Returns a context in which [REPLACEMENT] was accepted with the sender's first nonce,
+        /// whereas [REPLACED] never reached the gateway.
+        fn setup() -> RpcContext {
+            let context = RpcContext::for_tests();
+
+            let replacement = TransactionHash(felt_bytes!(REPLACEMENT));
+            let transaction = gateway::Transaction::Invoke(gateway::InvokeTransaction::V1(
+                gateway::InvokeTransactionV1 {
+                    calldata: vec![],
+                    sender_address: sender(),
+                    max_fee: Fee(felt!("0x100")),
+                    signature: vec![],
+                    nonce: TransactionNonce(felt!("0x0")),
+                    transaction_hash: replacement,
+                },
+            ));
+            let receipt = gateway::Receipt {
+                actual_fee: None,
+                events: vec![],
+                execution_resources: None,
+                execution_status: gateway::ExecutionStatus::Succeeded,
+                l1_to_l2_consumed_message: None,
+                l2_to_l1_messages: vec![],
+                revert_error: None,
+                transaction_hash: replacement,
+                transaction_index: TransactionIndex::new_or_panic(0),
+            };
+
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let header = db_tx
+                .block_header(BlockId::Latest)
+                .unwrap()
+                .unwrap()
+                .child_builder()
+                .finalize_with_hash(BlockHash(felt_bytes!(b"replacement block")));
+            db_tx.insert_block_header(&header).unwrap();
+            db_tx
+                .insert_transaction_data(header.hash, header.number, &[(transaction, receipt)])
+                .unwrap();
+            db_tx
+                .insert_canonical_state_diff(
+                    header.number,
+                    &StateDiff {
+                        nonces: vec![Nonce {
+                            contract_address: sender(),
+                            nonce: ContractNonce(felt!("0x1")),
+                        }],
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            // Avoid querying the gateway for the replaced transaction.
+            context.gateway_status_cache.insert(
+                TransactionHash(felt_bytes!(REPLACED)),
+                TransactionStatus::NotReceived,
+            );
+
+            context
+        }
+
+        fn input(check_superseded: Option<SenderNonce>) -> GetGatewayTransactionInput {
+            GetGatewayTransactionInput {
+                check_superseded,
+                ..super::input(TransactionHash(felt_bytes!(REPLACED)))
+            }
+        }
+
+        #[tokio::test]
+        async fn same_nonce() {
+            let check = SenderNonce {
+                sender_address: sender(),
+                nonce: TransactionNonce(felt!("0x0")),
+            };
+            let status = get_transaction_status(setup(), input(Some(check)))
+                .await
+                .unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput {
+                    superseded_by: Some(TransactionHash(felt_bytes!(REPLACEMENT))),
+                    ..GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn other_nonce() {
+            let check = SenderNonce {
+                sender_address: sender(),
+                nonce: TransactionNonce(felt!("0x1")),
+            };
+            let status = get_transaction_status(setup(), input(Some(check)))
+                .await
+                .unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+            );
+        }
+
+        #[tokio::test]
+        async fn not_checked_by_default() {
+            let status = get_transaction_status(setup(), input(None)).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+            );
+        }
+
+        #[test]
+        fn serialization() {
+            let output = GetTransactionStatusOutput {
+                superseded_by: Some(TransactionHash(felt!("0x1234"))),
+                ..GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+            };
+            let json = serde_json::to_value(output).unwrap();
+            assert_eq!(
+                json,
+                serde_json::json!({
+                    "status": "SUPERSEDED",
+                    "by": "0x1234",
+                })
+            );
+        }
+    }
+
+    mod aborted {
+        use super::*;
+
+        #[test]
+        fn reason_from_failure() {
+            for (code, expected) in [
+                ("REORG", AbortReason::Reorg),
+                ("INVALID_BLOCK", AbortReason::InvalidBlock),
+                (
+                    "SOMETHING_ELSE",
+                    AbortReason::Other("Failure message".to_owned()),
+                ),
+            ] {
+                let failure = GatewayFailure {
+                    code: code.to_owned(),
+                    error_message: "Failure message".to_owned(),
+                };
+                assert_eq!(AbortReason::from(failure), expected, "code: {code}");
+            }
+        }
+
+        #[test]
+        fn serialization() {
+            let output = GetTransactionStatusOutput {
+                abort_reason: Some(AbortReason::InvalidBlock),
+                ..GetTransactionStatusOutput::new(TransactionStatus::Aborted)
+            };
+            let json = serde_json::to_value(output).unwrap();
+            assert_eq!(
+                json,
+                serde_json::json!({
+                    "status": "ABORTED",
+                    "reason": "INVALID_BLOCK",
+                })
+            );
+
+            let output = GetTransactionStatusOutput {
+                abort_reason: Some(AbortReason::Other("Out of resources".to_owned())),
+                ..GetTransactionStatusOutput::new(TransactionStatus::Aborted)
+            };
+            let json = serde_json::to_value(output).unwrap();
+            assert_eq!(
+                json,
+                serde_json::json!({
+                    "status": "ABORTED",
+                    "reason": { "OTHER": "Out of resources" },
+                })
+            );
+        }
+    }
+
+    mod rejected {
+        use super::*;
+
+        #[test]
+        fn serialization() {
+            let output = GetTransactionStatusOutput {
+                rejection: Some(("Invalid nonce".to_owned(), RejectionReason::InvalidNonce)),
+                ..GetTransactionStatusOutput::new(TransactionStatus::Rejected)
+            };
+            let json = serde_json::to_value(output).unwrap();
+            assert_eq!(
+                json,
+                serde_json::json!({
+                    "status": "REJECTED",
+                    "reason": "Invalid nonce",
+                    "category": "INVALID_NONCE",
+                })
+            );
+        }
+
+        #[test]
+        fn category_from_reason() {
+            for (reason, expected) in [
+                (
+                    "Max fee (0x1) is too low. Minimum fee: 0x2d9b4bd2c6f.",
+                    RejectionReason::InsufficientMaxFee,
+                ),
+                (
+                    "Max fee is smaller than the minimal transaction cost (validation plus fee \
+                     transfer).",
+                    RejectionReason::InsufficientMaxFee,
+                ),
+                (
+                    "Account balance is smaller than the transaction's max_fee.",
+                    RejectionReason::InsufficientAccountBalance,
+                ),
+                (
+                    "Invalid transaction nonce of contract at address 0x1234. Account nonce: \
+                     0x5; got: 0x4.",
+                    RejectionReason::InvalidNonce,
+                ),
+                ("Invalid nonce", RejectionReason::InvalidNonce),
+                (
+                    "Contract validation failed: Error in the called contract (0x1234): \
+                     Error at pc=0:1: Got an exception while executing a hint.",
+                    RejectionReason::ValidationFailure,
+                ),
+                (
+                    "Transaction with hash 0x1234 already exists",
+                    RejectionReason::Other,
+                ),
+                ("", RejectionReason::Other),
+            ] {
+                assert_eq!(RejectionReason::from(reason), expected, "reason: {reason}");
+            }
+        }
+    }
+
+    mod batch {
+        use super::*;
+
+        #[tokio::test]
+        async fn mixed() {
+            let context = RpcContext::for_tests_with_pending().await;
+
+            let unknown = TransactionHash(felt_bytes!(b"unknown tx"));
+            // Avoid querying the gateway for the unknown transaction.
+            context
+                .gateway_status_cache
+                .insert(unknown, TransactionStatus::NotReceived);
+
+            let l1_accepted = TransactionHash(felt_bytes!(b"txn 0"));
+            let l2_accepted = TransactionHash(felt_bytes!(b"txn 1"));
+            let pending = TransactionHash(felt_bytes!(b"pending tx hash 0"));
+
+            let input = GetTransactionStatusesInput {
+                transaction_hashes: vec![l2_accepted, unknown, pending, l1_accepted],
+            };
+            let statuses = get_transaction_statuses(context, input).await.unwrap();
+
+            assert_eq!(
+                statuses,
+                vec![
+                    (l2_accepted, TransactionStatus::AcceptedOnL2),
+                    (unknown, TransactionStatus::NotReceived),
+                    (pending, TransactionStatus::Pending),
+                    (l1_accepted, TransactionStatus::AcceptedOnL1),
+                ]
+            );
+        }
+
+        #[tokio::test]
+        async fn sources() {
+            let context = RpcContext::for_tests_with_pending().await;
+
+            let unknown = TransactionHash(felt_bytes!(b"unknown tx"));
+            context
+                .gateway_status_cache
+                .insert(unknown, TransactionStatus::NotReceived);
+
+            let hashes = [
+                TransactionHash(felt_bytes!(b"pending tx hash 0")),
+                TransactionHash(felt_bytes!(b"txn 0")),
+                unknown,
+            ];
+            let sources = resolve_statuses(context, &hashes, StatusDetail::Finality)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|(source, _)| source)
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                sources,
+                vec![
+                    StatusSource::Pending,
+                    StatusSource::Database,
+                    StatusSource::Gateway
+                ]
+            );
+        }
+
+        #[tokio::test]
+        async fn empty() {
+            let context = RpcContext::for_tests();
+            let input = GetTransactionStatusesInput {
+                transaction_hashes: vec![],
+            };
+            let statuses = get_transaction_statuses(context, input).await.unwrap();
+
+            assert!(statuses.is_empty());
+        }
+
+        #[tokio::test]
+        async fn limit_exceeded() {
+            let context = RpcContext::for_tests();
+            let input = GetTransactionStatusesInput {
+                transaction_hashes: vec![TransactionHash(felt_bytes!(b"txn 0")); 101],
+            };
+            let err = get_transaction_statuses(context, input).await.unwrap_err();
+
+            assert_matches::assert_matches!(
+                err,
+                GetTransactionStatusesError::TransactionHashLimitExceeded {
+                    limit: 100,
+                    requested: 101
+                }
+            );
+        }
+    }
+
+    mod prewarm {
+        use super::*;
+
+        #[tokio::test]
+        async fn subsequent_queries_are_cache_hits() {
+            let gateway = starknet_gateway_client::Client::with_base_url(serve(
+                serde_json::json!({ "status": "NOT_RECEIVED" }),
+            ))
+            .unwrap();
+            let context = RpcContext {
+                sequencer: gateway,
+                ..RpcContext::for_tests()
+            };
+            let l1_accepted = TransactionHash(felt_bytes!(b"txn 0"));
+            let l2_accepted = TransactionHash(felt_bytes!(b"txn 3"));
+            let unknown = TransactionHash(felt_bytes!(b"unknown tx"));
+            let input = PrewarmTransactionStatusesInput {
+                transaction_hashes: vec![l1_accepted, l2_accepted, unknown, TransactionHash::ZERO],
+            };
+
+            let summary = prewarm_transaction_statuses(context.clone(), input)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                summary,
+                PrewarmSummary {
+                    database: 2,
+                    gateway: 1,
+                    other: 1,
+                }
+            );
+
+            let head = (
+                BlockNumber::new_or_panic(2),
+                BlockHash(felt_bytes!(b"latest")),
+            );
+            let cached = context
+                .database_status_cache
+                .get(l1_accepted, head)
+                .unwrap();
+            assert_eq!(cached.finality_status, TransactionStatus::AcceptedOnL1);
+            let cached = context
+                .database_status_cache
+                .get(l2_accepted, head)
+                .unwrap();
+            assert_eq!(cached.finality_status, TransactionStatus::AcceptedOnL2);
+            assert_eq!(
+                context.gateway_status_cache.get(&unknown),
+                Some(TransactionStatus::NotReceived)
+            );
+        }
+
+        #[tokio::test]
+        async fn limit_exceeded() {
+            let context = RpcContext::for_tests();
+            let input = PrewarmTransactionStatusesInput {
+                transaction_hashes: vec![TransactionHash(felt_bytes!(b"txn 0")); 1001],
+            };
+            let err = prewarm_transaction_statuses(context, input)
+                .await
+                .unwrap_err();
+
+            assert_matches::assert_matches!(
+                err,
+                GetTransactionStatusesError::TransactionHashLimitExceeded {
+                    limit: 1000,
+                    requested: 1001
+                }
+            );
+        }
+    }
+
+    mod explain {
+        use super::*;
+
+        async fn plan(
+            context: RpcContext,
+            transaction_hash: TransactionHash,
+        ) -> Vec<StatusBackend> {
+            let input = ExplainTransactionStatusInput { transaction_hash };
+            explain_transaction_status(context, input)
+                .await
+                .unwrap()
+                .backends
+        }
+
+        #[tokio::test]
+        async fn zero_hash() {
+            let context = RpcContext::for_tests_with_pending().await;
+
+            let plan = plan(context, TransactionHash::ZERO).await;
+
+            assert_eq!(plan, vec![StatusBackend::Validation]);
+        }
+
+        #[tokio::test]
+        async fn pending() {
+            let context = RpcContext::for_tests_with_pending().await;
+            let tx_hash = TransactionHash(felt_bytes!(b"pending tx hash 0"));
+
+            let plan = plan(context, tx_hash).await;
+
+            assert_eq!(plan, vec![StatusBackend::Pending, StatusBackend::Database]);
+        }
+
+        #[tokio::test]
+        async fn database() {
+            let tx_hash = TransactionHash(felt_bytes!(b"txn 1"));
+
+            let context = RpcContext::for_tests();
+            assert_eq!(plan(context, tx_hash).await, vec![StatusBackend::Database]);
+
+            let context = RpcContext::for_tests_with_pending().await;
+            assert_eq!(
+                plan(context, tx_hash).await,
+                vec![StatusBackend::Pending, StatusBackend::Database]
+            );
+
+            let context = RpcContext::for_tests().with_gateway_pending_fallback(true);
+            assert_eq!(
+                plan(context, tx_hash).await,
+                vec![StatusBackend::GatewayPending, StatusBackend::Database]
+            );
+        }
+
+        #[tokio::test]
+        async fn gateway() {
+            let context = RpcContext::for_tests();
+            let tx_hash = TransactionHash(felt_bytes!(b"unknown tx"));
+
+            assert_eq!(
+                plan(context.clone(), tx_hash).await,
+                vec![
+                    StatusBackend::Database,
+                    StatusBackend::GatewayCache,
+                    StatusBackend::Gateway
+                ]
+            );
+
+            context
+                .gateway_status_cache
+                .insert(tx_hash, TransactionStatus::NotReceived);
+            assert_eq!(
+                plan(context, tx_hash).await,
+                vec![StatusBackend::Database, StatusBackend::GatewayCache]
+            );
+        }
+
+        #[test]
+        fn serialization() {
+            let plan = StatusPlan {
+                backends: vec![StatusBackend::GatewayPending, StatusBackend::GatewayCache],
+            };
+            let json = serde_json::to_value(plan).unwrap();
+            assert_eq!(
+                json,
+                serde_json::json!({ "backends": ["gateway_pending", "gateway_cache"] })
+            );
+        }
+    }
+
+    mod finality_rank {
+        use super::*;
+
+        const ALL: [TransactionStatus; 10] = [
+            TransactionStatus::Unknown,
+            TransactionStatus::NotReceived,
+            TransactionStatus::Received,
+            TransactionStatus::Pending,
+            TransactionStatus::PreConfirmed,
+            TransactionStatus::AcceptedOnL2,
+            TransactionStatus::AcceptedOnL1,
+            TransactionStatus::Reverted,
+            TransactionStatus::Rejected,
+            TransactionStatus::Aborted,
+        ];
+
+        #[test]
+        fn ranks() {
+            for (rank, status) in ALL.iter().enumerate() {
+                assert_eq!(status.finality_rank() as usize, rank, "{status:?}");
+            }
+        }
+
+        #[test]
+        fn sorting() {
+            let mut statuses = ALL;
+            statuses.reverse();
+            statuses.sort();
+            assert_eq!(statuses, ALL);
+        }
+
+        #[test]
+        fn terminal_tier_follows_happy_path() {
+            let terminal = [
+                TransactionStatus::Reverted,
+                TransactionStatus::Rejected,
+                TransactionStatus::Aborted,
+            ];
+            for status in terminal {
+                for progress in &ALL[..=6] {
+                    assert!(status > *progress, "{status:?} <= {progress:?}");
+                }
+            }
+        }
+
+        #[test]
+        fn unknown_ranks_lowest() {
+            for status in &ALL[1..] {
+                assert!(TransactionStatus::Unknown < *status, "{status:?}");
+            }
+        }
+    }
+
+    mod status_code {
+        use super::*;
+
+        #[test]
+        fn encodings() {
+            for (status, name, code) in [
+                (TransactionStatus::NotReceived, "NOT_RECEIVED", 0),
+                (TransactionStatus::Received, "RECEIVED", 1),
+                (TransactionStatus::Pending, "PENDING", 2),
+                (TransactionStatus::PreConfirmed, "PRE_CONFIRMED", 3),
+                (TransactionStatus::Rejected, "REJECTED", 4),
+                (TransactionStatus::AcceptedOnL1, "ACCEPTED_ON_L1", 5),
+                (TransactionStatus::AcceptedOnL2, "ACCEPTED_ON_L2", 6),
+                (TransactionStatus::Reverted, "REVERTED", 7),
+                (TransactionStatus::Aborted, "ABORTED", 8),
+                (TransactionStatus::Unknown, "UNKNOWN", 9),
+            ] {
+                assert_eq!(serde_json::to_value(status).unwrap(), name);
+                assert_eq!(status.code(), code, "{name}");
+            }
+        }
+
+        #[tokio::test]
+        async fn l2_accepted() {
+            let context = RpcContext::for_tests();
+            let input = input(TransactionHash(felt_bytes!(b"txn 1")));
+
+            let code = get_transaction_status_code(context, input).await.unwrap();
+            assert_eq!(code, TransactionStatus::AcceptedOnL2.code());
+        }
+    }
+
+    mod lowercase {
+        use super::*;
+
+        const STATUSES: [(TransactionStatus, &str); 10] = [
+            (TransactionStatus::NotReceived, "NOT_RECEIVED"),
+            (TransactionStatus::Received, "RECEIVED"),
+            (TransactionStatus::Pending, "PENDING"),
+            (TransactionStatus::PreConfirmed, "PRE_CONFIRMED"),
+            (TransactionStatus::Rejected, "REJECTED"),
+            (TransactionStatus::AcceptedOnL1, "ACCEPTED_ON_L1"),
+            (TransactionStatus::AcceptedOnL2, "ACCEPTED_ON_L2"),
+            (TransactionStatus::Reverted, "REVERTED"),
+            (TransactionStatus::Aborted, "ABORTED"),
+            (TransactionStatus::Unknown, "UNKNOWN"),
+        ];
+
+        #[test]
+        fn uppercase_round_trip() {
+            for (status, name) in STATUSES {
+                let json = serde_json::to_value(status).unwrap();
+                assert_eq!(json, name);
+
+                let parsed: TransactionStatus = serde_json::from_value(json).unwrap();
+                assert_eq!(parsed, status);
+                let parsed: LowercaseStatus = serde_json::from_value(name.into()).unwrap();
+                assert_eq!(parsed, LowercaseStatus(status));
+            }
+        }
+
+        #[test]
+        fn lowercase_round_trip() {
+            for (status, name) in STATUSES {
+                let json = serde_json::to_value(LowercaseStatus(status)).unwrap();
+                assert_eq!(json, name.to_lowercase());
+
+                let parsed: LowercaseStatus = serde_json::from_value(json.clone()).unwrap();
+                assert_eq!(parsed, LowercaseStatus(status));
+                let parsed: TransactionStatus = serde_json::from_value(json).unwrap();
+                assert_eq!(parsed, status);
+            }
+        }
+
+        #[tokio::test]
+        async fn requested() {
+            let context = RpcContext::for_tests();
+            let input = GetGatewayTransactionInput {
+                lowercase: true,
+                ..input(TransactionHash(felt_bytes!(b"txn 0")))
+            };
+
+            let status = get_transaction_status(context, input).await.unwrap();
+
+            assert_eq!(serde_json::to_value(status).unwrap(), "accepted_on_l1");
+        }
+
+        #[test]
+        fn not_applied_with_details() {
+            let output = GetTransactionStatusOutput {
+                lowercase: true,
+                retry_after_ms: Some(5000),
+                ..GetTransactionStatusOutput::new(TransactionStatus::Received)
+            };
+            let json = serde_json::to_value(output).unwrap();
+            assert_eq!(
+                json,
+                serde_json::json!({ "status": "RECEIVED", "retry_after_ms": 5000 })
+            );
+        }
+    }
+
+    mod gateway_status_mapping {
+        use super::*;
+        use starknet_gateway_types::reply::Status;
+
+        const GATEWAY_STATUSES: [Status; 10] = [
+            Status::NotReceived,
+            Status::Received,
+            Status::Pending,
+            Status::PreConfirmed,
+            Status::Rejected,
+            Status::AcceptedOnL1,
+            Status::AcceptedOnL2,
+            Status::Reverted,
+            Status::Aborted,
+            Status::Unknown,
+        ];
+
+        /// Fails to compile once the gateway gains a status, as a reminder to add it to
+        /// [GATEWAY_STATUSES].
+        #[allow(dead_code)]
+        fn all_listed(status: Status) {
+            match status {
+                Status::NotReceived
+                | Status::Received
+                | Status::Pending
+                | Status::PreConfirmed
+                | Status::Rejected
+                | Status::AcceptedOnL1
+                | Status::AcceptedOnL2
+                | Status::Reverted
+                | Status::Aborted
+                | Status::Unknown => {}
+            }
+        }
+
+        #[test]
+        fn round_trip() {
+            for gateway_status in GATEWAY_STATUSES {
+                let status = TransactionStatus::from(gateway_status);
+                match status.as_gateway_status() {
+                    Some(inverse) => assert_eq!(inverse, gateway_status),
+                    None => assert_eq!(status, TransactionStatus::Unknown),
+                }
+            }
+        }
+
+        #[test]
+        fn names_match() {
+            for gateway_status in GATEWAY_STATUSES {
+                let status = TransactionStatus::from(gateway_status);
+                assert_eq!(
+                    serde_json::to_value(status).unwrap(),
+                    serde_json::to_value(gateway_status).unwrap(),
+                );
+            }
+        }
+    }
+
+    mod gateway_pending_fallback {
+        use super::*;
+        use starknet_gateway_client::MockGatewayApi;
+        use starknet_gateway_types::reply;
+
+        const TIMEOUT: Duration = Duration::from_secs(5);
+
+        fn gateway_with(status: reply::Status) -> MockGatewayApi {
+            let mut gateway = MockGatewayApi::new();
+            gateway.expect_transaction().returning(move |_| {
+                Ok(reply::Transaction {
+                    block_hash: None,
+                    block_number: None,
+                    status,
+                    transaction: None,
+                    transaction_index: None,
+                    transaction_failure_reason: None,
+                })
+            });
+            gateway
+        }
+
+        #[tokio::test]
+        async fn pending_statuses() {
+            let tx_hash = TransactionHash(felt_bytes!(b"submitted tx"));
+
+            for (gateway_status, expected) in [
+                (reply::Status::Received, Some(TransactionStatus::Received)),
+                (reply::Status::Pending, Some(TransactionStatus::Pending)),
+                (
+                    reply::Status::PreConfirmed,
+                    Some(TransactionStatus::PreConfirmed),
+                ),
+                (reply::Status::NotReceived, None),
+                (reply::Status::AcceptedOnL2, None),
+            ] {
+                let gateway = gateway_with(gateway_status);
+                let status = gateway_pending_status(&gateway, TIMEOUT, tx_hash)
+                    .await
+                    .map(|(status, _)| status);
+                assert_eq!(status, expected, "{gateway_status}");
+            }
+        }
+
+        #[tokio::test]
+        async fn gateway_error_is_ignored() {
+            let mut gateway = MockGatewayApi::new();
+            gateway
+                .expect_transaction()
+                .returning(|_| Err(SequencerError::InvalidStarknetErrorVariant));
+
+            let tx_hash = TransactionHash(felt_bytes!(b"submitted tx"));
+            let status = gateway_pending_status(&gateway, TIMEOUT, tx_hash).await;
+            assert!(status.is_none());
+        }
+
+        #[tokio::test]
+        async fn disabled() {
+            let context = RpcContext::for_tests();
+            assert!(!context.gateway_pending_fallback);
+
+            let input = input(TransactionHash(felt_bytes!(b"txn 1")));
+            let status = get_transaction_status(context, input).await.unwrap();
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+
+        #[tokio::test]
+        async fn enabled_falls_through_to_database() {
+            // Nothing listens on this port, so the gateway query fails.
+            let gateway = starknet_gateway_client::Client::with_base_url(
+                "http://127.0.0.1:1".parse().unwrap(),
+            )
+            .unwrap();
+            let context = RpcContext {
+                sequencer: gateway,
+                ..RpcContext::for_tests()
+            }
+            .with_gateway_timeout(Duration::from_millis(100))
+            .with_gateway_pending_fallback(true);
+
+            let input = input(TransactionHash(felt_bytes!(b"txn 1")));
+            let status = get_transaction_status(context, input).await.unwrap();
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+    }
+
+    /// Serves `reply` to every request, and returns the server's url.
+    fn serve(reply: impl ToString) -> reqwest::Url {
+        let reply = reply.to_string();
+        let make_service = hyper::service::make_service_fn(move |_| {
+            let reply = reply.clone();
+            async move {
+                Ok::<_, std::convert::Infallible>(hyper::service::service_fn(move |_| {
+                    let body = hyper::Body::from(reply.clone());
+                    async move { Ok::<_, std::convert::Infallible>(hyper::Response::new(body)) }
+                }))
+            }
+        });
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let url = format!("http://{}", server.local_addr()).parse().unwrap();
+        tokio::spawn(server);
+        url
+    }
+
+    mod status_debug {
+        use super::*;
+
+        async fn debug(context: RpcContext, hash: &[u8]) -> TransactionStatusDebug {
+            let input = GetTransactionStatusDebugInput {
+                transaction_hash: TransactionHash(felt_bytes!(hash)),
+            };
+            get_transaction_status_debug(context, input).await.unwrap()
+        }
+
+        #[tokio::test]
+        async fn rejected_includes_reply() {
+            let reply = serde_json::json!({
+                "status": "REJECTED",
+                "transaction_failure_reason": {
+                    "code": "INVALID_TRANSACTION_NONCE",
+                    "error_message": "Invalid transaction nonce",
+                },
+            });
+            let gateway = starknet_gateway_client::Client::with_base_url(serve(&reply)).unwrap();
+            let context = RpcContext::for_tests().with_status_gateway(gateway);
+
+            let status = debug(context.clone(), b"rejected tx").await;
+            assert_eq!(status.status, TransactionStatus::Rejected);
+            assert!(status.gateway_consulted);
+            assert_eq!(serde_json::to_value(status.gateway_reply).unwrap(), reply);
+
+            // The rejection is served from storage from now on, without consulting the gateway.
+            let status = debug(context, b"rejected tx").await;
+            assert_eq!(
+                status,
+                TransactionStatusDebug {
+                    status: TransactionStatus::Rejected,
+                    gateway_consulted: false,
+                    gateway_reply: None,
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn not_consulted_for_database() {
+            let status = debug(RpcContext::for_tests(), b"txn 1").await;
+            assert_eq!(
+                status,
+                TransactionStatusDebug {
+                    status: TransactionStatus::AcceptedOnL2,
+                    gateway_consulted: false,
+                    gateway_reply: None,
+                }
+            );
+        }
+    }
+
+    mod malformed_gateway_reply {
+        use super::*;
+
+        async fn status(
+            reply: impl ToString,
+        ) -> Result<TransactionStatus, GetGatewayTransactionError> {
+            let gateway = starknet_gateway_client::Client::with_base_url(serve(reply)).unwrap();
+            let context = RpcContext {
+                sequencer: gateway,
+                ..RpcContext::for_tests()
+            };
+
+            resolve_status(context, TransactionHash(felt_bytes!(b"unknown tx")))
+                .await
+                .map(|status| status.finality_status)
+        }
+
+        #[tokio::test]
+        async fn truncated() {
+            let result = status(r#"{"status": "ACCEPTED_ON_L2", "block_ha"#).await;
+
+            assert_matches::assert_matches!(
+                result,
+                Err(GetGatewayTransactionError::Internal(e)) => {
+                    assert!(e.to_string().starts_with("Malformed gateway reply"), "{e:#}");
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn garbage() {
+            let result = status("<html>Bad Gateway</html>").await;
+
+            assert_matches::assert_matches!(result, Err(GetGatewayTransactionError::Internal(_)));
+        }
+
+        #[tokio::test]
+        async fn missing_status() {
+            let result = status(serde_json::json!({ "transaction_index": 3 })).await;
+
+            assert_matches::assert_matches!(
+                result,
+                Err(GetGatewayTransactionError::Internal(e)) => {
+                    assert!(e.to_string().starts_with("Malformed gateway reply"), "{e:#}");
+                }
+            );
+        }
+    }
+
+    mod gateway_error_detail {
+        use super::*;
+
+        #[tokio::test]
+        async fn server_error() {
+            use jsonrpsee::types::error::CallError;
+
+            let reply = serde_json::json!({
+                "code": "StarknetErrorCode.SOME_INTERNAL_ERROR",
+                "message": "x".repeat(1000),
+            })
+            .to_string();
+            let make_service = hyper::service::make_service_fn(move |_| {
+                let reply = reply.clone();
+                async move {
+                    Ok::<_, std::convert::Infallible>(hyper::service::service_fn(move |_| {
+                        let response = hyper::Response::builder()
+                            .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                            .body(hyper::Body::from(reply.clone()))
+                            .unwrap();
+                        async move { Ok::<_, std::convert::Infallible>(response) }
+                    }))
+                }
+            });
+            let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+            let url = format!("http://{}", server.local_addr()).parse().unwrap();
+            tokio::spawn(server);
+
+            let context = RpcContext {
+                sequencer: starknet_gateway_client::Client::with_base_url(url).unwrap(),
+                ..RpcContext::for_tests()
+            };
+            let error = resolve_status(context, TransactionHash(felt_bytes!(b"unknown tx")))
+                .await
+                .unwrap_err();
+
+            let error = jsonrpsee::core::Error::from(crate::error::RpcError::from(error));
+            let data = assert_matches::assert_matches!(
+                error,
+                jsonrpsee::core::Error::Call(CallError::Custom(e)) => e.data().unwrap().to_owned()
+            );
+            let data: serde_json::Value = serde_json::from_str(data.get()).unwrap();
+
+            assert_eq!(data["gateway_status"], 500);
+            let body = data["gateway_body"].as_str().unwrap();
+            assert_eq!(body.len(), GatewayErrorDetail::MAX_BODY_LEN);
+            assert!(
+                body.starts_with(r#"{"code":"StarknetErrorCode.SOME_INTERNAL_ERROR""#),
+                "{body}"
+            );
+        }
+    }
+
+    mod received {
+        use super::*;
+
+        async fn status(context: RpcContext) -> GetTransactionStatusOutput {
+            let gateway = starknet_gateway_client::Client::with_base_url(serve(
+                serde_json::json!({ "status": "RECEIVED" }),
+            ))
+            .unwrap();
+            let context = RpcContext {
+                sequencer: gateway,
+                ..context
+            };
+            let input = input(TransactionHash(felt_bytes!(b"just submitted tx")));
+
+            get_transaction_status(context, input).await.unwrap()
+        }
+
+        #[tokio::test]
+        async fn hints_pending_interval() {
+            let context = RpcContext::for_tests().with_pending_interval(Duration::from_secs(5));
+
+            assert_eq!(
+                status(context).await,
+                GetTransactionStatusOutput {
+                    retry_after_ms: Some(5000),
+                    ..GetTransactionStatusOutput::new(TransactionStatus::Received)
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn without_pending_polling() {
+            assert_eq!(
+                status(RpcContext::for_tests()).await,
+                GetTransactionStatusOutput::new(TransactionStatus::Received)
+            );
+        }
+
+        #[test]
+        fn serialization() {
+            let output = GetTransactionStatusOutput {
+                retry_after_ms: Some(5000),
+                ..GetTransactionStatusOutput::new(TransactionStatus::Received)
+            };
+            let json = serde_json::to_value(output).unwrap();
+            assert_eq!(
+                json,
+                serde_json::json!({ "status": "RECEIVED", "retry_after_ms": 5000 })
+            );
+        }
+    }
+
+    mod verify {
+        use super::*;
+
+        async fn status(hash: &[u8], verify: bool) -> GetTransactionStatusOutput {
+            let gateway = starknet_gateway_client::Client::with_base_url(serve(
+                serde_json::json!({ "status": "REVERTED" }),
+            ))
+            .unwrap();
+            let context = RpcContext {
+                sequencer: gateway,
+                ..RpcContext::for_tests()
+            };
+            let input = GetGatewayTransactionInput {
+                verify,
+                ..input(TransactionHash(felt_bytes!(hash)))
+            };
+
+            get_transaction_status(context, input).await.unwrap()
+        }
+
+        #[tokio::test]
+        async fn gateway_wins_disagreement() {
+            assert_eq!(
+                status(b"txn 3", true).await,
+                GetTransactionStatusOutput::new(TransactionStatus::Reverted)
+            );
+        }
+
+        #[tokio::test]
+        async fn disabled_by_default() {
+            assert_eq!(
+                status(b"txn 3", false).await,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+
+        #[tokio::test]
+        async fn l1_accepted_is_not_verified() {
+            assert_eq!(
+                status(b"txn 0", true).await,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL1)
+            );
+        }
+
+        #[tokio::test]
+        async fn gateway_error_keeps_stored_status() {
+            // Nothing listens on this port, so any gateway query fails.
+            let gateway = starknet_gateway_client::Client::with_base_url(
+                "http://127.0.0.1:1".parse().unwrap(),
+            )
+            .unwrap();
+            let context = RpcContext {
+                sequencer: gateway,
+                ..RpcContext::for_tests()
+            };
+            let input = GetGatewayTransactionInput {
+                verify: true,
+                ..input(TransactionHash(felt_bytes!(b"txn 3")))
+            };
+
+            let status = get_transaction_status(context, input).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+
+        #[tokio::test]
+        async fn fails_over_to_fallback_gateway() {
+            // Nothing listens on this port, so any query to the status gateway fails.
+            let unreachable = starknet_gateway_client::Client::with_base_url(
+                "http://127.0.0.1:1".parse().unwrap(),
+            )
+            .unwrap();
+            let fallback = starknet_gateway_client::Client::with_base_url(serve(
+                serde_json::json!({ "status": "REVERTED" }),
+            ))
+            .unwrap();
+            let context = RpcContext {
+                sequencer: unreachable,
+                ..RpcContext::for_tests()
+            }
+            .with_fallback_gateways(vec![fallback]);
+            let input = GetGatewayTransactionInput {
+                verify: true,
+                ..input(TransactionHash(felt_bytes!(b"txn 3")))
+            };
+
+            let status = get_transaction_status(context, input).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::Reverted)
+            );
+        }
+
+        #[tokio::test]
+        async fn open_circuit_breaker_keeps_stored_status() {
+            // Nothing listens on this port, so any gateway query fails and opens the breaker.
+            let unreachable = starknet_gateway_client::Client::with_base_url(
+                "http://127.0.0.1:1".parse().unwrap(),
+            )
+            .unwrap();
+            let context = RpcContext {
+                sequencer: unreachable,
+                ..RpcContext::for_tests()
+            }
+            .with_gateway_retry(1, Duration::ZERO)
+            .with_gateway_circuit_breaker(
+                1,
+                Duration::from_secs(60),
+                Duration::from_secs(60),
+            );
+            let verify_input = || GetGatewayTransactionInput {
+                verify: true,
+                ..input(TransactionHash(felt_bytes!(b"txn 3")))
+            };
+            let status = get_transaction_status(context.clone(), verify_input())
+                .await
+                .unwrap();
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+
+            // The breaker is open, so even a gateway which disagrees is not queried.
+            let gateway = starknet_gateway_client::Client::with_base_url(serve(
+                serde_json::json!({ "status": "REVERTED" }),
+            ))
+            .unwrap();
+            let context = RpcContext {
+                sequencer: gateway,
+                ..context
+            };
+            let status = get_transaction_status(context, verify_input())
+                .await
+                .unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+    }
+
+    mod status_gateway {
+        use super::*;
+
+        #[tokio::test]
+        async fn used_instead_of_sequencer() {
+            // Nothing listens on this port, so any query to the sequencer fails.
+            let sequencer = starknet_gateway_client::Client::with_base_url(
+                "http://127.0.0.1:1".parse().unwrap(),
+            )
+            .unwrap();
+            let status_gateway = serve(serde_json::json!({
+                "status": "REJECTED",
+                "transaction_failure_reason": {
+                    "code": "INVALID_TRANSACTION_NONCE",
+                    "error_message": "Invalid nonce",
+                },
+            }));
+            let status_gateway =
+                starknet_gateway_client::Client::with_base_url(status_gateway).unwrap();
+            let context = RpcContext {
+                sequencer,
+                ..RpcContext::for_tests()
+            }
+            .with_status_gateway(status_gateway);
+
+            let input = input(TransactionHash(felt_bytes!(b"unknown tx")));
+            let status = get_transaction_status(context, input).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput {
+                    rejection: Some(("Invalid nonce".to_owned(), RejectionReason::InvalidNonce)),
+                    ..GetTransactionStatusOutput::new(TransactionStatus::Rejected)
+                }
+            );
+        }
+    }
+
+    mod block_transaction_status_summary {
+        use pathfinder_common::BlockId;
+        use starknet_gateway_types::reply::transaction::ExecutionStatus as GatewayStatus;
+
+        use super::*;
+
+        async fn summary(
+            context: RpcContext,
+            block_id: BlockId,
+        ) -> Result<BlockTransactionStatusSummary, BlockTransactionStatusSummaryError> {
+            block_transaction_status_summary(
+                context,
+                BlockTransactionStatusSummaryInput { block_id },
+            )
+            .await
+        }
+
+        #[tokio::test]
+        async fn mixed() {
+            let context = RpcContext::for_tests();
+            let block_hash = BlockHash(felt_bytes!(b"block 1"));
+
+            // Revert one of the block's two transactions.
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let mut data = db_tx
+                .transaction_data_for_block(block_hash.into())
+                .unwrap()
+                .unwrap();
+            data[0].1.execution_status = GatewayStatus::Reverted;
+            db_tx
+                .insert_transaction_data(block_hash, BlockNumber::new_or_panic(1), &data)
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            let expected = BlockTransactionStatusSummary {
+                succeeded: 1,
+                reverted: 1,
+            };
+            let by_hash = summary(context.clone(), BlockId::Hash(block_hash))
+                .await
+                .unwrap();
+            assert_eq!(by_hash, expected);
+            let by_number = summary(context, BlockId::Number(BlockNumber::new_or_panic(1)))
+                .await
+                .unwrap();
+            assert_eq!(by_number, expected);
+        }
+
+        #[tokio::test]
+        async fn latest() {
+            let summary = summary(RpcContext::for_tests(), BlockId::Latest)
+                .await
+                .unwrap();
+            assert_eq!(
+                summary,
+                BlockTransactionStatusSummary {
+                    succeeded: 3,
+                    reverted: 0,
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn pending() {
+            let context = RpcContext::for_tests_with_pending().await;
+            let pending = context.pending_data.as_ref().unwrap();
+            let block = pending.block().await.unwrap();
+            let mut transactions = block.transactions.clone();
+            let mut receipts = block.transaction_receipts.clone();
+            let mut reverted = receipts[0].clone();
+            reverted.execution_status = GatewayStatus::Reverted;
+            reverted.transaction_hash = TransactionHash(felt_bytes!(b"reverted pending tx"));
+            transactions.push(transactions[0].clone());
+            receipts.push(reverted);
+
+            let context =
+                RpcContext::for_tests_with_pending_transactions(transactions, receipts).await;
+            let summary = summary(context, BlockId::Pending).await.unwrap();
+            assert_eq!(
+                summary,
+                BlockTransactionStatusSummary {
+                    succeeded: 2,
+                    reverted: 1,
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn pending_without_pending_data() {
+            let summary = summary(RpcContext::for_tests(), BlockId::Pending)
+                .await
+                .unwrap();
+            assert_eq!(summary, BlockTransactionStatusSummary::default());
+        }
+
+        #[tokio::test]
+        async fn unknown_block() {
+            let result = summary(
+                RpcContext::for_tests(),
+                BlockId::Hash(BlockHash(felt_bytes!(b"unknown block"))),
+            )
+            .await;
+            assert_matches::assert_matches!(
+                result,
+                Err(BlockTransactionStatusSummaryError::BlockNotFound)
+            );
+        }
+    }
+
+    mod block_range {
+        use super::*;
+
+        fn input(from: u64, to: u64) -> GetTransactionStatusesInBlockRangeInput {
+            GetTransactionStatusesInBlockRangeInput {
+                from_block: BlockNumber::new_or_panic(from),
+                to_block: BlockNumber::new_or_panic(to),
+            }
+        }
+
+        fn hash(hash: &[u8]) -> TransactionHash {
+            TransactionHash(felt_bytes!(hash))
+        }
+
+        #[tokio::test]
+        async fn statuses_per_block() {
+            let context = RpcContext::for_tests();
+
+            let statuses = get_transaction_statuses_in_block_range(context, input(0, 2))
+                .await
+                .unwrap();
+
+            assert_eq!(
+                statuses,
+                vec![
+                    (hash(b"txn 0"), TransactionStatus::AcceptedOnL1),
+                    (hash(b"txn 1"), TransactionStatus::AcceptedOnL2),
+                    (hash(b"txn 2"), TransactionStatus::AcceptedOnL2),
+                    (hash(b"txn 3"), TransactionStatus::AcceptedOnL2),
+                    (hash(b"txn 4"), TransactionStatus::AcceptedOnL2),
+                    (hash(b"txn 5"), TransactionStatus::AcceptedOnL2),
+                ]
+            );
+        }
+
+        #[tokio::test]
+        async fn limit_exceeded() {
+            let context = RpcContext::for_tests();
+
+            let result = get_transaction_statuses_in_block_range(context, input(0, 100)).await;
+
+            assert_matches::assert_matches!(
+                result,
+                Err(
+                    GetTransactionStatusesInBlockRangeError::BlockRangeLimitExceeded {
+                        limit: 100,
+                        requested: 101
+                    }
+                )
+            );
+        }
+
+        #[tokio::test]
+        async fn inverted_range() {
+            let context = RpcContext::for_tests();
+
+            let result = get_transaction_statuses_in_block_range(context, input(2, 1)).await;
+
+            assert_matches::assert_matches!(
+                result,
+                Err(GetTransactionStatusesInBlockRangeError::InvalidBlockRange)
+            );
+        }
+    }
+
+    mod recent_rejected {
+        use super::*;
+
+        async fn reject(context: &RpcContext, hashes: &'static [&'static [u8]]) {
+            let storage = context.storage.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut db = storage.connection().unwrap();
+                let db_tx = db.transaction().unwrap();
+                for hash in hashes {
+                    let hash = TransactionHash(felt_bytes!(hash));
+                    db_tx.insert_rejected_transaction(hash, "reason").unwrap();
+                }
+                db_tx.commit().unwrap();
+            })
+            .await
+            .unwrap();
+        }
+
+        async fn page(
+            context: &RpcContext,
+            cursor: Option<u64>,
+            limit: usize,
+        ) -> (Vec<TransactionHash>, Option<u64>) {
+            let input = RecentRejectedTransactionsInput {
+                cursor,
+                limit: Some(limit),
+            };
+            let page = recent_rejected_transactions(context.clone(), input)
+                .await
+                .unwrap();
+
+            let hashes = page
+                .rejections
+                .into_iter()
+                .map(|rejection| rejection.transaction_hash)
+                .collect();
+            (hashes, page.next_cursor)
+        }
+
+        fn hash(hash: &[u8]) -> TransactionHash {
+            TransactionHash(felt_bytes!(hash))
+        }
+
+        #[tokio::test]
+        async fn multiple_pages() {
+            let context = RpcContext::for_tests();
+            reject(&context, &[b"rejected 0", b"rejected 1", b"rejected 2"]).await;
+
+            let (first, cursor) = page(&context, None, 2).await;
+            assert_eq!(first, vec![hash(b"rejected 2"), hash(b"rejected 1")]);
+            assert!(cursor.is_some());
+
+            let (second, cursor) = page(&context, cursor, 2).await;
+            assert_eq!(second, vec![hash(b"rejected 0")]);
+            assert_eq!(cursor, None);
+        }
+
+        #[tokio::test]
+        async fn cursor_is_stable() {
+            let context = RpcContext::for_tests();
+            reject(&context, &[b"rejected 0", b"rejected 1", b"rejected 2"]).await;
+
+            let (_, cursor) = page(&context, None, 1).await;
+            // Newer rejections do not shift the following pages.
+            reject(&context, &[b"rejected 3"]).await;
+
+            let (second, _) = page(&context, cursor, 1).await;
+            assert_eq!(second, vec![hash(b"rejected 1")]);
+            // Neither does fetching the same page again.
+            let (again, _) = page(&context, cursor, 1).await;
+            assert_eq!(again, second);
+        }
+
+        #[tokio::test]
+        async fn page_size_is_limited() {
+            let input = RecentRejectedTransactionsInput {
+                cursor: None,
+                limit: Some(MAX_REJECTIONS_PAGE_SIZE + 1),
+            };
+            let result = recent_rejected_transactions(RpcContext::for_tests(), input).await;
+
+            assert_matches::assert_matches!(
+                result,
+                Err(RecentRejectedTransactionsError::PageSizeTooBig)
+            );
+        }
+    }
+
+    mod stuck_received {
+        use super::*;
+
+        #[tokio::test]
+        async fn reported_beyond_threshold() {
+            let url = serve(serde_json::json!({ "status": "RECEIVED" }));
+            let gateway = starknet_gateway_client::Client::with_base_url(url).unwrap();
+            let context = RpcContext {
+                sequencer: gateway,
+                ..RpcContext::for_tests()
+            };
+            let hash = TransactionHash(felt_bytes!(b"received tx"));
+            let input = input(hash);
+            let status = get_transaction_status(context.clone(), input)
+                .await
+                .unwrap();
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::Received)
+            );
+
+            // Only pause the clock now, as the gateway query relies on it to time out.
+            tokio::time::pause();
+            tokio::time::advance(Duration::from_secs(600)).await;
+
+            let stuck = |older_than_secs| {
+                stuck_received_transactions(
+                    context.clone(),
+                    StuckReceivedTransactionsInput { older_than_secs },
+                )
+            };
+            assert_eq!(stuck(300).await.unwrap(), vec![hash]);
+            assert_eq!(stuck(3600).await.unwrap(), vec![]);
+        }
+    }
+
+    mod gateway_consulted {
+        use super::*;
+
+        async fn gateway_consulted(context: RpcContext, hash: &[u8]) -> Option<bool> {
+            let input = input(TransactionHash(felt_bytes!(hash)));
+            let status = get_transaction_status_details(context, input)
+                .await
+                .unwrap();
+            status.gateway_consulted
+        }
+
+        #[tokio::test]
+        async fn consulted() {
+            let url = serve(serde_json::json!({ "status": "NOT_RECEIVED" }));
+            let gateway = starknet_gateway_client::Client::with_base_url(url).unwrap();
+            let context = RpcContext {
+                sequencer: gateway,
+                ..RpcContext::for_tests()
+            };
+
+            assert_eq!(gateway_consulted(context, b"unknown tx").await, Some(true));
+        }
+
+        #[tokio::test]
+        async fn not_consulted() {
+            let context = RpcContext::for_tests().with_gateway_status_fallback(false);
+
+            assert_eq!(gateway_consulted(context, b"unknown tx").await, Some(false));
+        }
+
+        #[tokio::test]
+        async fn only_available_for_not_received() {
+            let context = RpcContext::for_tests().with_gateway_status_fallback(false);
+
+            assert_eq!(gateway_consulted(context, b"txn 1").await, None);
+        }
+    }
+
+    mod known_transactions {
+        use super::*;
+        use crate::transaction_status::KnownTransactions;
+
+        async fn status(known: KnownTransactions, hash: TransactionHash) -> TransactionStatus {
+            let context = RpcContext::for_tests()
+                .with_gateway_status_fallback(false)
+                .with_known_transactions(known);
+
+            resolve_status(context, hash).await.unwrap().finality_status
+        }
+
+        #[tokio::test]
+        async fn definite_miss_skips_database() {
+            // The stored transaction is deliberately left out, to tell whether storage was read.
+            let known = KnownTransactions::with_capacity(100);
+
+            let status = status(known, TransactionHash(felt_bytes!(b"txn 1"))).await;
+            assert_eq!(status, TransactionStatus::NotReceived);
+        }
+
+        #[tokio::test]
+        async fn false_positive_falls_through() {
+            let known = KnownTransactions::with_capacity(100);
+            let stored = TransactionHash(felt_bytes!(b"txn 1"));
+            let unknown = TransactionHash(felt_bytes!(b"unknown tx"));
+            known.insert(stored);
+            known.insert(unknown);
+
+            assert_eq!(
+                status(known.clone(), stored).await,
+                TransactionStatus::AcceptedOnL2
+            );
+            assert_eq!(status(known, unknown).await, TransactionStatus::NotReceived);
+        }
+    }
+
+    mod pending_status_policy {
+        use super::*;
+        use crate::transaction_status::PendingStatusPolicy;
+
+        async fn status(policy: PendingStatusPolicy, hash: &'static [u8]) -> TransactionStatus {
+            let context = RpcContext::for_tests_with_pending()
+                .await
+                .with_pending_status_policy(policy);
+
+            resolve_status(context, TransactionHash(felt_bytes!(hash)))
+                .await
+                .unwrap()
+                .finality_status
+        }
+
+        #[tokio::test]
+        async fn pending_by_default() {
+            assert_eq!(
+                status(PendingStatusPolicy::default(), b"pending tx hash 0").await,
+                TransactionStatus::Pending
+            );
+        }
+
+        #[tokio::test]
+        async fn accepted_on_l2() {
+            assert_eq!(
+                status(PendingStatusPolicy::AcceptedOnL2, b"pending tx hash 0").await,
+                TransactionStatus::AcceptedOnL2
+            );
+            // Transactions in storage are unaffected.
+            assert_eq!(
+                status(PendingStatusPolicy::AcceptedOnL2, b"txn 0").await,
+                TransactionStatus::AcceptedOnL1
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_block_header_is_inconsistent_state() {
+        let context = RpcContext::for_tests().with_gateway_status_fallback(false);
+        let storage = context.storage.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut db = storage.connection().unwrap();
+            db.delete_block_header_only(BlockHash(felt_bytes!(b"block 1")))
+                .unwrap();
+        })
+        .await
+        .unwrap();
+
+        let status = resolve_status(context.clone(), TransactionHash(felt_bytes!(b"txn 1")))
+            .await
+            .unwrap();
+        assert_eq!(status.finality_status, TransactionStatus::AcceptedOnL2);
+
+        let result = resolve_status_details(context, TransactionHash(felt_bytes!(b"txn 1"))).await;
+        assert_matches::assert_matches!(result, Err(GetGatewayTransactionError::InconsistentState));
+    }
+
+    mod empty_storage {
+        use super::*;
+        use crate::transaction_status::EmptyStorage;
+
+        fn empty_context() -> RpcContext {
+            let storage = Storage::in_memory().unwrap();
+            let empty_storage = EmptyStorage::load(&storage).unwrap();
+
+            RpcContext::for_tests()
+                .with_storage(storage)
+                .with_empty_storage(empty_storage)
+                .with_gateway_status_fallback(false)
+        }
+
+        #[tokio::test]
+        async fn skips_database() {
+            let context = empty_context();
+
+            let storage = context.storage.clone();
+            let before = storage.acquired_connections();
+            let status = resolve_status(context, TransactionHash(felt_bytes!(b"txn 1")))
+                .await
+                .unwrap();
+
+            assert_eq!(status.finality_status, TransactionStatus::NotReceived);
+            assert_eq!(storage.acquired_connections(), before);
+        }
+
+        #[tokio::test]
+        async fn queries_database_once_cleared() {
+            let context = empty_context();
+            context.empty_storage.clear();
+
+            let storage = context.storage.clone();
+            let before = storage.acquired_connections();
+            let status = resolve_status(context, TransactionHash(felt_bytes!(b"txn 1")))
+                .await
+                .unwrap();
+
+            assert_eq!(status.finality_status, TransactionStatus::NotReceived);
+            assert_eq!(storage.acquired_connections(), before + 1);
+        }
+    }
+
+    mod by_message_hash {
+        use super::*;
+        use pathfinder_common::{CallParam, EntryPoint, TransactionIndex};
+        use pathfinder_storage::BlockId;
+        use starknet_gateway_types::reply::transaction as gateway;
+
+        const L1_HANDLER: &[u8] = b"l1 handler tx hash";
+
+        /// The keccak256 of the message's sender 0x1, recipient 0x2, nonce 0x3, selector 0x4 and
+        /// its single element payload 0x5, preceded by the payload's length.
+        fn message_hash() -> H256 {
+            use sha3::{Digest, Keccak256};
+
+            let words = [1u8, 2, 3, 4, 1, 5].map(|value| {
+                let mut word = [0u8; 32];
+                word[31] = value;
+                word
+            });
+
+            H256(Keccak256::digest(words.concat()).into())
+        }
+
+        /// Returns a context with an L1 handler transaction, which consumed the message with
+        /// [message_hash], in a new latest block.
+        fn setup() -> RpcContext {
+            let context = RpcContext::for_tests();
+
+            let transaction = gateway::Transaction::L1Handler(gateway::L1HandlerTransaction {
+                contract_address: ContractAddress::new_or_panic(felt!("0x2")),
+                entry_point_selector: EntryPoint(felt!("0x4")),
+                nonce: TransactionNonce(felt!("0x3")),
+                calldata: vec![CallParam(felt!("0x1")), CallParam(felt!("0x5"))],
+                transaction_hash: TransactionHash(felt_bytes!(L1_HANDLER)),
+                version: TransactionVersion::ZERO,
+            });
+            let receipt = gateway::Receipt {
+                actual_fee: None,
+                events: vec![],
+                execution_resources: None,
+                execution_status: gateway::ExecutionStatus::Succeeded,
+                l1_to_l2_consumed_message: None,
+                l2_to_l1_messages: vec![],
+                revert_error: None,
+                transaction_hash: TransactionHash(felt_bytes!(L1_HANDLER)),
+                transaction_index: TransactionIndex::new_or_panic(0),
+            };
+
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let header = db_tx
+                .block_header(BlockId::Latest)
+                .unwrap()
+                .unwrap()
+                .child_builder()
+                .finalize_with_hash(BlockHash(felt_bytes!(b"l1 handler block")));
+            db_tx.insert_block_header(&header).unwrap();
+            db_tx
+                .insert_transaction_data(header.hash, header.number, &[(transaction, receipt)])
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            context
+        }
+
+        #[tokio::test]
+        async fn l1_handler() {
+            let input = TransactionStatusByMessageHashInput {
+                message_hash: message_hash(),
+            };
+            let status = transaction_status_by_message_hash(setup(), input)
+                .await
+                .unwrap();
+
+            assert_eq!(status, TransactionStatus::AcceptedOnL2);
+        }
+
+        #[tokio::test]
+        async fn unknown_message_is_not_found() {
+            let input = TransactionStatusByMessageHashInput {
+                message_hash: H256::from_low_u64_be(0xabcdef),
+            };
+            let result = transaction_status_by_message_hash(setup(), input).await;
+
+            assert_matches::assert_matches!(result, Err(TransactionLookupError::TxnHashNotFound));
+        }
+    }
+
+    mod latest_for_address {
+        use super::*;
+        use pathfinder_common::{ContractNonce, TransactionIndex};
+        use pathfinder_storage::types::state_update::{Nonce, StateDiff};
+        use pathfinder_storage::BlockId;
+        use starknet_gateway_types::reply::transaction as gateway;
+
+        fn sender() -> ContractAddress {
+            ContractAddress::new_or_panic(felt_bytes!(b"sender"))
+        }
+
+        /// Returns an invoke transaction by [sender] with `nonce`, along with its receipt.
+        fn invoke(hash: &[u8], nonce: u64) -> (gateway::Transaction, gateway::Receipt) {
+            let transaction_hash = TransactionHash(felt_bytes!(hash));
+            let transaction = gateway::Transaction::Invoke(gateway::InvokeTransaction::V1(
+                gateway::InvokeTransactionV1 {
+                    calldata: vec![],
+                    sender_address: sender(),
+                    max_fee: Fee(felt!("0x100")),
+                    signature: vec![],
+                    nonce: TransactionNonce(stark_hash::Felt::from_u64(nonce)),
+                    transaction_hash,
+                },
+            ));
+            let receipt = gateway::Receipt {
+                actual_fee: None,
+                events: vec![],
+                execution_resources: None,
+                execution_status: gateway::ExecutionStatus::Succeeded,
+                l1_to_l2_consumed_message: None,
+                l2_to_l1_messages: vec![],
+                revert_error: None,
+                transaction_hash,
+                transaction_index: TransactionIndex::new_or_panic(0),
+            };
+
+            (transaction, receipt)
+        }
+
+        /// Commits a block for each of [sender]'s first `count` transactions.
+        fn commit_sender_blocks(context: &RpcContext, count: u64) {
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            for nonce in 0..count {
+                let header = db_tx
+                    .block_header(BlockId::Latest)
+                    .unwrap()
+                    .unwrap()
+                    .child_builder()
+                    .finalize_with_hash(BlockHash(felt_bytes!(
+                        format!("sender block {nonce}").as_bytes()
+                    )));
+                db_tx.insert_block_header(&header).unwrap();
+                db_tx
+                    .insert_transaction_data(
+                        header.hash,
+                        header.number,
+                        &[invoke(format!("sender tx {nonce}").as_bytes(), nonce)],
+                    )
+                    .unwrap();
+                db_tx
+                    .insert_canonical_state_diff(
+                        header.number,
+                        &StateDiff {
+                            nonces: vec![Nonce {
+                                contract_address: sender(),
+                                nonce: ContractNonce(stark_hash::Felt::from_u64(nonce + 1)),
+                            }],
+                            ..Default::default()
+                        },
+                    )
+                    .unwrap();
+            }
+            db_tx.commit().unwrap();
+        }
+
+        fn input(address: ContractAddress) -> LatestTransactionStatusForAddressInput {
+            LatestTransactionStatusForAddressInput { address }
+        }
+
+        #[tokio::test]
+        async fn latest_block() {
+            let context = RpcContext::for_tests_with_pending().await;
+            commit_sender_blocks(&context, 2);
+
+            let latest = latest_transaction_status_for_address(context, input(sender()))
+                .await
+                .unwrap();
+            assert_eq!(
+                latest,
+                LatestTransactionStatus {
+                    transaction_hash: TransactionHash(felt_bytes!(b"sender tx 1")),
+                    status: TransactionStatus::AcceptedOnL2,
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn pending_is_preferred() {
+            let context = RpcContext::for_tests_with_pending().await;
+            let block = context
+                .pending_data
+                .as_ref()
+                .unwrap()
+                .block()
+                .await
+                .unwrap();
+            let (transaction, receipt) = invoke(b"sender tx 2", 2);
+            let mut transactions = block.transactions.clone();
+            let mut receipts = block.transaction_receipts.clone();
+            transactions.push(transaction);
+            receipts.push(receipt);
+
+            let context =
+                RpcContext::for_tests_with_pending_transactions(transactions, receipts).await;
+            commit_sender_blocks(&context, 2);
+
+            let latest = latest_transaction_status_for_address(context, input(sender()))
+                .await
+                .unwrap();
+            assert_eq!(
+                latest,
+                LatestTransactionStatus {
+                    transaction_hash: TransactionHash(felt_bytes!(b"sender tx 2")),
+                    status: TransactionStatus::Pending,
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn no_transactions_is_not_found() {
+            let context = RpcContext::for_tests_with_pending().await;
+            commit_sender_blocks(&context, 2);
+
+            let other = ContractAddress::new_or_panic(felt_bytes!(b"other sender"));
+            let result = latest_transaction_status_for_address(context, input(other)).await;
+
+            assert_matches::assert_matches!(result, Err(TransactionLookupError::TxnHashNotFound));
+        }
+    }
+
+    mod transaction_is_known {
+        use super::*;
+
+        fn input(transaction_hash: TransactionHash) -> TransactionIsKnownInput {
+            TransactionIsKnownInput { transaction_hash }
+        }
+
+        #[tokio::test]
+        async fn known_in_pending() {
+            let context = RpcContext::for_tests_with_pending().await;
+            let hash = TransactionHash(felt_bytes!(b"pending tx hash 0"));
+
+            let storage = context.storage.clone();
+            let before = storage.acquired_connections();
+            let known = transaction_is_known(context, input(hash)).await.unwrap();
+
+            assert!(known);
+            assert_eq!(storage.acquired_connections(), before);
+        }
+
+        #[tokio::test]
+        async fn known_in_database() {
+            let context = RpcContext::for_tests_with_pending().await;
+            let hash = TransactionHash(felt_bytes!(b"txn 1"));
+
+            let known = transaction_is_known(context, input(hash)).await.unwrap();
+
+            assert!(known);
+        }
+
+        #[tokio::test]
+        async fn unknown() {
+            // Nothing listens on this port, so the query would fail if it consulted the gateway.
+            let gateway = starknet_gateway_client::Client::with_base_url(
+                "http://127.0.0.1:1".parse().unwrap(),
+            )
+            .unwrap();
+            let context = RpcContext {
+                sequencer: gateway,
+                ..RpcContext::for_tests_with_pending().await
+            };
+            let hash = TransactionHash(felt_bytes!(b"unknown tx"));
+
+            let known = transaction_is_known(context, input(hash)).await.unwrap();
+
+            assert!(!known);
+        }
+    }
+
+    mod local_only {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use super::*;
+
+        /// Serves a rejection of any transaction, and counts the queries.
+        fn counting_gateway() -> (starknet_gateway_client::Client, Arc<AtomicUsize>) {
+            let reply = serde_json::json!({
+                "status": "REJECTED",
+                "transaction_failure_reason": {
+                    "code": "INVALID_TRANSACTION_NONCE",
+                    "error_message": "Invalid nonce",
+                },
+            })
+            .to_string();
+            let queries = Arc::new(AtomicUsize::new(0));
+            let counter = queries.clone();
+            let make_service = hyper::service::make_service_fn(move |_| {
+                let reply = reply.clone();
+                let counter = counter.clone();
+                async move {
+                    Ok::<_, std::convert::Infallible>(hyper::service::service_fn(move |_| {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                        let body = hyper::Body::from(reply.clone());
+                        async move { Ok::<_, std::convert::Infallible>(hyper::Response::new(body)) }
+                    }))
+                }
+            });
+            let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+            let url = format!("http://{}", server.local_addr()).parse().unwrap();
+            tokio::spawn(server);
+
+            let gateway = starknet_gateway_client::Client::with_base_url(url).unwrap();
+            (gateway, queries)
+        }
+
+        fn input(local_only: bool) -> GetGatewayTransactionInput {
+            GetGatewayTransactionInput {
+                verify: true,
+                local_only,
+                ..super::input(TransactionHash(felt_bytes!(b"unknown tx")))
+            }
+        }
+
+        #[tokio::test]
+        async fn gateway_is_never_called() {
+            let (gateway, queries) = counting_gateway();
+            let context = RpcContext {
+                sequencer: gateway,
+                ..RpcContext::for_tests()
+            }
+            .with_gateway_pending_fallback(true);
+
+            let status = get_transaction_status(context.clone(), input(true))
+                .await
+                .unwrap();
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+            );
+            assert_eq!(queries.load(Ordering::Relaxed), 0);
+
+            // The gateway does know the transaction.
+            let status = get_transaction_status(context, input(false)).await.unwrap();
+            assert_matches::assert_matches!(
+                status,
+                GetTransactionStatusOutput {
+                    rejection: Some(_),
+                    ..
+                }
+            );
+            assert_ne!(queries.load(Ordering::Relaxed), 0);
+        }
+    }
+
+    mod fallback_gateways {
+        use super::*;
+
+        fn input() -> GetGatewayTransactionInput {
+            super::input(TransactionHash(felt_bytes!(b"unknown tx")))
+        }
+
+        fn unreachable() -> starknet_gateway_client::Client {
+            // Nothing listens on this port, so any query fails.
+            starknet_gateway_client::Client::with_base_url("http://127.0.0.1:1".parse().unwrap())
+                .unwrap()
+        }
+
+        fn rejecting() -> starknet_gateway_client::Client {
+            let url = serve(serde_json::json!({
+                "status": "REJECTED",
+                "transaction_failure_reason": {
+                    "code": "INVALID_TRANSACTION_NONCE",
+                    "error_message": "Invalid nonce",
+                },
+            }));
+            starknet_gateway_client::Client::with_base_url(url).unwrap()
+        }
+
+        #[tokio::test]
+        async fn used_while_primary_is_unavailable() {
+            let context = RpcContext {
+                sequencer: unreachable(),
+                ..RpcContext::for_tests()
+            }
+            .with_fallback_gateways(vec![unreachable(), rejecting()]);
+
+            let status = get_transaction_status(context, input()).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput {
+                    rejection: Some(("Invalid nonce".to_owned(), RejectionReason::InvalidNonce)),
+                    ..GetTransactionStatusOutput::new(TransactionStatus::Rejected)
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn definitive_answer_is_not_failed_over() {
+            let url = serve(serde_json::json!({ "status": "NOT_RECEIVED" }));
+            let fallback = starknet_gateway_client::Client::with_base_url(url).unwrap();
+            let context = RpcContext {
+                sequencer: rejecting(),
+                ..RpcContext::for_tests()
+            }
+            .with_fallback_gateways(vec![fallback]);
+
+            let status = get_transaction_status(context, input()).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput {
+                    rejection: Some(("Invalid nonce".to_owned(), RejectionReason::InvalidNonce)),
+                    ..GetTransactionStatusOutput::new(TransactionStatus::Rejected)
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn all_unavailable() {
+            let context = RpcContext {
+                sequencer: unreachable(),
+                ..RpcContext::for_tests()
+            }
+            .with_fallback_gateways(vec![unreachable()]);
+
+            let result = get_transaction_status(context, input()).await;
+
+            assert_matches::assert_matches!(
+                result,
+                Err(GetGatewayTransactionError::GatewayUnavailable)
+            );
+        }
+    }
+
+    mod include_proof {
+        use super::*;
+        use pathfinder_common::{BlockHeader, StorageValue};
+        use pathfinder_merkle_tree::contract_state::update_contract_state;
+        use pathfinder_merkle_tree::StorageCommitmentTree;
+        use pathfinder_storage::types::state_update::StateDiff;
+        use starknet_gateway_types::reply::state_update::StorageDiff;
+
+        fn input(hash: &[u8]) -> GetGatewayTransactionInput {
+            GetGatewayTransactionInput {
+                include_proof: true,
+                ..super::input(TransactionHash(felt_bytes!(hash)))
+            }
+        }
+
+        /// Commits a block on top of "latest" which records the genesis block's hash in the block
+        /// hash contract, and the block's state on L1.
+        fn record_genesis_hash(context: &RpcContext) -> BlockHeader {
+            let mut db = context.storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+
+            let genesis = db_tx
+                .block_header(BlockNumber::GENESIS.into())
+                .unwrap()
+                .unwrap();
+            let latest = db_tx
+                .block_header(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+                .unwrap();
+
+            let contract = ContractAddress::new_or_panic(felt!("0x1"));
+            let key = StorageAddress::new_or_panic(felt!("0x0"));
+            let value = StorageValue(genesis.hash.0);
+
+            let mut tree = StorageCommitmentTree::load(&db_tx, latest.storage_commitment).unwrap();
+            let contract_state_hash = update_contract_state(
+                contract,
+                &[StorageDiff { key, value }],
+                None,
+                Some(ClassHash::ZERO),
+                &tree,
+                &db_tx,
+            )
+            .unwrap();
+            tree.set(contract, contract_state_hash).unwrap();
+            let (storage_commitment, nodes) = tree.commit().unwrap();
+            db_tx
+                .insert_storage_trie(storage_commitment, &nodes)
+                .unwrap();
+
+            let header = latest
+                .child_builder()
+                .with_storage_commitment(storage_commitment)
+                .with_calculated_state_commitment()
+                .finalize_with_hash(BlockHash(felt_bytes!(b"block 3")));
+            db_tx.insert_block_header(&header).unwrap();
+            db_tx
+                .insert_state_diff(
+                    header.number,
+                    &StateDiff::default().add_storage_update(contract, key, value),
+                )
+                .unwrap();
+            db_tx
+                .upsert_l1_state(&EthereumStateUpdate {
+                    state_root: header.state_commitment,
+                    block_number: header.number,
+                    block_hash: header.hash,
+                    l1_block_number: Some(10),
+                    l1_tx_hash: None,
+                })
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            header
+        }
+
+        #[tokio::test]
+        async fn l1_accepted_with_proof() {
+            let context = RpcContext::for_tests();
+            let header = record_genesis_hash(&context);
+
+            let status = get_transaction_status_details(context, input(b"txn 0"))
+                .await
+                .unwrap();
+
+            assert_eq!(status.finality_status, TransactionStatus::AcceptedOnL1);
+            let proof = status.block_proof.unwrap();
+            assert_eq!(proof.block_number, header.number);
+            assert_eq!(proof.state_commitment, header.state_commitment);
+            assert_eq!(proof.key, StorageAddress::new_or_panic(felt!("0x0")));
+
+            let proof = serde_json::to_value(proof.proof).unwrap();
+            let storage_proof = proof["contract_data"]["storage_proofs"][0]
+                .as_array()
+                .unwrap();
+            assert!(!storage_proof.is_empty());
+        }
+
+        #[tokio::test]
+        async fn unrecorded_block_hash_is_unavailable() {
+            let context = RpcContext::for_tests();
+
+            let error = get_transaction_status_details(context, input(b"txn 0"))
+                .await
+                .unwrap_err();
+
+            assert_matches::assert_matches!(
+                error,
+                GetTransactionStatusDetailsError::ProofUnavailable
+            );
+        }
+
+        #[tokio::test]
+        async fn l2_accepted_has_no_proof() {
+            let context = RpcContext::for_tests();
+
+            let status = get_transaction_status_details(context, input(b"txn 1"))
+                .await
+                .unwrap();
+
+            assert_eq!(status.finality_status, TransactionStatus::AcceptedOnL2);
+            assert_eq!(status.block_proof, None);
+        }
+    }
+
+    mod wait_for {
+        use super::*;
+        use pathfinder_storage::BlockId;
+        use tokio::sync::broadcast;
+
+        fn input(
+            hash: &[u8],
+            wait_for: TransactionStatus,
+            timeout_ms: u64,
+        ) -> GetGatewayTransactionInput {
+            GetGatewayTransactionInput {
+                wait_for: Some(wait_for),
+                timeout_ms: Some(timeout_ms),
+                ..super::input(TransactionHash(felt_bytes!(hash)))
+            }
+        }
+
+        /// Commits the pending block after `delay`, and announces it on `new_heads`.
+        async fn commit_pending_after(
+            context: &RpcContext,
+            new_heads: broadcast::Sender<crate::websocket::types::BlockHeader>,
+            delay: Duration,
+        ) {
+            let pending = context.pending_data.as_ref().unwrap();
+            let pending = pending.block().await.unwrap();
+            let storage = context.storage.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                let header = tokio::task::spawn_blocking(move || {
+                    let mut db = storage.connection().unwrap();
+                    let db_tx = db.transaction().unwrap();
+                    let header = db_tx
+                        .block_header(BlockId::Latest)
+                        .unwrap()
+                        .unwrap()
+                        .child_builder()
+                        .finalize_with_hash(BlockHash(felt_bytes!(b"committed pending")));
+                    db_tx.insert_block_header(&header).unwrap();
+
+                    let data = pending
+                        .transactions
+                        .iter()
+                        .cloned()
+                        .zip(pending.transaction_receipts.iter().cloned())
+                        .collect::<Vec<_>>();
+                    db_tx
+                        .insert_transaction_data(header.hash, header.number, &data)
+                        .unwrap();
+                    db_tx.commit().unwrap();
+                    header
+                })
+                .await
+                .unwrap();
+
+                new_heads
+                    .send(crate::websocket::types::BlockHeader {
+                        block_hash: header.hash,
+                        block_number: header.number,
+                        gas_price: None,
+                        parent_block_hash: header.parent_hash,
+                        sequencer_address: None,
+                        state_commitment: header.state_commitment,
+                        status: starknet_gateway_types::reply::Status::AcceptedOnL2,
+                        timestamp: header.timestamp,
+                        starknet_version: header.starknet_version,
+                    })
+                    .unwrap();
+            });
+        }
+
+        #[tokio::test]
+        async fn accepted_on_l2_during_wait() {
+            let (new_heads, _) = broadcast::channel(1);
+            let context = RpcContext::for_tests_with_pending()
+                .await
+                .with_new_heads(new_heads.clone());
+
+            // Commit the pending block a while into the wait.
+            commit_pending_after(&context, new_heads, Duration::from_millis(200)).await;
+
+            let start = std::time::Instant::now();
+            let input = input(
+                b"pending tx hash 0",
+                TransactionStatus::AcceptedOnL2,
+                30_000,
+            );
+            let status = get_transaction_status(context, input).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+            // The new block ended the wait, rather than a recheck.
+            assert!(start.elapsed() < WAIT_POLL_INTERVAL);
+        }
+
+        #[tokio::test]
+        async fn rechecks_are_staggered() {
+            use crate::transaction_status::WaitJitter;
+
+            const SEED: u64 = 7;
+            const COMMIT: Duration = Duration::from_millis(200);
+            let max = Duration::from_secs(1);
+
+            let (new_heads, _) = broadcast::channel(1);
+            let mut context = RpcContext::for_tests_with_pending()
+                .await
+                .with_new_heads(new_heads.clone());
+            context.wait_jitter = WaitJitter::with_seed(max, SEED);
+
+            // The two waits draw the first two delays of the seeded RNG, in either order.
+            let expected = WaitJitter::with_seed(max, SEED);
+            let mut delays = [expected.delay(), expected.delay()];
+            delays.sort();
+
+            commit_pending_after(&context, new_heads, COMMIT).await;
+
+            let start = std::time::Instant::now();
+            let wait = |hash: &'static [u8]| {
+                let context = context.clone();
+                async move {
+                    let input = input(hash, TransactionStatus::AcceptedOnL2, 30_000);
+                    let status = get_transaction_status(context, input).await.unwrap();
+                    (status, start.elapsed())
+                }
+            };
+            let (first, second) =
+                tokio::join!(wait(b"pending tx hash 0"), wait(b"pending tx hash 1"));
+
+            let status = GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2);
+            assert_eq!(first.0, status);
+            assert_eq!(second.0, status);
+
+            // Each wait re-checked the status only after its own delay following the new block.
+            let mut elapsed = [first.1, second.1];
+            elapsed.sort();
+            assert!(elapsed[0] >= COMMIT + delays[0], "{elapsed:?} {delays:?}");
+            assert!(elapsed[1] >= COMMIT + delays[1], "{elapsed:?} {delays:?}");
+            assert!(elapsed[1] < WAIT_POLL_INTERVAL, "{elapsed:?}");
+        }
+
+        #[tokio::test]
+        async fn already_reached() {
+            let context = RpcContext::for_tests();
+            let input = input(b"txn 0", TransactionStatus::AcceptedOnL2, 30_000);
+
+            let start = std::time::Instant::now();
+            let status = get_transaction_status(context, input).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL1)
+            );
+            assert!(start.elapsed() < WAIT_POLL_INTERVAL);
+        }
+
+        #[tokio::test]
+        async fn timeout_returns_current_status() {
+            let context = RpcContext::for_tests_with_pending().await;
+            let input = input(b"pending tx hash 0", TransactionStatus::AcceptedOnL1, 100);
+
+            let start = std::time::Instant::now();
+            let status = get_transaction_status(context, input).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::Pending)
+            );
+            assert!(start.elapsed() >= Duration::from_millis(100));
+        }
+
+        #[tokio::test]
+        async fn disabled_returns_current_status() {
+            let context = RpcContext::for_tests_with_pending()
+                .await
+                .with_status_waits(false);
+            let input = input(
+                b"pending tx hash 0",
+                TransactionStatus::AcceptedOnL1,
+                30_000,
+            );
+
+            let start = std::time::Instant::now();
+            let status = get_transaction_status(context, input).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::Pending)
+            );
+            assert!(start.elapsed() < WAIT_POLL_INTERVAL);
+        }
+
+        #[tokio::test]
+        async fn disabling_ends_ongoing_wait() {
+            let context = RpcContext::for_tests_with_pending().await;
+
+            let wait = tokio::spawn({
+                let context = context.clone();
+                let input = input(
+                    b"pending tx hash 0",
+                    TransactionStatus::AcceptedOnL1,
+                    30_000,
+                );
+                async move { get_transaction_status(context, input).await }
+            });
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert!(!wait.is_finished());
+
+            let start = std::time::Instant::now();
+            let input = SetStatusWaitsInput { enabled: false };
+            let was_enabled = set_status_waits(context.clone(), input).await.unwrap();
+            assert!(was_enabled);
+
+            let status = wait.await.unwrap().unwrap();
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::Pending)
+            );
+            assert!(start.elapsed() < WAIT_POLL_INTERVAL);
+
+            // Waits resume once enabled again.
+            let input = SetStatusWaitsInput { enabled: true };
+            assert!(!set_status_waits(context.clone(), input).await.unwrap());
+            assert!(context.status_waits.is_enabled());
+        }
+
+        #[test]
+        fn reaches() {
+            use TransactionStatus::*;
+
+            assert!(AcceptedOnL1.reaches(AcceptedOnL2));
+            assert!(AcceptedOnL2.reaches(AcceptedOnL2));
+            assert!(!Pending.reaches(AcceptedOnL2));
+            assert!(!NotReceived.reaches(Received));
+            // Final statuses end the wait.
+            assert!(Rejected.reaches(AcceptedOnL2));
+            assert!(Reverted.reaches(AcceptedOnL1));
+            // Statuses outside of the acceptance path are reached right away.
+            assert!(Pending.reaches(Rejected));
+        }
+    }
+
+    mod gateway_circuit_breaker {
+        use super::*;
+
+        const COOLDOWN: Duration = Duration::from_millis(200);
+
+        fn input() -> GetGatewayTransactionInput {
+            super::input(TransactionHash(felt_bytes!(b"unknown tx")))
+        }
+
+        #[tokio::test]
+        async fn open_cooldown_and_recovery() {
+            // Nothing listens on this port, so any query fails.
+            let unreachable = starknet_gateway_client::Client::with_base_url(
+                "http://127.0.0.1:1".parse().unwrap(),
+            )
+            .unwrap();
+            let context = RpcContext {
+                sequencer: unreachable,
+                ..RpcContext::for_tests()
+            }
+            .with_gateway_retry(1, Duration::ZERO)
+            .with_gateway_circuit_breaker(2, Duration::from_secs(60), COOLDOWN);
+
+            for _ in 0..2 {
+                let result = get_transaction_status(context.clone(), input()).await;
+                assert_matches::assert_matches!(
+                    result,
+                    Err(GetGatewayTransactionError::GatewayUnavailable)
+                );
+            }
+
+            // The breaker is open, so even a gateway which has since recovered is not queried.
+            let url = serve(serde_json::json!({
+                "status": "REJECTED",
+                "transaction_failure_reason": {
+                    "code": "INVALID_TRANSACTION_NONCE",
+                    "error_message": "Invalid nonce",
+                },
+            }));
+            let context = RpcContext {
+                sequencer: starknet_gateway_client::Client::with_base_url(url).unwrap(),
+                ..context
+            };
+            let result = get_transaction_status(context.clone(), input()).await;
+            assert_matches::assert_matches!(
+                result,
+                Err(GetGatewayTransactionError::GatewayUnavailable)
+            );
+
+            // Once cooled down, the probe reaches the gateway and closes the breaker.
+            tokio::time::sleep(COOLDOWN).await;
+            let rejected = GetTransactionStatusOutput {
+                rejection: Some(("Invalid nonce".to_owned(), RejectionReason::InvalidNonce)),
+                ..GetTransactionStatusOutput::new(TransactionStatus::Rejected)
+            };
+            let status = get_transaction_status(context.clone(), input())
+                .await
+                .unwrap();
+            assert_eq!(status, rejected);
+
+            let status = get_transaction_status(
+                context,
+                GetGatewayTransactionInput {
+                    transaction_hash: TransactionHash(felt_bytes!(b"other unknown tx")),
+                    ..input()
+                },
+            )
+            .await
+            .unwrap();
+            assert_eq!(status, rejected);
+        }
+    }
+
+    mod gateway_status_fallback_disabled {
+        use super::*;
+
+        /// A context whose gateway fails every query, with the gateway status fallback disabled.
+        fn context() -> RpcContext {
+            // Nothing listens on this port, so any gateway query fails.
+            let gateway = starknet_gateway_client::Client::with_base_url(
+                "http://127.0.0.1:1".parse().unwrap(),
+            )
+            .unwrap();
+            RpcContext {
+                sequencer: gateway,
+                ..RpcContext::for_tests()
+            }
+            .with_gateway_timeout(Duration::from_millis(100))
+            .with_gateway_status_fallback(false)
+        }
+
+        #[tokio::test]
+        async fn unknown_is_not_received() {
+            let input = input(TransactionHash(felt_bytes!(b"unknown tx")));
+            let status = get_transaction_status(context(), input).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::NotReceived)
+            );
+        }
+
+        #[tokio::test]
+        async fn known_is_unaffected() {
+            let input = input(TransactionHash(felt_bytes!(b"txn 1")));
+            let status = get_transaction_status(context(), input).await.unwrap();
+
+            assert_eq!(
+                status,
+                GetTransactionStatusOutput::new(TransactionStatus::AcceptedOnL2)
+            );
+        }
+
+        #[tokio::test]
+        async fn plan_excludes_gateway() {
+            let input = ExplainTransactionStatusInput {
+                transaction_hash: TransactionHash(felt_bytes!(b"unknown tx")),
+            };
+            let plan = explain_transaction_status(context(), input).await.unwrap();
+
+            assert_eq!(plan.backends, vec![StatusBackend::Database]);
+        }
+    }
+
+    mod gateway_cache {
+        use super::*;
+        use starknet_gateway_client::MockGatewayApi;
+        use starknet_gateway_types::reply;
+
+        const TIMEOUT: Duration = Duration::from_secs(5);
+
+        fn gateway_reply(status: TransactionStatus) -> reply::Transaction {
+            reply::Transaction {
+                block_hash: None,
+                block_number: None,
+                status: status.as_gateway_status().unwrap(),
+                transaction: None,
+                transaction_index: None,
+                transaction_failure_reason: None,
+            }
+        }
+
+        #[tokio::test]
+        async fn rejected_is_only_fetched_once() {
+            let mut gateway = MockGatewayApi::new();
+            gateway
+                .expect_transaction()
+                .times(1)
+                .returning(|_| Ok(gateway_reply(TransactionStatus::Rejected)));
+
+            let cache = GatewayStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"rejected tx"));
+
+            for _ in 0..2 {
+                let status = gateway_status(&gateway, &cache, TIMEOUT, NO_RETRY, tx_hash)
+                    .await
+                    .unwrap()
+                    .status;
+                assert_eq!(status, TransactionStatus::Rejected);
+            }
+        }
+
+        #[tokio::test]
+        async fn rejection_reason() {
+            let mut gateway = MockGatewayApi::new();
+            gateway.expect_transaction().times(1).returning(|_| {
+                Ok(reply::Transaction {
+                    transaction_failure_reason: Some(reply::transaction::Failure {
+                        code: "INVALID_TRANSACTION_NONCE".to_owned(),
+                        error_message: "Invalid nonce".to_owned(),
+                    }),
+                    ..gateway_reply(TransactionStatus::Rejected)
+                })
+            });
+
+            let cache = GatewayStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"rejected tx"));
+
+            let status = gateway_status(&gateway, &cache, TIMEOUT, NO_RETRY, tx_hash)
+                .await
+                .unwrap();
+            assert_eq!(status.rejection, Some("Invalid nonce".to_owned()));
+
+            // Cached statuses are not reported as a new rejection.
+            let status = gateway_status(&gateway, &cache, TIMEOUT, NO_RETRY, tx_hash)
+                .await
+                .unwrap();
+            assert_eq!(status.status, TransactionStatus::Rejected);
+            assert_eq!(status.rejection, None);
+        }
+
+        #[tokio::test]
+        async fn not_received_is_not_a_rejection() {
+            let mut gateway = MockGatewayApi::new();
+            gateway.expect_transaction().times(1).returning(|_| {
+                Ok(reply::Transaction {
+                    // Even with a failure attached, this is not a rejection.
+                    transaction_failure_reason: Some(reply::transaction::Failure {
+                        code: "TRANSACTION_NOT_FOUND".to_owned(),
+                        error_message: "Transaction not found".to_owned(),
+                    }),
+                    ..gateway_reply(TransactionStatus::NotReceived)
+                })
+            });
+
+            let cache = GatewayStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"unknown tx"));
+
+            let status = gateway_status(&gateway, &cache, TIMEOUT, NO_RETRY, tx_hash)
+                .await
+                .unwrap();
+            assert_eq!(status.status, TransactionStatus::NotReceived);
+            assert_eq!(status.rejection, None);
+            assert_eq!(status.abort_reason, None);
+        }
+
+        #[tokio::test]
+        async fn abort_reason() {
+            let mut gateway = MockGatewayApi::new();
+            gateway.expect_transaction().times(1).returning(|_| {
+                Ok(reply::Transaction {
+                    transaction_failure_reason: Some(reply::transaction::Failure {
+                        code: "REORG".to_owned(),
+                        error_message: "Block was reorged".to_owned(),
+                    }),
+                    ..gateway_reply(TransactionStatus::Aborted)
+                })
+            });
+
+            let cache = GatewayStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"aborted tx"));
+
+            let status = gateway_status(&gateway, &cache, TIMEOUT, NO_RETRY, tx_hash)
+                .await
+                .unwrap();
+            assert_eq!(status.status, TransactionStatus::Aborted);
+            assert_eq!(status.abort_reason, Some(AbortReason::Reorg));
+            assert_eq!(status.rejection, None);
+        }
+
+        #[tokio::test]
+        async fn not_received_expires() {
+            let mut gateway = MockGatewayApi::new();
+            gateway
+                .expect_transaction()
+                .times(2)
+                .returning(|_| Ok(gateway_reply(TransactionStatus::NotReceived)));
+
+            let cache = GatewayStatusCache::new(std::time::Duration::ZERO);
+            let tx_hash = TransactionHash(felt_bytes!(b"unknown tx"));
+
+            for _ in 0..2 {
+                let status = gateway_status(&gateway, &cache, TIMEOUT, NO_RETRY, tx_hash)
+                    .await
+                    .unwrap()
+                    .status;
+                assert_eq!(status, TransactionStatus::NotReceived);
+            }
+        }
+
+        #[tokio::test]
+        async fn server_error_is_unavailable() {
+            let mut gateway = MockGatewayApi::new();
+            gateway.expect_transaction().times(1).returning(|_| {
+                let response = http::Response::builder()
+                    .status(http::StatusCode::SERVICE_UNAVAILABLE)
+                    .body("")
+                    .unwrap();
+                let error = reqwest::Response::from(response)
+                    .error_for_status()
+                    .unwrap_err();
+                Err(SequencerError::ReqwestError(error))
+            });
+
+            let cache = GatewayStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"unknown tx"));
+
+            let err = gateway_status(&gateway, &cache, TIMEOUT, NO_RETRY, tx_hash)
+                .await
+                .unwrap_err();
+            assert_matches::assert_matches!(err, GetGatewayTransactionError::GatewayUnavailable);
+        }
+
+        #[tokio::test]
+        async fn starknet_error_is_internal() {
+            use starknet_gateway_types::error::{KnownStarknetErrorCode, StarknetError};
+
+            let mut gateway = MockGatewayApi::new();
+            gateway.expect_transaction().times(1).returning(|_| {
+                Err(SequencerError::StarknetError(StarknetError {
+                    code: KnownStarknetErrorCode::MalformedRequest.into(),
+                    message: "Malformed request".to_owned(),
+                }))
+            });
+
+            let cache = GatewayStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"unknown tx"));
+
+            let err = gateway_status(&gateway, &cache, TIMEOUT, NO_RETRY, tx_hash)
+                .await
+                .unwrap_err();
+            assert_matches::assert_matches!(err, GetGatewayTransactionError::Internal(_));
+        }
+
+        #[tokio::test]
+        async fn other_statuses_are_not_cached() {
+            let mut gateway = MockGatewayApi::new();
+            gateway
+                .expect_transaction()
+                .times(2)
+                .returning(|_| Ok(gateway_reply(TransactionStatus::Received)));
+
+            let cache = GatewayStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"received tx"));
+
+            for _ in 0..2 {
+                let status = gateway_status(&gateway, &cache, TIMEOUT, NO_RETRY, tx_hash)
+                    .await
+                    .unwrap()
+                    .status;
+                assert_eq!(status, TransactionStatus::Received);
+            }
+        }
+    }
+
+    mod gateway_retry {
+        use super::*;
+        use starknet_gateway_types::reply;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        const RETRY: GatewayRetry = GatewayRetry {
+            max_attempts: 3,
+            base_delay: Duration::from_secs(1),
+        };
+
+        /// Fails with a server error for the first `failures` queries and succeeds afterwards.
+        struct FlakyGateway {
+            failures: u32,
+            calls: AtomicU32,
+        }
+
+        impl FlakyGateway {
+            fn new(failures: u32) -> Self {
+                Self {
+                    failures,
+                    calls: AtomicU32::new(0),
+                }
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl GatewayApi for FlakyGateway {
+            async fn transaction(
+                &self,
+                _: TransactionHash,
+            ) -> Result<reply::Transaction, SequencerError> {
+                if self.calls.fetch_add(1, Ordering::Relaxed) < self.failures {
+                    let response = http::Response::builder()
+                        .status(http::StatusCode::SERVICE_UNAVAILABLE)
+                        .body("")
+                        .unwrap();
+                    let error = reqwest::Response::from(response)
+                        .error_for_status()
+                        .unwrap_err();
+                    return Err(SequencerError::ReqwestError(error));
+                }
+
+                Ok(reply::Transaction {
+                    block_hash: None,
+                    block_number: None,
+                    status: reply::Status::Received,
+                    transaction: None,
+                    transaction_index: None,
+                    transaction_failure_reason: None,
+                })
+            }
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn succeeds_after_transient_failures() {
+            let gateway = FlakyGateway::new(2);
+            let cache = GatewayStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"unknown tx"));
+
+            let start = tokio::time::Instant::now();
+            let status = gateway_status(&gateway, &cache, Duration::from_secs(10), RETRY, tx_hash)
+                .await
+                .unwrap()
+                .status;
+
+            assert_eq!(status, TransactionStatus::Received);
+            assert_eq!(gateway.calls.load(Ordering::Relaxed), 3);
+            // Backed off for 1s and then 2s.
+            assert_eq!(start.elapsed(), Duration::from_secs(3));
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn gives_up_after_max_attempts() {
+            let gateway = FlakyGateway::new(3);
+            let cache = GatewayStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"unknown tx"));
+
+            let err = gateway_status(&gateway, &cache, Duration::from_secs(10), RETRY, tx_hash)
+                .await
+                .unwrap_err();
+
+            assert_matches::assert_matches!(err, GetGatewayTransactionError::GatewayUnavailable);
+            assert_eq!(gateway.calls.load(Ordering::Relaxed), 3);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn respects_timeout() {
+            let gateway = FlakyGateway::new(2);
+            let cache = GatewayStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"unknown tx"));
+
+            // Only leaves time for the first retry.
+            let start = tokio::time::Instant::now();
+            let err = gateway_status(&gateway, &cache, Duration::from_secs(2), RETRY, tx_hash)
+                .await
+                .unwrap_err();
+
+            assert_matches::assert_matches!(err, GetGatewayTransactionError::GatewayUnavailable);
+            assert_eq!(gateway.calls.load(Ordering::Relaxed), 2);
+            assert!(start.elapsed() < Duration::from_secs(2));
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn only_failed_queries_are_retried() {
+            let gateway = FlakyGateway::new(1);
+            let cache = GatewayStatusCache::default();
+            let hashes = [
+                TransactionHash(felt_bytes!(b"unknown tx 0")),
+                TransactionHash(felt_bytes!(b"unknown tx 1")),
+            ];
+
+            let statuses =
+                gateway_statuses(&gateway, &cache, Duration::from_secs(10), RETRY, &hashes)
+                    .await
+                    .unwrap();
+
+            assert_eq!(statuses.len(), 2);
+            for status in statuses {
+                assert_eq!(status.status, TransactionStatus::Received);
+            }
+            assert_eq!(gateway.calls.load(Ordering::Relaxed), 3);
+        }
+
+        #[tokio::test]
+        async fn rejection_is_not_retried() {
+            use starknet_gateway_types::error::{KnownStarknetErrorCode, StarknetError};
+
+            let mut gateway = starknet_gateway_client::MockGatewayApi::new();
+            gateway.expect_transaction().times(1).returning(|_| {
+                Err(SequencerError::StarknetError(StarknetError {
+                    code: KnownStarknetErrorCode::MalformedRequest.into(),
+                    message: "Malformed request".to_owned(),
+                }))
+            });
+
+            let cache = GatewayStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"unknown tx"));
+
+            let err = gateway_status(&gateway, &cache, Duration::from_secs(10), RETRY, tx_hash)
+                .await
+                .unwrap_err();
+            assert_matches::assert_matches!(err, GetGatewayTransactionError::Internal(_));
+        }
+    }
+
+    #[test]
+    fn unrecognized_gateway_status() {
+        let status: starknet_gateway_types::reply::Status =
+            serde_json::from_str(r#""SOME_FUTURE_STATUS""#).unwrap();
+
+        assert_eq!(TransactionStatus::from(status), TransactionStatus::Unknown);
+    }
+
+    #[tokio::test]
+    async fn locked_database_is_retryable() {
+        let context = RpcContext::for_tests();
+
+        // The in-memory test database uses a shared cache, in which an uncommitted write locks
+        // the table for all other connections.
+        let mut db = context.storage.connection().unwrap();
+        let db_tx = db.transaction().unwrap();
+        db_tx
+            .insert_rejected_transaction(TransactionHash(felt_bytes!(b"other tx")), "reason")
+            .unwrap();
+
+        let err = resolve_status(context.clone(), TransactionHash(felt_bytes!(b"unknown tx")))
+            .await
+            .unwrap_err();
+        assert_matches::assert_matches!(err, GetGatewayTransactionError::DatabaseBusy);
+    }
+
+    #[tokio::test]
+    async fn pending_receipt_only() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let tx_hash = TransactionHash(felt_bytes!(b"pending tx hash 0"));
+
+        // The pending block's transactions lag behind its receipts.
+        let pending = context.pending_data.as_ref().unwrap();
+        let mut block = (*pending.block().await.unwrap()).clone();
+        block.transactions.retain(|tx| tx.hash() != tx_hash);
+        let state_update = pending.state_update().await.unwrap();
+        pending.set(Arc::new(block), state_update).await;
+
+        let status = resolve_status(context, tx_hash).await.unwrap();
+
+        assert_eq!(status.finality_status, TransactionStatus::Pending);
+        assert_eq!(status.execution_status, Some(ExecutionStatus::Succeeded));
+    }
+
+    #[tokio::test]
+    async fn persisted_rejection() {
+        let context = RpcContext::for_tests();
+        let tx_hash = TransactionHash(felt_bytes!(b"rejected tx"));
+
+        let mut db = context.storage.connection().unwrap();
+        let db_tx = db.transaction().unwrap();
+        db_tx
+            .insert_rejected_transaction(tx_hash, "Invalid nonce")
+            .unwrap();
+        db_tx.commit().unwrap();
+
+        // The gateway doesn't know this transaction, so this can only be served from storage.
+        let input = input(tx_hash);
+        let status = get_transaction_status(context, input).await.unwrap();
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput {
+                rejection: Some(("Invalid nonce".to_owned(), RejectionReason::InvalidNonce)),
+                ..GetTransactionStatusOutput::new(TransactionStatus::Rejected)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn persisted_rejection_without_reason() {
+        let context = RpcContext::for_tests();
+        let tx_hash = TransactionHash(felt_bytes!(b"rejected tx"));
+
+        let mut db = context.storage.connection().unwrap();
+        let db_tx = db.transaction().unwrap();
+        db_tx.insert_rejected_transaction(tx_hash, "").unwrap();
+        db_tx.commit().unwrap();
+
+        let input = input(tx_hash);
+        let status = get_transaction_status(context, input).await.unwrap();
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput::new(TransactionStatus::Rejected)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn saturated_status_permits_are_retryable() {
+        let context = RpcContext::for_tests().with_status_concurrency_limit(2);
+        let tx_hash = TransactionHash(felt_bytes!(b"txn 1"));
+
+        let held = context
+            .status_permits
+            .clone()
+            .try_acquire_many_owned(2)
+            .unwrap();
+
+        let start = tokio::time::Instant::now();
+        let result = resolve_status(context.clone(), tx_hash).await;
+        assert_matches::assert_matches!(result, Err(GetGatewayTransactionError::DatabaseBusy));
+        assert_eq!(start.elapsed(), STATUS_PERMIT_WAIT);
+
+        drop(held);
+        let status = resolve_status(context, tx_hash).await.unwrap();
+        assert_eq!(status.finality_status, TransactionStatus::AcceptedOnL2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gateway_timeout() {
+        use starknet_gateway_types::reply;
+
+        struct UnresponsiveGateway;
+
+        #[async_trait::async_trait]
+        impl GatewayApi for UnresponsiveGateway {
+            async fn transaction(
+                &self,
+                _: TransactionHash,
+            ) -> Result<reply::Transaction, SequencerError> {
+                tokio::time::sleep(Duration::from_secs(3600)).await;
+                unreachable!("Request should have timed out");
+            }
+        }
+
+        let cache = GatewayStatusCache::default();
+        let tx_hash = TransactionHash(felt_bytes!(b"unknown tx"));
+        let timeout = Duration::from_secs(5);
+
+        let start = tokio::time::Instant::now();
+        gateway_status(&UnresponsiveGateway, &cache, timeout, NO_RETRY, tx_hash)
+            .await
+            .unwrap_err();
+
+        // Time is paused, so only the timeout should have elapsed -- not the gateway's delay.
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn rejected() {
+        let input = input(TransactionHash(felt!(
+            // Transaction hash known to be rejected by the testnet gateway.
+            "0x07c64b747bdb0831e7045925625bfa6309c422fded9527bacca91199a1c8d212"
+        )));
+        let context = RpcContext::for_tests();
+        let status = get_transaction_status(context, input).await.unwrap();
+
+        assert_matches::assert_matches!(
+            status,
+            GetTransactionStatusOutput {
+                rejection: Some(_),
+                ..
+            }
+        );
+    }
+
+    mod db_task_cancellation {
+        use std::num::NonZeroU32;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        use pathfinder_storage::JournalMode;
+
+        use super::*;
+
+        #[tokio::test]
+        async fn dropped_request_returns_connection_to_pool() {
+            let db_dir = tempfile::TempDir::new().unwrap();
+            let storage = Storage::migrate(db_dir.path().join("db.sqlite"), JournalMode::WAL)
+                .unwrap()
+                .create_pool(NonZeroU32::new(1).unwrap())
+                .unwrap();
+
+            // Hold the only connection, so that the request's database task has to wait for it.
+            let held = storage.connection().unwrap();
+
+            let ran = Arc::new(AtomicBool::new(false));
+            let task_ran = ran.clone();
+            let request = with_db_tx(storage.clone(), ConnectionKind::Primary, move |_| {
+                task_ran.store(true, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_secs(5));
+                Ok(())
+            });
+            // Dropped as if the client had disconnected.
+            tokio::time::timeout(Duration::from_millis(50), request)
+                .await
+                .unwrap_err();
+
+            // The waiting task should now take the connection and return it right away.
+            drop(held);
+            tokio::time::sleep(Duration::from_millis(200)).await;
+
+            assert_eq!(storage.idle_connections(), 1);
+            assert!(!ran.load(Ordering::SeqCst));
+        }
+    }
+
+    mod batch_connection {
+        use super::*;
+        use crate::middleware::batch_connection::BatchConnection;
+
+        #[tokio::test]
+        async fn batch_checks_out_a_single_connection() {
+            let context = RpcContext::for_tests();
+            let storage = context.storage.clone();
+            let idle = storage.idle_connections();
+
+            let batch = BatchConnection::default();
+            let idle_during_batch = batch
+                .clone()
+                .scope(async {
+                    let queries = [b"txn 0", b"txn 1", b"txn 3", b"txn 4"].map(|hash| {
+                        let input = input(TransactionHash(felt_bytes!(hash)));
+                        get_transaction_status(context.clone(), input)
+                    });
+                    for status in futures::future::join_all(queries).await {
+                        status.unwrap();
+                    }
+
+                    storage.idle_connections()
+                })
+                .await;
+            // The batch holds on to its connection until it completes.
+            assert_eq!(idle_during_batch, idle - 1);
+
+            drop(batch);
+            assert_eq!(storage.idle_connections(), idle);
+        }
     }
 }
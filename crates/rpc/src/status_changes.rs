@@ -0,0 +1,407 @@
+//! Observes the status changes of transactions, both for the
+//! `pathfinder_subscribe_allTransactionStatus` subscription and for embedders which register a
+//! callback via [RpcContext::on_transaction_status_change].
+//!
+//! Changes are derived from newly committed blocks and refreshes of the pending block. Neither L1
+//! acceptance nor reorgs are reported.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber, TransactionHash};
+use pathfinder_storage::{BlockId, Storage};
+use starknet_gateway_types::reply::PendingBlock;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use crate::context::RpcContext;
+use crate::websocket::types::BlockHeader;
+
+pub use crate::pathfinder::methods::TransactionStatus;
+
+/// How often the pending data is checked for changes in between new heads.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A status change as `(hash, old_status, new_status)`.
+pub(crate) type Change = (TransactionHash, TransactionStatus, TransactionStatus);
+
+/// Called with a transaction's hash and new status whenever its status changes.
+pub type StatusChangeCallback = Box<dyn Fn(TransactionHash, TransactionStatus) + Send>;
+
+/// Starts calling `callback` for every status change observed from now on, see
+/// [RpcContext::on_transaction_status_change].
+pub(crate) async fn register(
+    context: RpcContext,
+    callback: StatusChangeCallback,
+) -> anyhow::Result<()> {
+    let tracker = StatusTracker::new(context.storage.clone()).await?;
+    let new_heads = context
+        .new_heads
+        .as_ref()
+        .map(|new_heads| new_heads.subscribe());
+
+    // Changes are queued rather than dropped for a slow callback, as it cannot catch up on them
+    // any other way.
+    let (changes_tx, mut changes_rx) = mpsc::unbounded_channel();
+    std::thread::Builder::new()
+        .name("status-change-callback".to_owned())
+        .spawn(move || {
+            while let Some((transaction_hash, status)) = changes_rx.blocking_recv() {
+                callback(transaction_hash, status);
+            }
+        })
+        .context("Spawning status change callback thread")?;
+
+    tokio::spawn(async move {
+        track(
+            &context,
+            tracker,
+            new_heads,
+            || changes_tx.is_closed(),
+            |(transaction_hash, _, new_status)| {
+                let _ = changes_tx.send((transaction_hash, new_status));
+            },
+        )
+        .await
+    });
+
+    Ok(())
+}
+
+/// Passes every status change to `emit`, until `is_closed` reports that nobody listens anymore.
+///
+/// The changes are evaluated on each new head and every [POLL_INTERVAL].
+pub(crate) async fn track(
+    context: &RpcContext,
+    mut tracker: StatusTracker,
+    mut new_heads: Option<broadcast::Receiver<BlockHeader>>,
+    is_closed: impl Fn() -> bool,
+    emit: impl Fn(Change),
+) {
+    let mut poll =
+        tokio::time::interval_at(tokio::time::Instant::now() + POLL_INTERVAL, POLL_INTERVAL);
+
+    loop {
+        let new_head = async {
+            match &mut new_heads {
+                // Lagging behind is fine, as the tracker catches up on all blocks since the last
+                // refresh.
+                Some(new_heads) => {
+                    if let Err(RecvError::Closed) = new_heads.recv().await {
+                        futures::future::pending::<()>().await;
+                    }
+                }
+                None => futures::future::pending::<()>().await,
+            }
+        };
+
+        tokio::select! {
+            _ = poll.tick() => {}
+            _ = new_head => {}
+        }
+
+        if is_closed() {
+            return;
+        }
+
+        match tracker.refresh(context).await {
+            Ok(changes) => changes.into_iter().for_each(&emit),
+            // Transient, so simply try again on the next tick.
+            Err(error) => tracing::trace!("Refreshing transaction status changes failed {error:?}"),
+        }
+    }
+}
+
+/// Derives status changes from the committed blocks and the pending block.
+pub(crate) struct StatusTracker {
+    /// The first block whose transactions have not been reported as accepted yet.
+    next_block: BlockNumber,
+    /// The latest committed block.
+    head: Option<BlockHash>,
+    /// The last reported status of the transactions in the pending block.
+    pending: HashMap<TransactionHash, TransactionStatus>,
+}
+
+impl StatusTracker {
+    /// Starts tracking on top of the latest committed block.
+    pub(crate) async fn new(storage: Storage) -> anyhow::Result<Self> {
+        let head = tokio::task::spawn_blocking(move || {
+            let mut db = storage
+                .connection()
+                .context("Opening database connection")?;
+            let db_tx = db.transaction().context("Creating database transaction")?;
+            db_tx
+                .block_id(BlockId::Latest)
+                .context("Fetching latest block")
+        })
+        .await
+        .context("Joining database task")??;
+
+        Ok(Self {
+            next_block: head.map_or(BlockNumber::GENESIS, |(number, _)| number + 1),
+            head: head.map(|(_, hash)| hash),
+            pending: HashMap::new(),
+        })
+    }
+
+    /// Returns how the transactions' statuses changed since the previous refresh.
+    async fn refresh(&mut self, context: &RpcContext) -> anyhow::Result<Vec<Change>> {
+        let storage = context.storage.clone();
+        let next_block = self.next_block;
+        let (head, committed) =
+            tokio::task::spawn_blocking(move || committed_since(storage, next_block))
+                .await
+                .context("Joining database task")??;
+
+        let mut changes = Vec::new();
+        for hash in committed {
+            let old_status = self
+                .pending
+                .remove(&hash)
+                .unwrap_or(TransactionStatus::NotReceived);
+            changes.push((hash, old_status, TransactionStatus::AcceptedOnL2));
+        }
+        if let Some((number, hash)) = head {
+            self.next_block = number + 1;
+            self.head = Some(hash);
+        }
+
+        let pending = match &context.pending_data {
+            Some(pending) => pending.block().await,
+            None => None,
+        };
+        // A stale pending block is not conclusive about which transactions it no longer contains,
+        // as those may simply not have been committed yet.
+        let Some(pending) = pending.filter(|block| Some(block.parent_hash) == self.head) else {
+            return Ok(changes);
+        };
+
+        let status = pending_status(&pending);
+        let mut current = HashMap::with_capacity(pending.transactions.len());
+        for transaction in &pending.transactions {
+            let hash = transaction.hash();
+            let old_status = self
+                .pending
+                .remove(&hash)
+                .unwrap_or(TransactionStatus::NotReceived);
+            if old_status != status {
+                changes.push((hash, old_status, status));
+            }
+            current.insert(hash, status);
+        }
+        // Whatever is left was dropped from the pending block.
+        for (hash, old_status) in self.pending.drain() {
+            changes.push((hash, old_status, TransactionStatus::NotReceived));
+        }
+        self.pending = current;
+
+        Ok(changes)
+    }
+}
+
+/// Returns the latest committed block, along with the transactions of all blocks from
+/// `first_block` up to it.
+fn committed_since(
+    storage: Storage,
+    first_block: BlockNumber,
+) -> anyhow::Result<(Option<(BlockNumber, BlockHash)>, Vec<TransactionHash>)> {
+    let mut db = storage
+        .connection()
+        .context("Opening database connection")?;
+    let db_tx = db.transaction().context("Creating database transaction")?;
+
+    let head = db_tx
+        .block_id(BlockId::Latest)
+        .context("Fetching latest block")?;
+
+    let mut hashes = Vec::new();
+    if let Some((latest, _)) = head {
+        let mut number = first_block;
+        while number <= latest {
+            let data = db_tx
+                .transaction_data_for_block(number.into())
+                .context("Fetching block's transactions")?
+                .unwrap_or_default();
+            hashes.extend(data.iter().map(|(transaction, _)| transaction.hash()));
+            number += 1;
+        }
+    }
+
+    Ok((head, hashes))
+}
+
+fn pending_status(block: &PendingBlock) -> TransactionStatus {
+    match block.status {
+        starknet_gateway_types::reply::Status::PreConfirmed => TransactionStatus::PreConfirmed,
+        _ => TransactionStatus::Pending,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::felt_bytes;
+
+    use super::*;
+
+    /// Commits the pending block on top of the latest block, and returns its header.
+    async fn commit_pending(context: &RpcContext) -> BlockHeader {
+        let pending = context.pending_data.as_ref().unwrap();
+        let pending = pending.block().await.unwrap();
+        let storage = context.storage.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut db = storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let header = db_tx
+                .block_header(BlockId::Latest)
+                .unwrap()
+                .unwrap()
+                .child_builder()
+                .finalize_with_hash(BlockHash(felt_bytes!(b"committed pending")));
+            db_tx.insert_block_header(&header).unwrap();
+
+            let data = pending
+                .transactions
+                .iter()
+                .cloned()
+                .zip(pending.transaction_receipts.iter().cloned())
+                .collect::<Vec<_>>();
+            db_tx
+                .insert_transaction_data(header.hash, header.number, &data)
+                .unwrap();
+            db_tx.commit().unwrap();
+
+            BlockHeader {
+                block_hash: header.hash,
+                block_number: header.number,
+                gas_price: None,
+                parent_block_hash: header.parent_hash,
+                sequencer_address: None,
+                state_commitment: header.state_commitment,
+                status: starknet_gateway_types::reply::Status::AcceptedOnL2,
+                timestamp: header.timestamp,
+                starknet_version: header.starknet_version,
+            }
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn pending_to_accepted() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let mut tracker = StatusTracker::new(context.storage.clone()).await.unwrap();
+
+        let pending_0 = TransactionHash(felt_bytes!(b"pending tx hash 0"));
+        let pending_1 = TransactionHash(felt_bytes!(b"pending tx hash 1"));
+
+        let changes = tracker.refresh(&context).await.unwrap();
+        assert!(changes.contains(&(
+            pending_0,
+            TransactionStatus::NotReceived,
+            TransactionStatus::Pending
+        )));
+        assert!(changes.contains(&(
+            pending_1,
+            TransactionStatus::NotReceived,
+            TransactionStatus::Pending
+        )));
+
+        // Once reported, an unchanged status is not reported again.
+        let changes = tracker.refresh(&context).await.unwrap();
+        assert_eq!(changes, vec![]);
+
+        // Commit the pending block, while the pending data is not updated yet.
+        commit_pending(&context).await;
+
+        let changes = tracker.refresh(&context).await.unwrap();
+        assert!(changes.contains(&(
+            pending_0,
+            TransactionStatus::Pending,
+            TransactionStatus::AcceptedOnL2
+        )));
+        assert!(changes.contains(&(
+            pending_1,
+            TransactionStatus::Pending,
+            TransactionStatus::AcceptedOnL2
+        )));
+        // The stale pending block does not cause the transactions to be reported as dropped.
+        assert!(changes
+            .iter()
+            .all(|(_, _, new_status)| *new_status == TransactionStatus::AcceptedOnL2));
+
+        let changes = tracker.refresh(&context).await.unwrap();
+        assert_eq!(changes, vec![]);
+    }
+
+    #[tokio::test]
+    async fn dropped_from_pending() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let mut tracker = StatusTracker::new(context.storage.clone()).await.unwrap();
+        tracker.refresh(&context).await.unwrap();
+
+        // Replace the pending block with an empty one on the same parent.
+        let pending = context.pending_data.as_ref().unwrap();
+        let block = pending.block().await.unwrap();
+        let state_update = pending.state_update().await.unwrap();
+        let empty = PendingBlock {
+            transactions: vec![],
+            transaction_receipts: vec![],
+            ..(*block).clone()
+        };
+        pending.set(empty.into(), state_update).await;
+
+        let changes = tracker.refresh(&context).await.unwrap();
+        assert_eq!(changes.len(), block.transactions.len());
+        assert!(changes.iter().all(|(_, old_status, new_status)| {
+            *old_status == TransactionStatus::Pending
+                && *new_status == TransactionStatus::NotReceived
+        }));
+    }
+
+    #[tokio::test]
+    async fn callback_fires_on_new_block() {
+        let (new_heads, _) = broadcast::channel(1);
+        let context = RpcContext::for_tests_with_pending()
+            .await
+            .with_new_heads(new_heads.clone());
+
+        let (calls_tx, mut calls_rx) = mpsc::unbounded_channel();
+        context
+            .on_transaction_status_change(Box::new(move |transaction_hash, status| {
+                let thread = std::thread::current().name().map(str::to_owned);
+                let _ = calls_tx.send((transaction_hash, status, thread));
+            }))
+            .await
+            .unwrap();
+
+        let header = commit_pending(&context).await;
+        new_heads.send(header).unwrap();
+
+        let mut calls = Vec::new();
+        for _ in 0..2 {
+            let call = tokio::time::timeout(Duration::from_secs(5), calls_rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            calls.push(call);
+        }
+        calls.sort_by_key(|(transaction_hash, ..)| transaction_hash.0);
+
+        let thread = Some("status-change-callback".to_owned());
+        let mut expected = vec![
+            (
+                TransactionHash(felt_bytes!(b"pending tx hash 0")),
+                TransactionStatus::AcceptedOnL2,
+                thread.clone(),
+            ),
+            (
+                TransactionHash(felt_bytes!(b"pending tx hash 1")),
+                TransactionStatus::AcceptedOnL2,
+                thread,
+            ),
+        ];
+        expected.sort_by_key(|(transaction_hash, ..)| transaction_hash.0);
+        assert_eq!(calls, expected);
+    }
+}
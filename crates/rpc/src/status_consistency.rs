@@ -0,0 +1,136 @@
+//! A background self-check which compares the statuses this node resolves for recently accepted
+//! transactions to the gateway's, in order to catch sync bugs.
+//!
+//! Each round resolves a sample of the transactions in the latest blocks just like
+//! `pathfinder_getTransactionStatus` does, and queries the status gateway for them. Disagreements
+//! are logged and counted by the [METRIC_DISCREPANCIES] metric.
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::{BlockNumber, TransactionHash};
+use pathfinder_storage::{BlockId, ConnectionKind};
+
+use crate::context::RpcContext;
+use crate::error::RpcError;
+use crate::pathfinder::methods::{resolve_status, with_db_tx, TransactionStatus};
+
+pub const METRIC_DISCREPANCIES: &str = "rpc_status_consistency_discrepancies_total";
+
+/// The number of transactions sampled per round, unless configured otherwise.
+pub const DEFAULT_SAMPLE_SIZE: usize = 20;
+
+/// The number of latest blocks whose transactions are sampled.
+const SAMPLE_BLOCKS: u64 = 10;
+
+/// Runs a consistency check every `interval`, sampling up to `sample_size` transactions each
+/// time. Failed checks are logged and retried on the next round, so this never returns.
+pub async fn run(context: RpcContext, interval: Duration, sample_size: usize) {
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        match check(&context, sample_size).await {
+            Ok(0) => tracing::trace!("Transaction statuses are consistent with the gateway"),
+            Ok(discrepancies) => {
+                tracing::warn!(%discrepancies, "Transaction statuses disagree with the gateway")
+            }
+            Err(error) => tracing::debug!(%error, "Transaction status consistency check failed"),
+        }
+    }
+}
+
+/// Compares the statuses of up to `sample_size` recently accepted transactions to the gateway's,
+/// and returns the number of transactions they disagree on.
+///
+/// A transaction which the gateway already reports as accepted on L1, while this node does not,
+/// is not considered a disagreement as L1 sync may simply lag behind. Transactions which the
+/// gateway fails to report are skipped.
+pub async fn check(context: &RpcContext, sample_size: usize) -> anyhow::Result<usize> {
+    let sample = with_db_tx(
+        context.storage.clone(),
+        ConnectionKind::Primary,
+        move |db_tx| {
+            let Some((latest, _)) = db_tx
+                .block_id(BlockId::Latest)
+                .context("Fetching latest block")?
+            else {
+                return Ok(Vec::new());
+            };
+
+            let mut sample = Vec::new();
+            let first = latest.get().saturating_sub(SAMPLE_BLOCKS - 1);
+            for number in (first..=latest.get()).rev() {
+                let data = db_tx
+                    .transaction_data_for_block(BlockNumber::new_or_panic(number).into())
+                    .context("Fetching block's transactions")?
+                    .unwrap_or_default();
+                sample.extend(data.iter().map(|(transaction, _)| transaction.hash()));
+                if sample.len() >= sample_size {
+                    break;
+                }
+            }
+            sample.truncate(sample_size);
+
+            Ok(sample)
+        },
+    )
+    .await
+    .map_err(RpcError::from)
+    .context("Sampling recently accepted transactions")?;
+
+    let mut discrepancies = 0;
+    for transaction_hash in sample {
+        let stored = resolve_status(context.clone(), transaction_hash)
+            .await
+            .map_err(RpcError::from)
+            .with_context(|| format!("Resolving status of transaction {transaction_hash}"))?
+            .finality_status;
+
+        let Some(gateway) = gateway_status(context, transaction_hash).await else {
+            continue;
+        };
+
+        let lagging_l1 =
+            stored == TransactionStatus::AcceptedOnL2 && gateway == TransactionStatus::AcceptedOnL1;
+        if stored != gateway && !lagging_l1 {
+            tracing::warn!(
+                %transaction_hash,
+                ?stored,
+                ?gateway,
+                "Transaction status disagrees with the gateway"
+            );
+            metrics::increment_counter!(METRIC_DISCREPANCIES);
+            discrepancies += 1;
+        }
+    }
+
+    Ok(discrepancies)
+}
+
+/// Returns the gateway's status of the transaction, or [None] if the gateway failed to report it.
+async fn gateway_status(
+    context: &RpcContext,
+    transaction_hash: TransactionHash,
+) -> Option<TransactionStatus> {
+    use starknet_gateway_client::GatewayApi;
+
+    let transaction = tokio::time::timeout(
+        context.gateway_timeout,
+        context.status_gateway().transaction(transaction_hash),
+    )
+    .await;
+
+    match transaction {
+        Ok(Ok(transaction)) => Some(TransactionStatus::from(transaction.status)),
+        Ok(Err(error)) => {
+            tracing::debug!(%transaction_hash, %error, "Fetching status from gateway failed");
+            None
+        }
+        Err(_) => {
+            tracing::debug!(%transaction_hash, "Fetching status from gateway timed out");
+            None
+        }
+    }
+}
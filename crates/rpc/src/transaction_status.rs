@@ -0,0 +1,1544 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use pathfinder_common::{BlockHash, BlockNumber, TransactionHash};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::sync::watch;
+
+use crate::pathfinder::methods::{
+    ReorgRisk, StatusDetail, TransactionStatus, TransactionStatusResponse,
+};
+
+const METRIC_CACHE_HITS: &str = "rpc_status_cache_hits_total";
+const METRIC_CACHE_MISSES: &str = "rpc_status_cache_misses_total";
+
+/// Counts the lookups of a status cache, both as metrics labelled by `cache` and for a debug log
+/// of its hit ratio which is emitted every [CacheStats::LOG_INTERVAL] lookups.
+struct CacheStats {
+    cache: &'static str,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheStats {
+    const LOG_INTERVAL: u64 = 1000;
+
+    fn new(cache: &'static str) -> Self {
+        Self {
+            cache,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, hit: bool) {
+        let (hits, misses) = match hit {
+            true => {
+                metrics::increment_counter!(METRIC_CACHE_HITS, "cache" => self.cache);
+                let hits = self.hits.fetch_add(1, Ordering::Relaxed) + 1;
+                (hits, self.misses.load(Ordering::Relaxed))
+            }
+            false => {
+                metrics::increment_counter!(METRIC_CACHE_MISSES, "cache" => self.cache);
+                let misses = self.misses.fetch_add(1, Ordering::Relaxed) + 1;
+                (self.hits.load(Ordering::Relaxed), misses)
+            }
+        };
+
+        let lookups = hits + misses;
+        if lookups % Self::LOG_INTERVAL == 0 {
+            tracing::debug!(
+                cache = self.cache,
+                %hits,
+                %misses,
+                hit_ratio = hits as f64 / lookups as f64,
+                "Status cache hit ratio"
+            );
+        }
+    }
+}
+
+/// Caches the transaction statuses reported by the gateway for transactions unknown to this node.
+///
+/// A `REJECTED` status is final and is therefore cached indefinitely, whereas `NOT_RECEIVED` may
+/// still change once the transaction reaches the sequencer and is only cached for a short while.
+/// All other statuses are not cached as they are expected to be superseded by local data soon.
+#[derive(Clone)]
+pub struct GatewayStatusCache {
+    inner: Arc<Mutex<HashMap<TransactionHash, (Instant, TransactionStatus)>>>,
+    not_received_ttl: Duration,
+    stats: Arc<CacheStats>,
+}
+
+impl Default for GatewayStatusCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(30))
+    }
+}
+
+impl GatewayStatusCache {
+    /// Upper bound on the number of cached statuses.
+    const CAPACITY: usize = 10_000;
+
+    pub fn new(not_received_ttl: Duration) -> Self {
+        Self {
+            inner: Default::default(),
+            not_received_ttl,
+            stats: Arc::new(CacheStats::new("gateway")),
+        }
+    }
+
+    pub(crate) fn get(&self, transaction_hash: &TransactionHash) -> Option<TransactionStatus> {
+        let mut cache = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        let status = match cache.get(transaction_hash) {
+            Some((cached_at, status))
+                if *status == TransactionStatus::Rejected
+                    || cached_at.elapsed() < self.not_received_ttl =>
+            {
+                Some(*status)
+            }
+            Some(_) => {
+                cache.remove(transaction_hash);
+                None
+            }
+            None => None,
+        };
+
+        self.stats.record(status.is_some());
+        status
+    }
+
+    pub(crate) fn insert(&self, transaction_hash: TransactionHash, status: TransactionStatus) {
+        if !matches!(
+            status,
+            TransactionStatus::NotReceived | TransactionStatus::Rejected
+        ) {
+            return;
+        }
+
+        let mut cache = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        if cache.len() >= Self::CAPACITY {
+            let ttl = self.not_received_ttl;
+            cache.retain(|_, (cached_at, status)| {
+                *status == TransactionStatus::Rejected || cached_at.elapsed() < ttl
+            });
+        }
+
+        // Rather skip caching than evicting rejections which would have to be re-fetched.
+        if cache.len() < Self::CAPACITY {
+            cache.insert(transaction_hash, (Instant::now(), status));
+        }
+    }
+}
+
+/// Caches the statuses resolved from storage for as long as neither the L2 nor the L1 head
+/// change.
+///
+/// Entries are keyed on the transaction and the number of the L2 head they were resolved at, so
+/// that a new block invalidates all of them -- including those of transactions which are not in
+/// it, as their reorg risk changes along with it. The head's hash is checked as well, so that a
+/// reorg to a different block at the same height also invalidates the cache. So is the [L1Head]
+/// they were resolved at, as L1 sync moves transactions to L1 acceptance and adds confirmations
+/// without the L2 head changing.
+///
+/// An entry only serves lookups for at most the [StatusDetail] it was resolved with, as a
+/// [StatusDetail::Finality] status lacks the receipt and header details.
+///
+/// Sync additionally evicts the entries resolved at an orphaned head as soon as it reorgs, see
+/// [DatabaseStatusCache::invalidate_from].
+#[derive(Clone)]
+pub struct DatabaseStatusCache {
+    inner: Arc<Mutex<HashMap<(TransactionHash, BlockNumber), CachedStatus>>>,
+    stats: Arc<CacheStats>,
+}
+
+impl Default for DatabaseStatusCache {
+    fn default() -> Self {
+        Self {
+            inner: Default::default(),
+            stats: Arc::new(CacheStats::new("database")),
+        }
+    }
+}
+
+struct CachedStatus {
+    head: BlockHash,
+    l1_head: L1Head,
+    detail: StatusDetail,
+    status: TransactionStatusResponse,
+}
+
+/// The L1 state a stored status is resolved at.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct L1Head {
+    /// The latest L2 block which storage considers accepted on L1.
+    pub l2_block: Option<BlockNumber>,
+    /// The latest L1 block observed by L1 sync, which L1 confirmations are counted up to.
+    pub l1_block: Option<u64>,
+    /// The L2 block of the in-memory L1 head, which may be ahead of storage.
+    pub latest: Option<(BlockNumber, BlockHash)>,
+}
+
+impl DatabaseStatusCache {
+    /// Upper bound on the number of cached statuses.
+    const CAPACITY: usize = 10_000;
+
+    pub(crate) fn get(
+        &self,
+        transaction_hash: TransactionHash,
+        (number, hash): (BlockNumber, BlockHash),
+        l1_head: L1Head,
+        detail: StatusDetail,
+    ) -> Option<TransactionStatusResponse> {
+        let cache = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        let status = cache
+            .get(&(transaction_hash, number))
+            .filter(|cached| {
+                cached.head == hash && cached.l1_head == l1_head && cached.detail >= detail
+            })
+            .map(|cached| cached.status.clone());
+
+        self.stats.record(status.is_some());
+        status
+    }
+
+    pub(crate) fn insert(
+        &self,
+        transaction_hash: TransactionHash,
+        (number, hash): (BlockNumber, BlockHash),
+        l1_head: L1Head,
+        detail: StatusDetail,
+        status: TransactionStatusResponse,
+    ) {
+        let mut cache = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        // All entries share the same heads, and are dropped once they change as they can no
+        // longer be hit.
+        let head_changed = match cache.iter().next() {
+            Some(((_, cached_number), cached)) => {
+                (*cached_number, cached.head, cached.l1_head) != (number, hash, l1_head)
+            }
+            None => false,
+        };
+        if head_changed {
+            cache.clear();
+        }
+
+        if cache.len() < Self::CAPACITY {
+            cache.insert(
+                (transaction_hash, number),
+                CachedStatus {
+                    head: hash,
+                    l1_head,
+                    detail,
+                    status,
+                },
+            );
+        }
+    }
+
+    /// Evicts the statuses resolved at a head which a reorg purging all blocks from `reorg_tail`
+    /// onwards orphaned, so that they are re-resolved from the reorged storage.
+    pub fn invalidate_from(&self, reorg_tail: BlockNumber) {
+        let mut cache = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        cache.retain(|(_, head), _| *head < reorg_tail);
+    }
+}
+
+/// Lets concurrent queries for the same key, e.g. a transaction, share a single in-flight status
+/// resolution.
+///
+/// Only successful results are shared, and only with the queries which were already waiting for
+/// them. If the resolution fails, each waiting query runs its own instead.
+pub struct SingleFlight<T, K = TransactionHash> {
+    in_flight: Arc<Mutex<HashMap<K, watch::Receiver<Option<T>>>>>,
+}
+
+impl<T, K> Clone for SingleFlight<T, K> {
+    fn clone(&self) -> Self {
+        Self {
+            in_flight: self.in_flight.clone(),
+        }
+    }
+}
+
+impl<T, K> Default for SingleFlight<T, K> {
+    fn default() -> Self {
+        Self {
+            in_flight: Default::default(),
+        }
+    }
+}
+
+impl<T: Clone, K: Clone + Eq + Hash> SingleFlight<T, K> {
+    /// Runs `resolve` unless a resolution for `key` is already in flight, in which case its
+    /// result is awaited instead.
+    pub(crate) async fn run<F, Fut, E>(&self, key: K, resolve: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let leader = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            match in_flight.get(&key) {
+                Some(receiver) => Err(receiver.clone()),
+                None => {
+                    let (sender, receiver) = watch::channel(None);
+                    in_flight.insert(key.clone(), receiver);
+                    Ok(sender)
+                }
+            }
+        };
+
+        match leader {
+            Ok(sender) => {
+                // Removes the entry even if this query is cancelled.
+                let _guard = RemoveOnDrop {
+                    in_flight: &self.in_flight,
+                    key,
+                };
+
+                let result = resolve().await;
+                if let Ok(value) = &result {
+                    sender.send_replace(Some(value.clone()));
+                }
+
+                result
+            }
+            Err(mut receiver) => {
+                // The sender is dropped without a value if the resolution failed.
+                if receiver.changed().await.is_ok() {
+                    if let Some(value) = receiver.borrow().clone() {
+                        return Ok(value);
+                    }
+                }
+
+                resolve().await
+            }
+        }
+    }
+}
+
+struct RemoveOnDrop<'a, T, K: Eq + Hash> {
+    in_flight: &'a Mutex<HashMap<K, watch::Receiver<Option<T>>>>,
+    key: K,
+}
+
+impl<T, K: Eq + Hash> Drop for RemoveOnDrop<'_, T, K> {
+    fn drop(&mut self) {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.key);
+    }
+}
+
+/// How often, and how patiently, a gateway query which failed with a transient error is retried.
+///
+/// The delay before retry `N` is `base_delay * 2^(N - 1)`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GatewayRetry {
+    /// Total number of attempts, including the first one. Zero is treated as one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+}
+
+impl Default for GatewayRetry {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+        }
+    }
+}
+
+impl GatewayRetry {
+    /// Returns the delay before the given retry, or [None] if `retry` exceeds the attempts
+    /// budget. The first retry is number 1.
+    pub(crate) fn delay(&self, retry: u32) -> Option<Duration> {
+        if retry == 0 || retry >= self.max_attempts {
+            return None;
+        }
+
+        let factor = 2u32.checked_pow(retry - 1).unwrap_or(u32::MAX);
+        Some(self.base_delay.saturating_mul(factor))
+    }
+}
+
+/// Delays the status re-checks of waiting status queries by a random duration of up to `max`.
+///
+/// All waiting queries are woken by the same new block, so without this they would all hit the
+/// database at once.
+#[derive(Clone)]
+pub struct WaitJitter {
+    max: Duration,
+    rng: Arc<Mutex<StdRng>>,
+}
+
+impl Default for WaitJitter {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(250))
+    }
+}
+
+impl WaitJitter {
+    pub fn new(max: Duration) -> Self {
+        Self {
+            max,
+            rng: Arc::new(Mutex::new(StdRng::from_entropy())),
+        }
+    }
+
+    /// Draws the delays from a deterministic RNG.
+    #[cfg(test)]
+    pub(crate) fn with_seed(max: Duration, seed: u64) -> Self {
+        Self {
+            max,
+            rng: Arc::new(Mutex::new(StdRng::seed_from_u64(seed))),
+        }
+    }
+
+    /// Returns the delay before the next re-check.
+    pub(crate) fn delay(&self) -> Duration {
+        if self.max.is_zero() {
+            return Duration::ZERO;
+        }
+
+        self.rng
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .gen_range(Duration::ZERO..=self.max)
+    }
+}
+
+/// Stops gateway status queries for a while once the gateway keeps failing, so that queries fail
+/// fast during an outage instead of piling up behind doomed gateway calls.
+///
+/// The breaker trips after `failure_threshold` consecutive failures, the first of which happened
+/// no longer than `window` ago. It then stays open for `cooldown`, after which a single query is
+/// let through to probe the gateway: if it succeeds the breaker closes, otherwise it opens again.
+#[derive(Clone)]
+pub struct GatewayCircuitBreaker {
+    state: Arc<Mutex<BreakerState>>,
+    failure_threshold: u32,
+    window: Duration,
+    cooldown: Duration,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum BreakerState {
+    Closed {
+        /// The number of consecutive failures, and when the first of them happened.
+        failures: Option<(u32, tokio::time::Instant)>,
+    },
+    Open {
+        until: tokio::time::Instant,
+    },
+    /// A probe was let through at the given time, and its outcome is pending.
+    HalfOpen {
+        since: tokio::time::Instant,
+    },
+}
+
+impl Default for GatewayCircuitBreaker {
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(10), Duration::from_secs(30))
+    }
+}
+
+impl GatewayCircuitBreaker {
+    pub fn new(failure_threshold: u32, window: Duration, cooldown: Duration) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BreakerState::Closed { failures: None })),
+            failure_threshold,
+            window,
+            cooldown,
+        }
+    }
+
+    /// Returns true if the gateway may be queried, and false if the query should fail fast.
+    pub(crate) fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = tokio::time::Instant::now();
+
+        match *state {
+            BreakerState::Closed { .. } => true,
+            BreakerState::Open { until } if now < until => false,
+            // A probe whose outcome was never recorded, e.g. because its request was cancelled,
+            // must not keep the breaker half-open forever.
+            BreakerState::HalfOpen { since } if now < since + self.cooldown => false,
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => {
+                *state = BreakerState::HalfOpen { since: now };
+                true
+            }
+        }
+    }
+
+    /// Records that the gateway answered, which closes the breaker.
+    pub(crate) fn record_success(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if matches!(*state, BreakerState::HalfOpen { .. }) {
+            tracing::info!("Gateway recovered, resuming status queries");
+        }
+        *state = BreakerState::Closed { failures: None };
+    }
+
+    /// Records that the gateway failed, which trips the breaker once the failures add up or if
+    /// they occurred while probing.
+    pub(crate) fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = tokio::time::Instant::now();
+
+        let trip = match *state {
+            BreakerState::Closed { failures } => {
+                let failures = match failures {
+                    Some((count, first)) if now.saturating_duration_since(first) <= self.window => {
+                        (count + 1, first)
+                    }
+                    _ => (1, now),
+                };
+                *state = BreakerState::Closed {
+                    failures: Some(failures),
+                };
+                failures.0 >= self.failure_threshold
+            }
+            BreakerState::HalfOpen { .. } => true,
+            // Failures of queries which were let through before the breaker tripped.
+            BreakerState::Open { .. } => false,
+        };
+
+        if trip {
+            tracing::warn!(
+                cooldown=?self.cooldown,
+                "Gateway keeps failing, skipping status queries for a while"
+            );
+            *state = BreakerState::Open {
+                until: now + self.cooldown,
+            };
+        }
+    }
+}
+
+/// Maps an L2 accepted transaction's distance to the L2 head onto a [ReorgRisk].
+#[derive(Copy, Clone, Debug)]
+pub struct ReorgRiskThresholds {
+    /// The distance from which on a transaction's reorg risk is low.
+    pub low_risk_distance: u64,
+}
+
+impl Default for ReorgRiskThresholds {
+    fn default() -> Self {
+        Self {
+            low_risk_distance: 10,
+        }
+    }
+}
+
+impl ReorgRiskThresholds {
+    pub(crate) fn risk(&self, unsafe_head_distance: u64) -> ReorgRisk {
+        match unsafe_head_distance >= self.low_risk_distance {
+            true => ReorgRisk::Low,
+            false => ReorgRisk::Medium,
+        }
+    }
+}
+
+/// Whether status queries may wait for a status. Operators can disable this at runtime, e.g.
+/// during an incident in which holding connections open is dangerous, and doing so also ends the
+/// ongoing waits with the status at that point.
+///
+/// Enabled by default.
+#[derive(Clone)]
+pub struct StatusWaits(Arc<watch::Sender<bool>>);
+
+impl Default for StatusWaits {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+impl StatusWaits {
+    pub fn new(enabled: bool) -> Self {
+        Self(Arc::new(watch::channel(enabled).0))
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.0.send_replace(enabled);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Completes once waits are disabled, right away if they already are.
+    pub(crate) async fn disabled(&self) {
+        let mut enabled = self.0.subscribe();
+        // The sender is held by self, so this cannot fail.
+        let _ = enabled.wait_for(|enabled| !enabled).await;
+    }
+}
+
+/// Determines how a transaction which is only part of the pending block is reported.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PendingStatusPolicy {
+    /// As [TransactionStatus::Pending], or [TransactionStatus::PreConfirmed] if the pending block
+    /// is pre-confirmed.
+    #[default]
+    Pending,
+    /// As [TransactionStatus::AcceptedOnL2], for integrators which treat inclusion in the pending
+    /// block as acceptance.
+    AcceptedOnL2,
+}
+
+impl PendingStatusPolicy {
+    /// Maps the status of a transaction found in the pending block according to this policy.
+    pub(crate) fn apply(self, status: TransactionStatus) -> TransactionStatus {
+        match (self, status) {
+            (Self::AcceptedOnL2, TransactionStatus::Pending | TransactionStatus::PreConfirmed) => {
+                TransactionStatus::AcceptedOnL2
+            }
+            (_, status) => status,
+        }
+    }
+}
+
+/// Remembers the transactions seen in recent pending blocks, along with the number of the last
+/// pending block each was seen in.
+///
+/// This lets a transaction which is not received be told apart from one which was in a pending
+/// block which got dropped. At most `capacity` transactions are remembered, evicting the ones
+/// first seen longest ago.
+#[derive(Clone)]
+pub struct PendingSightings {
+    inner: Arc<Mutex<Sightings>>,
+    capacity: usize,
+}
+
+#[derive(Default)]
+struct Sightings {
+    last_seen: HashMap<TransactionHash, BlockNumber>,
+    /// Transactions in the order they were first seen.
+    order: VecDeque<TransactionHash>,
+}
+
+impl Default for PendingSightings {
+    fn default() -> Self {
+        Self::new(10_000)
+    }
+}
+
+impl PendingSightings {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Default::default(),
+            capacity,
+        }
+    }
+
+    /// Records the transactions of the pending block which would become block `number`.
+    pub fn record(
+        &self,
+        number: BlockNumber,
+        transaction_hashes: impl IntoIterator<Item = TransactionHash>,
+    ) {
+        let mut sightings = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let Sightings { last_seen, order } = &mut *sightings;
+
+        for transaction_hash in transaction_hashes {
+            // The same pending block is polled repeatedly, so most transactions are already known.
+            if last_seen.insert(transaction_hash, number).is_none() {
+                order.push_back(transaction_hash);
+            }
+        }
+
+        while order.len() > self.capacity {
+            if let Some(evicted) = order.pop_front() {
+                last_seen.remove(&evicted);
+            }
+        }
+    }
+
+    /// Returns the number of the last pending block the transaction was seen in.
+    pub(crate) fn last_seen(&self, transaction_hash: &TransactionHash) -> Option<BlockNumber> {
+        let sightings = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        sightings.last_seen.get(transaction_hash).copied()
+    }
+}
+
+/// Remembers when the gateway first reported transactions as received, until they are resolved to
+/// any other status, so that transactions which are stuck as received can be told apart.
+///
+/// At most `capacity` transactions are remembered, evicting the ones first seen longest ago.
+#[derive(Clone)]
+pub struct ReceivedSightings {
+    inner: Arc<Mutex<ReceivedSince>>,
+    capacity: usize,
+}
+
+#[derive(Default)]
+struct ReceivedSince {
+    first_seen: HashMap<TransactionHash, tokio::time::Instant>,
+    /// Transactions in the order they were first seen.
+    order: VecDeque<TransactionHash>,
+}
+
+impl Default for ReceivedSightings {
+    fn default() -> Self {
+        Self::new(10_000)
+    }
+}
+
+impl ReceivedSightings {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Default::default(),
+            capacity,
+        }
+    }
+
+    /// Records that the transaction is received, unless it already was.
+    pub(crate) fn record(&self, transaction_hash: TransactionHash) {
+        let mut sightings = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let ReceivedSince { first_seen, order } = &mut *sightings;
+
+        if first_seen.contains_key(&transaction_hash) {
+            return;
+        }
+        let now = tokio::time::Instant::now();
+        first_seen.insert(transaction_hash, now);
+        order.push_back(transaction_hash);
+
+        while order.len() > self.capacity {
+            if let Some(evicted) = order.pop_front() {
+                first_seen.remove(&evicted);
+            }
+        }
+    }
+
+    /// Forgets the transaction, as it is no longer received.
+    pub(crate) fn forget(&self, transaction_hash: &TransactionHash) {
+        let mut sightings = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let ReceivedSince { first_seen, order } = &mut *sightings;
+
+        // Transactions usually resolve to another status without ever being received, in which
+        // case there is nothing to forget.
+        if first_seen.remove(transaction_hash).is_some() {
+            order.retain(|hash| hash != transaction_hash);
+        }
+    }
+
+    /// Returns the transactions which have been received for at least `older_than`, longest
+    /// received first.
+    pub(crate) fn stuck(&self, older_than: Duration) -> Vec<TransactionHash> {
+        let sightings = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let now = tokio::time::Instant::now();
+
+        sightings
+            .order
+            .iter()
+            .take_while(|hash| {
+                let seen = sightings.first_seen[*hash];
+                now.saturating_duration_since(seen) >= older_than
+            })
+            .copied()
+            .collect()
+    }
+}
+
+/// A bloom filter of the transactions in storage, including persisted rejections, which lets
+/// status queries skip the database for hashes it definitely does not know.
+///
+/// Sync inserts the transactions of each block before committing it, so the filter never misses a
+/// stored transaction. False positives are merely fed through to the database, and become more
+/// likely once more transactions are inserted than the filter's capacity.
+///
+/// The default filter is disabled, i.e. it might contain any transaction.
+#[derive(Clone, Default)]
+pub struct KnownTransactions(Option<Arc<BloomFilter>>);
+
+struct BloomFilter {
+    bits: Box<[AtomicU64]>,
+}
+
+impl KnownTransactions {
+    /// Yields roughly a 1% false positive rate up to the filter's capacity.
+    const BITS_PER_TRANSACTION: usize = 10;
+    const HASHES: u64 = 7;
+    /// The minimum capacity a filter is [loaded](KnownTransactions::load) with, so that a new node
+    /// does not outgrow it right away.
+    const MIN_CAPACITY: usize = 1_000_000;
+
+    /// Creates an empty filter sized for `capacity` transactions.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let bits = capacity.max(1) * Self::BITS_PER_TRANSACTION;
+        let words = (bits + 63) / 64;
+        let bits = (0..words).map(|_| AtomicU64::new(0)).collect();
+
+        Self(Some(Arc::new(BloomFilter { bits })))
+    }
+
+    /// Creates a filter of all transactions in `storage`, with room for a quarter more. This reads
+    /// every transaction hash from the database, so it may take a while.
+    pub fn load(storage: &pathfinder_storage::Storage) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+        let db_tx = db.transaction().context("Creating database transaction")?;
+
+        let count = db_tx
+            .known_transaction_count()
+            .context("Counting known transactions")?;
+        let filter = Self::with_capacity((count + count / 4).max(Self::MIN_CAPACITY));
+        db_tx
+            .for_each_known_transaction_hash(|hash| filter.insert(hash))
+            .context("Reading known transaction hashes")?;
+
+        Ok(filter)
+    }
+
+    pub fn insert(&self, transaction_hash: TransactionHash) {
+        let Some(filter) = &self.0 else {
+            return;
+        };
+
+        for bit in filter.bit_indices(&transaction_hash) {
+            filter.bits[bit / 64].fetch_or(1 << (bit % 64), Ordering::Release);
+        }
+    }
+
+    /// Returns false only if the transaction was never inserted.
+    pub(crate) fn might_contain(&self, transaction_hash: &TransactionHash) -> bool {
+        let Some(filter) = &self.0 else {
+            return true;
+        };
+
+        filter
+            .bit_indices(transaction_hash)
+            .all(|bit| filter.bits[bit / 64].load(Ordering::Acquire) & (1 << (bit % 64)) != 0)
+    }
+}
+
+impl BloomFilter {
+    /// Derives the filter's bits for the transaction by double hashing.
+    fn bit_indices(&self, transaction_hash: &TransactionHash) -> impl Iterator<Item = usize> {
+        use std::hash::{Hash, Hasher};
+
+        let hash = |seed: u64| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            seed.hash(&mut hasher);
+            transaction_hash.hash(&mut hasher);
+            hasher.finish()
+        };
+        let (first, second) = (hash(0), hash(1));
+        let len = self.bits.len() as u64 * 64;
+
+        (0..KnownTransactions::HASHES)
+            .map(move |i| (first.wrapping_add(i.wrapping_mul(second)) % len) as usize)
+    }
+}
+
+/// Whether storage holds no blocks yet, which lets status queries on a freshly initialized node
+/// skip the database altogether.
+///
+/// Sync clears the flag before committing a block, and status queries before persisting a
+/// rejection, so storage which holds either is never taken to be empty. The default flag is
+/// cleared, i.e. storage is always queried.
+#[derive(Clone, Default)]
+pub struct EmptyStorage(Arc<AtomicBool>);
+
+impl EmptyStorage {
+    /// Sets the flag if `storage` holds no blocks.
+    pub fn load(storage: &pathfinder_storage::Storage) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+        let db_tx = db.transaction().context("Creating database transaction")?;
+
+        let latest = db_tx
+            .block_id(pathfinder_storage::BlockId::Latest)
+            .context("Fetching latest block")?;
+
+        Ok(Self(Arc::new(AtomicBool::new(latest.is_none()))))
+    }
+
+    pub fn clear(&self) {
+        self.0.store(false, Ordering::Release);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// The hash of the latest block in storage, which tells whether the pending data is stale, i.e.
+/// whether storage may already hold the pending block's transactions.
+///
+/// Sync records each block before committing it, so that pending data built on its parent is
+/// stale as soon as storage may hold the block, and forgets the head before a reorg. While the
+/// head is unknown, which is the default, pending data is always considered stale.
+#[derive(Clone, Default)]
+pub struct StorageHead(Arc<Mutex<Option<BlockHash>>>);
+
+impl StorageHead {
+    /// Records the latest block in `storage`.
+    pub fn load(storage: &pathfinder_storage::Storage) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+        let db_tx = db.transaction().context("Creating database transaction")?;
+
+        let latest = db_tx
+            .block_id(pathfinder_storage::BlockId::Latest)
+            .context("Fetching latest block")?;
+
+        Ok(Self(Arc::new(Mutex::new(latest.map(|(_, hash)| hash)))))
+    }
+
+    pub fn set(&self, head: Option<BlockHash>) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = head;
+    }
+
+    /// Whether pending data built on `parent_hash` may be stale.
+    pub(crate) fn is_stale(&self, parent_hash: BlockHash) -> bool {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) != Some(parent_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use pathfinder_common::felt_bytes;
+
+    use super::*;
+
+    const QUERIES: usize = 10;
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_queries_share_one_resolution() {
+        let flight = SingleFlight::default();
+        let calls = AtomicUsize::new(0);
+        let hash = TransactionHash(felt_bytes!(b"submitted tx"));
+
+        let queries = (0..QUERIES).map(|_| {
+            flight.run(hash, || async {
+                calls.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok::<_, ()>(TransactionStatus::Received)
+            })
+        });
+        let results = futures::future::join_all(queries).await;
+
+        assert_eq!(results, vec![Ok(TransactionStatus::Received); QUERIES]);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert!(flight.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn errors_are_not_shared() {
+        let flight = SingleFlight::<TransactionStatus>::default();
+        let calls = AtomicUsize::new(0);
+        let hash = TransactionHash(felt_bytes!(b"submitted tx"));
+
+        let queries = (0..QUERIES).map(|_| {
+            flight.run(hash, || async {
+                calls.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Err::<TransactionStatus, _>(())
+            })
+        });
+        let results = futures::future::join_all(queries).await;
+
+        assert_eq!(results, vec![Err(()); QUERIES]);
+        assert_eq!(calls.load(Ordering::Relaxed), QUERIES);
+        assert!(flight.in_flight.lock().unwrap().is_empty());
+    }
+
+    mod database_status_cache {
+        use super::*;
+
+        fn status(finality_status: TransactionStatus) -> TransactionStatusResponse {
+            TransactionStatusResponse::new(finality_status)
+        }
+
+        fn head(number: u64, hash: &[u8]) -> (BlockNumber, BlockHash) {
+            (
+                BlockNumber::new_or_panic(number),
+                BlockHash(felt_bytes!(hash)),
+            )
+        }
+
+        #[test]
+        fn hit_at_same_head() {
+            let cache = DatabaseStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"tx"));
+            let accepted = status(TransactionStatus::AcceptedOnL2);
+
+            assert_eq!(
+                cache.get(
+                    tx_hash,
+                    head(1, b"block 1"),
+                    L1Head::default(),
+                    StatusDetail::Finality
+                ),
+                None
+            );
+            cache.insert(
+                tx_hash,
+                head(1, b"block 1"),
+                L1Head::default(),
+                StatusDetail::Finality,
+                accepted.clone(),
+            );
+            assert_eq!(
+                cache.get(
+                    tx_hash,
+                    head(1, b"block 1"),
+                    L1Head::default(),
+                    StatusDetail::Finality
+                ),
+                Some(accepted)
+            );
+        }
+
+        #[test]
+        fn miss_at_other_head() {
+            let cache = DatabaseStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"tx"));
+            cache.insert(
+                tx_hash,
+                head(1, b"block 1"),
+                L1Head::default(),
+                StatusDetail::Finality,
+                status(TransactionStatus::AcceptedOnL2),
+            );
+
+            assert_eq!(
+                cache.get(
+                    tx_hash,
+                    head(2, b"block 2"),
+                    L1Head::default(),
+                    StatusDetail::Finality
+                ),
+                None
+            );
+            // A reorg to another block at the same height.
+            assert_eq!(
+                cache.get(
+                    tx_hash,
+                    head(1, b"other block 1"),
+                    L1Head::default(),
+                    StatusDetail::Finality
+                ),
+                None
+            );
+        }
+
+        #[test]
+        fn miss_at_other_l1_head() {
+            let cache = DatabaseStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"tx"));
+            cache.insert(
+                tx_hash,
+                head(1, b"block 1"),
+                L1Head::default(),
+                StatusDetail::Finality,
+                status(TransactionStatus::AcceptedOnL2),
+            );
+
+            let accepted_on_l1 = L1Head {
+                l2_block: Some(BlockNumber::new_or_panic(1)),
+                ..Default::default()
+            };
+            assert_eq!(
+                cache.get(
+                    tx_hash,
+                    head(1, b"block 1"),
+                    accepted_on_l1,
+                    StatusDetail::Finality
+                ),
+                None
+            );
+        }
+
+        #[test]
+        fn miss_for_more_detail() {
+            let cache = DatabaseStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"tx"));
+            let accepted = status(TransactionStatus::AcceptedOnL2);
+            cache.insert(
+                tx_hash,
+                head(1, b"block 1"),
+                L1Head::default(),
+                StatusDetail::Finality,
+                accepted.clone(),
+            );
+
+            assert_eq!(
+                cache.get(
+                    tx_hash,
+                    head(1, b"block 1"),
+                    L1Head::default(),
+                    StatusDetail::Full
+                ),
+                None
+            );
+
+            // Full details also serve lookups for less.
+            cache.insert(
+                tx_hash,
+                head(1, b"block 1"),
+                L1Head::default(),
+                StatusDetail::Full,
+                accepted.clone(),
+            );
+            assert_eq!(
+                cache.get(
+                    tx_hash,
+                    head(1, b"block 1"),
+                    L1Head::default(),
+                    StatusDetail::Finality
+                ),
+                Some(accepted)
+            );
+        }
+
+        #[test]
+        fn new_head_evicts_old_entries() {
+            let cache = DatabaseStatusCache::default();
+            let old = TransactionHash(felt_bytes!(b"old tx"));
+            let new = TransactionHash(felt_bytes!(b"new tx"));
+            let accepted = status(TransactionStatus::AcceptedOnL2);
+
+            cache.insert(
+                old,
+                head(1, b"block 1"),
+                L1Head::default(),
+                StatusDetail::Finality,
+                accepted.clone(),
+            );
+            cache.insert(
+                new,
+                head(2, b"block 2"),
+                L1Head::default(),
+                StatusDetail::Finality,
+                accepted,
+            );
+
+            let inner = cache.inner.lock().unwrap();
+            assert_eq!(inner.len(), 1);
+            assert!(inner.contains_key(&(new, BlockNumber::new_or_panic(2))));
+        }
+
+        #[test]
+        fn reorg_evicts_orphaned_entries() {
+            let cache = DatabaseStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"tx"));
+            cache.insert(
+                tx_hash,
+                head(2, b"block 2"),
+                L1Head::default(),
+                StatusDetail::Finality,
+                status(TransactionStatus::AcceptedOnL2),
+            );
+
+            cache.invalidate_from(BlockNumber::new_or_panic(2));
+            assert!(cache.inner.lock().unwrap().is_empty());
+
+            // The status is re-resolved at the new head, which caches it again.
+            let not_received = status(TransactionStatus::NotReceived);
+            cache.insert(
+                tx_hash,
+                head(1, b"block 1"),
+                L1Head::default(),
+                StatusDetail::Finality,
+                not_received.clone(),
+            );
+            assert_eq!(
+                cache.get(
+                    tx_hash,
+                    head(1, b"block 1"),
+                    L1Head::default(),
+                    StatusDetail::Finality
+                ),
+                Some(not_received)
+            );
+        }
+
+        #[test]
+        fn reorg_keeps_entries_below_it() {
+            let cache = DatabaseStatusCache::default();
+            let tx_hash = TransactionHash(felt_bytes!(b"tx"));
+            let accepted = status(TransactionStatus::AcceptedOnL2);
+            cache.insert(
+                tx_hash,
+                head(1, b"block 1"),
+                L1Head::default(),
+                StatusDetail::Finality,
+                accepted.clone(),
+            );
+
+            cache.invalidate_from(BlockNumber::new_or_panic(2));
+            assert_eq!(
+                cache.get(
+                    tx_hash,
+                    head(1, b"block 1"),
+                    L1Head::default(),
+                    StatusDetail::Finality
+                ),
+                Some(accepted)
+            );
+        }
+    }
+
+    mod gateway_circuit_breaker {
+        use super::*;
+
+        const WINDOW: Duration = Duration::from_secs(10);
+        const COOLDOWN: Duration = Duration::from_secs(30);
+
+        #[tokio::test(start_paused = true)]
+        async fn trips_after_consecutive_failures() {
+            let breaker = GatewayCircuitBreaker::new(3, WINDOW, COOLDOWN);
+
+            breaker.record_failure();
+            breaker.record_failure();
+            assert!(breaker.allow());
+
+            breaker.record_failure();
+            assert!(!breaker.allow());
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn success_resets_failures() {
+            let breaker = GatewayCircuitBreaker::new(3, WINDOW, COOLDOWN);
+
+            breaker.record_failure();
+            breaker.record_failure();
+            breaker.record_success();
+            breaker.record_failure();
+            breaker.record_failure();
+
+            assert!(breaker.allow());
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn failures_outside_window_start_over() {
+            let breaker = GatewayCircuitBreaker::new(3, WINDOW, COOLDOWN);
+
+            breaker.record_failure();
+            breaker.record_failure();
+            tokio::time::advance(WINDOW + Duration::from_secs(1)).await;
+            breaker.record_failure();
+            breaker.record_failure();
+            assert!(breaker.allow());
+
+            breaker.record_failure();
+            assert!(!breaker.allow());
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn half_open_probe_recovers() {
+            let breaker = GatewayCircuitBreaker::new(1, WINDOW, COOLDOWN);
+            breaker.record_failure();
+
+            tokio::time::advance(COOLDOWN - Duration::from_secs(1)).await;
+            assert!(!breaker.allow());
+
+            tokio::time::advance(Duration::from_secs(1)).await;
+            // Only a single probe is let through.
+            assert!(breaker.allow());
+            assert!(!breaker.allow());
+
+            breaker.record_success();
+            assert!(breaker.allow());
+            assert!(breaker.allow());
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn failed_probe_reopens() {
+            let breaker = GatewayCircuitBreaker::new(3, WINDOW, COOLDOWN);
+            for _ in 0..3 {
+                breaker.record_failure();
+            }
+
+            tokio::time::advance(COOLDOWN).await;
+            assert!(breaker.allow());
+            // A single failure suffices while probing.
+            breaker.record_failure();
+            assert!(!breaker.allow());
+
+            tokio::time::advance(COOLDOWN).await;
+            assert!(breaker.allow());
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn abandoned_probe_is_retried() {
+            let breaker = GatewayCircuitBreaker::new(1, WINDOW, COOLDOWN);
+            breaker.record_failure();
+
+            tokio::time::advance(COOLDOWN).await;
+            assert!(breaker.allow());
+
+            // The probe's outcome is never recorded.
+            tokio::time::advance(COOLDOWN).await;
+            assert!(breaker.allow());
+        }
+    }
+
+    mod received_sightings {
+        use super::*;
+
+        fn hash(i: u64) -> TransactionHash {
+            TransactionHash(stark_hash::Felt::from_u64(i))
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn stuck_until_forgotten() {
+            let sightings = ReceivedSightings::default();
+            sightings.record(hash(0));
+            tokio::time::advance(Duration::from_secs(60)).await;
+            sightings.record(hash(1));
+            // Seeing a received transaction again keeps its first sighting.
+            sightings.record(hash(0));
+            tokio::time::advance(Duration::from_secs(60)).await;
+
+            assert_eq!(
+                sightings.stuck(Duration::from_secs(60)),
+                vec![hash(0), hash(1)]
+            );
+            assert_eq!(sightings.stuck(Duration::from_secs(120)), vec![hash(0)]);
+            assert_eq!(sightings.stuck(Duration::from_secs(180)), vec![]);
+
+            sightings.forget(&hash(0));
+            assert_eq!(sightings.stuck(Duration::from_secs(60)), vec![hash(1)]);
+
+            // Once received again, the sighting starts over.
+            sightings.record(hash(0));
+            assert_eq!(sightings.stuck(Duration::from_secs(60)), vec![hash(1)]);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn oldest_sightings_are_evicted() {
+            let sightings = ReceivedSightings::new(3);
+
+            for i in 0..3 {
+                sightings.record(hash(i));
+            }
+            // A forgotten transaction no longer takes up a place.
+            sightings.forget(&hash(2));
+            sightings.record(hash(3));
+            sightings.record(hash(4));
+
+            assert_eq!(
+                sightings.stuck(Duration::ZERO),
+                vec![hash(1), hash(3), hash(4)]
+            );
+        }
+    }
+
+    mod status_waits {
+        use super::*;
+
+        #[tokio::test]
+        async fn disabling_completes_disabled() {
+            let waits = StatusWaits::default();
+            assert!(waits.is_enabled());
+
+            let disabled = tokio::spawn({
+                let waits = waits.clone();
+                async move { waits.disabled().await }
+            });
+            tokio::task::yield_now().await;
+            assert!(!disabled.is_finished());
+
+            waits.set_enabled(false);
+            assert!(!waits.is_enabled());
+            tokio::time::timeout(Duration::from_secs(1), disabled)
+                .await
+                .unwrap()
+                .unwrap();
+
+            // Already disabled.
+            waits.disabled().await;
+        }
+    }
+
+    mod empty_storage {
+        use super::*;
+
+        #[test]
+        fn loaded_from_empty_storage() {
+            let storage = pathfinder_storage::Storage::in_memory().unwrap();
+
+            let empty = EmptyStorage::load(&storage).unwrap();
+            assert!(empty.is_empty());
+
+            empty.clear();
+            assert!(!empty.is_empty());
+        }
+
+        #[test]
+        fn loaded_from_synced_storage() {
+            let storage = crate::test_utils::setup_storage();
+
+            assert!(!EmptyStorage::load(&storage).unwrap().is_empty());
+        }
+
+        #[test]
+        fn default_is_not_empty() {
+            assert!(!EmptyStorage::default().is_empty());
+        }
+    }
+
+    mod known_transactions {
+        use super::*;
+
+        fn hash(i: u64) -> TransactionHash {
+            TransactionHash(pathfinder_common::Felt::from_u64(i))
+        }
+
+        #[test]
+        fn inserted_transactions_are_contained() {
+            let filter = KnownTransactions::with_capacity(1000);
+            for i in 0..1000 {
+                filter.insert(hash(i));
+            }
+
+            assert!((0..1000).all(|i| filter.might_contain(&hash(i))));
+            // The false positive rate is around 1%, which is checked loosely to avoid flakiness.
+            let false_positives = (1000..2000)
+                .filter(|i| filter.might_contain(&hash(*i)))
+                .count();
+            assert!(false_positives < 100, "{false_positives}");
+        }
+
+        #[test]
+        fn disabled_contains_everything() {
+            let filter = KnownTransactions::default();
+            filter.insert(hash(0));
+
+            assert!(filter.might_contain(&hash(1)));
+        }
+
+        #[test]
+        fn load() {
+            let storage = pathfinder_storage::Storage::in_memory().unwrap();
+            let mut db = storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            let rejected = TransactionHash(felt_bytes!(b"rejected tx"));
+            db_tx.insert_rejected_transaction(rejected, "").unwrap();
+            db_tx.commit().unwrap();
+
+            let filter = KnownTransactions::load(&storage).unwrap();
+
+            assert!(filter.might_contain(&rejected));
+            assert!(!filter.might_contain(&TransactionHash(felt_bytes!(b"unknown tx"))));
+        }
+    }
+
+    mod pending_sightings {
+        use super::*;
+
+        fn hash(i: u64) -> TransactionHash {
+            TransactionHash(stark_hash::Felt::from_u64(i))
+        }
+
+        #[test]
+        fn last_seen_is_updated() {
+            let sightings = PendingSightings::default();
+            assert_eq!(sightings.last_seen(&hash(0)), None);
+
+            sightings.record(BlockNumber::new_or_panic(5), [hash(0), hash(1)]);
+            sightings.record(BlockNumber::new_or_panic(6), [hash(1)]);
+
+            assert_eq!(
+                sightings.last_seen(&hash(0)),
+                Some(BlockNumber::new_or_panic(5))
+            );
+            assert_eq!(
+                sightings.last_seen(&hash(1)),
+                Some(BlockNumber::new_or_panic(6))
+            );
+        }
+
+        #[test]
+        fn oldest_sightings_are_evicted() {
+            let sightings = PendingSightings::new(3);
+            let number = BlockNumber::new_or_panic(1);
+
+            sightings.record(number, (0..3).map(hash));
+            // Seeing a known transaction again does not change its eviction order.
+            sightings.record(number + 1, [hash(0)]);
+            sightings.record(number + 1, [hash(3), hash(4)]);
+
+            assert_eq!(sightings.last_seen(&hash(0)), None);
+            assert_eq!(sightings.last_seen(&hash(1)), None);
+            for i in 2..5 {
+                assert!(sightings.last_seen(&hash(i)).is_some(), "{i}");
+            }
+
+            let inner = sightings.inner.lock().unwrap();
+            assert_eq!(inner.order.len(), 3);
+            assert_eq!(inner.last_seen.len(), 3);
+        }
+    }
+
+    #[test]
+    fn retry_delays_double() {
+        let retry = GatewayRetry {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(100),
+        };
+
+        let delays = (0..=4).map(|n| retry.delay(n)).collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            vec![
+                None,
+                Some(Duration::from_millis(100)),
+                Some(Duration::from_millis(200)),
+                Some(Duration::from_millis(400)),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn wait_jitter_staggers_delays() {
+        let max = Duration::from_millis(500);
+        let jitter = WaitJitter::with_seed(max, 42);
+
+        let delays = (0..10).map(|_| jitter.delay()).collect::<Vec<_>>();
+        assert!(delays.iter().all(|delay| *delay <= max), "{delays:?}");
+
+        let mut distinct = delays.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert!(distinct.len() > 1, "{delays:?}");
+
+        // The same seed reproduces the same delays.
+        let again = WaitJitter::with_seed(max, 42);
+        assert_eq!((0..10).map(|_| again.delay()).collect::<Vec<_>>(), delays);
+    }
+
+    #[test]
+    fn zero_wait_jitter_does_not_delay() {
+        let jitter = WaitJitter::new(Duration::ZERO);
+        assert_eq!(jitter.delay(), Duration::ZERO);
+    }
+}
@@ -821,10 +821,12 @@ pub mod reply {
                 AcceptedOnL2 => BlockStatus::AcceptedOnL2,
                 NotReceived => BlockStatus::Rejected,
                 Pending => BlockStatus::Pending,
+                PreConfirmed => BlockStatus::Pending,
                 Received => BlockStatus::Pending,
                 Rejected => BlockStatus::Rejected,
                 Reverted => BlockStatus::Rejected,
                 Aborted => BlockStatus::Rejected,
+                Unknown => BlockStatus::Pending,
             }
         }
     }
@@ -1,11 +1,13 @@
 mod estimate_fee;
 mod get_events;
 mod get_state_update;
+mod get_transaction_status;
 pub(crate) mod simulate_transaction;
 
 pub(super) use estimate_fee::estimate_fee;
 pub(super) use get_events::get_events;
 pub(super) use get_state_update::get_state_update;
+pub(super) use get_transaction_status::get_transaction_status;
 pub(crate) use simulate_transaction::simulate_transaction;
 
 pub(crate) mod common {
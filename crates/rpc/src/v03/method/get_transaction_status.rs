@@ -0,0 +1,173 @@
+use pathfinder_common::TransactionHash;
+
+use crate::context::RpcContext;
+use crate::pathfinder::methods::{
+    resolve_status_details, ExecutionStatus, GetGatewayTransactionError, TransactionStatus,
+};
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct GetTransactionStatusInput {
+    transaction_hash: TransactionHash,
+}
+
+crate::error::generate_rpc_error_subset!(
+    GetTransactionStatusError: TxnHashNotFound,
+    GatewayUnavailable,
+    DatabaseBusy,
+    InconsistentState
+);
+
+impl From<GetGatewayTransactionError> for GetTransactionStatusError {
+    fn from(e: GetGatewayTransactionError) -> Self {
+        match e {
+            GetGatewayTransactionError::GatewayUnavailable => Self::GatewayUnavailable,
+            GetGatewayTransactionError::DatabaseBusy => Self::DatabaseBusy,
+            GetGatewayTransactionError::InconsistentState => Self::InconsistentState,
+            GetGatewayTransactionError::Internal(internal) => Self::Internal(internal),
+        }
+    }
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Clone, Debug, serde::Serialize, PartialEq)]
+pub struct GetTransactionStatusOutput {
+    finality_status: FinalityStatus,
+    execution_status: Option<ExecutionStatus>,
+}
+
+/// The spec's finality statuses, which unlike [TransactionStatus] have no notion of a
+/// transaction which was never received.
+#[derive(Copy, Clone, Debug, serde::Serialize, PartialEq)]
+pub enum FinalityStatus {
+    #[serde(rename = "RECEIVED")]
+    Received,
+    #[serde(rename = "REJECTED")]
+    Rejected,
+    #[serde(rename = "ACCEPTED_ON_L2")]
+    AcceptedOnL2,
+    #[serde(rename = "ACCEPTED_ON_L1")]
+    AcceptedOnL1,
+}
+
+/// Spec-compliant counterpart of `pathfinder_getTransactionStatus`, sharing its status
+/// resolution.
+pub async fn get_transaction_status(
+    context: RpcContext,
+    input: GetTransactionStatusInput,
+) -> Result<GetTransactionStatusOutput, GetTransactionStatusError> {
+    // The execution status is only known from the receipt.
+    let status = resolve_status_details(context, input.transaction_hash).await?;
+
+    let mut execution_status = status.execution_status;
+    let finality_status = match status.finality_status {
+        TransactionStatus::NotReceived => return Err(GetTransactionStatusError::TxnHashNotFound),
+        TransactionStatus::Received => FinalityStatus::Received,
+        TransactionStatus::Rejected | TransactionStatus::Aborted => FinalityStatus::Rejected,
+        // The spec considers pending transactions to be accepted on L2.
+        TransactionStatus::Pending
+        | TransactionStatus::PreConfirmed
+        | TransactionStatus::AcceptedOnL2 => FinalityStatus::AcceptedOnL2,
+        TransactionStatus::AcceptedOnL1 => FinalityStatus::AcceptedOnL1,
+        // Older gateway versions report reverted transactions as a finality status of their own.
+        TransactionStatus::Reverted => {
+            execution_status.get_or_insert(ExecutionStatus::Reverted);
+            FinalityStatus::AcceptedOnL2
+        }
+        TransactionStatus::Unknown => {
+            return Err(anyhow::anyhow!("Unrecognized transaction status").into())
+        }
+    };
+
+    Ok(GetTransactionStatusOutput {
+        finality_status,
+        execution_status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::felt_bytes;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn unknown_hash_is_not_found() {
+        let context = RpcContext::for_tests();
+        let unknown = TransactionHash(felt_bytes!(b"unknown txn"));
+        context
+            .gateway_status_cache
+            .insert(unknown, TransactionStatus::NotReceived);
+        let input = GetTransactionStatusInput {
+            transaction_hash: unknown,
+        };
+
+        let result = get_transaction_status(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(GetTransactionStatusError::TxnHashNotFound));
+    }
+
+    #[tokio::test]
+    async fn zero_hash_is_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetTransactionStatusInput {
+            transaction_hash: TransactionHash::ZERO,
+        };
+
+        let result = get_transaction_status(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(GetTransactionStatusError::TxnHashNotFound));
+    }
+
+    #[tokio::test]
+    async fn l1_accepted() {
+        let context = RpcContext::for_tests();
+        let input = GetTransactionStatusInput {
+            transaction_hash: TransactionHash(felt_bytes!(b"txn 0")),
+        };
+
+        let status = get_transaction_status(context, input).await.unwrap();
+
+        assert_eq!(
+            status,
+            GetTransactionStatusOutput {
+                finality_status: FinalityStatus::AcceptedOnL1,
+                execution_status: Some(ExecutionStatus::Succeeded),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn pending_is_accepted_on_l2() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let input = GetTransactionStatusInput {
+            transaction_hash: TransactionHash(felt_bytes!(b"pending tx hash 0")),
+        };
+
+        let status = get_transaction_status(context, input).await.unwrap();
+
+        assert_eq!(status.finality_status, FinalityStatus::AcceptedOnL2);
+    }
+
+    #[test]
+    fn serialization() {
+        let status = GetTransactionStatusOutput {
+            finality_status: FinalityStatus::Received,
+            execution_status: None,
+        };
+        let json = serde_json::to_value(status).unwrap();
+        assert_eq!(json, serde_json::json!({ "finality_status": "RECEIVED" }));
+
+        let status = GetTransactionStatusOutput {
+            finality_status: FinalityStatus::AcceptedOnL2,
+            execution_status: Some(ExecutionStatus::Reverted),
+        };
+        let json = serde_json::to_value(status).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "finality_status": "ACCEPTED_ON_L2",
+                "execution_status": "REVERTED",
+            })
+        );
+    }
+}
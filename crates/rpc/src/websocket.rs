@@ -7,13 +7,28 @@ pub fn register_subscriptions(
     module: Module,
     ws_broadcast_txs: types::WebsocketSenders,
 ) -> anyhow::Result<Module> {
-    let module = module.register_subscription(
-        "pathfinder_subscribe_newHeads",
-        "pathfinder_subscription_newHead",
-        "pathfinder_unsubscribe_newHeads",
-        subscription::subscribe_new_heads::subscribe_new_heads,
-        ws_broadcast_txs.new_head,
-    )?;
+    let module = module
+        .register_subscription(
+            "pathfinder_subscribe_newHeads",
+            "pathfinder_subscription_newHead",
+            "pathfinder_unsubscribe_newHeads",
+            subscription::subscribe_new_heads::subscribe_new_heads,
+            ws_broadcast_txs.new_head.clone(),
+        )?
+        .register_subscription_with_input(
+            "pathfinder_subscribe_transactionStatus",
+            "pathfinder_subscription_transactionStatus",
+            "pathfinder_unsubscribe_transactionStatus",
+            subscription::subscribe_transaction_status::subscribe_transaction_status,
+            ws_broadcast_txs.new_head.clone(),
+        )?
+        .register_subscription(
+            "pathfinder_subscribe_allTransactionStatus",
+            "pathfinder_subscription_allTransactionStatus",
+            "pathfinder_unsubscribe_allTransactionStatus",
+            subscription::subscribe_all_transaction_status::subscribe_all_transaction_status,
+            ws_broadcast_txs.new_head,
+        )?;
 
     Ok(module)
 }
@@ -1 +1,3 @@
+pub(super) mod subscribe_all_transaction_status;
 pub(super) mod subscribe_new_heads;
+pub(super) mod subscribe_transaction_status;
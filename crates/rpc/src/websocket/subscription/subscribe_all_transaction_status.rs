@@ -0,0 +1,113 @@
+use crate::context::RpcContext;
+use crate::status_changes::{self, StatusTracker, TransactionStatus};
+use crate::websocket::types::{BlockHeader, SubscriptionBroadcaster};
+use jsonrpsee::types::error::SubscriptionEmptyError;
+use jsonrpsee::SubscriptionSink;
+use pathfinder_common::TransactionHash;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// How often the sink is checked for having been closed while there are no changes to send.
+const CLOSED_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The number of status changes buffered for a subscriber. Once it is full, the oldest change is
+/// dropped in favour of the newest one.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// A change of a transaction's status, as sent to subscribers.
+#[derive(Clone, Debug, serde::Serialize, PartialEq)]
+pub struct StatusChange {
+    pub transaction_hash: TransactionHash,
+    pub old_status: TransactionStatus,
+    pub new_status: TransactionStatus,
+    /// The number of changes which were dropped since the previous notification, as the
+    /// subscriber did not keep up.
+    pub dropped: u64,
+}
+
+/// Notifies the subscriber of every status change of any transaction, caused by a refresh of the
+/// pending data or a newly committed block.
+///
+/// Each change is sent at most once: it is never repeated, and is dropped if the subscriber falls
+/// more than [CHANNEL_CAPACITY] changes behind, which is reported by the next notification's
+/// `dropped` count. Only changes which occur after subscribing are sent, see [status_changes] for
+/// which changes are observed.
+pub fn subscribe_all_transaction_status(
+    context: RpcContext,
+    mut sink: SubscriptionSink,
+    ws_new_heads_tx: &SubscriptionBroadcaster<BlockHeader>,
+) -> Result<(), SubscriptionEmptyError> {
+    let new_heads = ws_new_heads_tx.0.subscribe();
+    let (changes_tx, mut changes_rx) = broadcast::channel(CHANNEL_CAPACITY);
+
+    sink.accept()?;
+
+    // Tracks the statuses, so that a slow subscriber never holds up their evaluation.
+    tokio::spawn(async move {
+        let tracker = match StatusTracker::new(context.storage.clone()).await {
+            Ok(tracker) => tracker,
+            Err(error) => {
+                tracing::trace!("WS: allTransactionStatus subscription failed {error:?}");
+                return;
+            }
+        };
+
+        status_changes::track(
+            &context,
+            tracker,
+            Some(new_heads),
+            || changes_tx.receiver_count() == 0,
+            |change| {
+                let _ = changes_tx.send(change);
+            },
+        )
+        .await
+    });
+
+    tokio::spawn(async move {
+        let mut closed_check = tokio::time::interval(CLOSED_CHECK_INTERVAL);
+        let mut dropped = 0;
+        loop {
+            let result = tokio::select! {
+                result = changes_rx.recv() => result,
+                // Dropping the receiver stops the tracking, even if there are no changes to send.
+                _ = closed_check.tick() => {
+                    if sink.is_closed() {
+                        tracing::trace!("WS: allTransactionStatus subscription peer aborted");
+                        return;
+                    }
+                    continue;
+                }
+            };
+            let (transaction_hash, old_status, new_status) = match result {
+                Ok(change) => change,
+                Err(RecvError::Lagged(count)) => {
+                    dropped += count;
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+
+            let change = StatusChange {
+                transaction_hash,
+                old_status,
+                new_status,
+                dropped,
+            };
+            match sink.send(&change) {
+                Ok(true) => dropped = 0,
+                Ok(false) => {
+                    tracing::trace!("WS: allTransactionStatus subscription peer aborted");
+                    return;
+                }
+                Err(error) => {
+                    tracing::trace!("WS: allTransactionStatus subscription failed {error:?}");
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
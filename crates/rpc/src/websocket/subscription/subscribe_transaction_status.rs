@@ -0,0 +1,122 @@
+use crate::context::RpcContext;
+use crate::error::RpcError;
+use crate::pathfinder::methods::{resolve_status, GetGatewayTransactionError, TransactionStatus};
+use crate::websocket::types::{BlockHeader, SubscriptionBroadcaster};
+use jsonrpsee::core::error::SubscriptionClosed;
+use jsonrpsee::types::error::{ErrorCode, SubscriptionEmptyError};
+use jsonrpsee::types::ErrorObject;
+use jsonrpsee::SubscriptionSink;
+use pathfinder_common::TransactionHash;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+/// How often the status is re-evaluated in between new heads, which is required to pick up
+/// changes to the pending block and L1 acceptance.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct SubscribeTransactionStatusInput {
+    transaction_hash: TransactionHash,
+}
+
+/// Notifies the subscriber every time the status of the transaction changes.
+///
+/// The subscription is closed once the transaction reaches a final status, which includes
+/// the case where the transaction is already L1 accepted when subscribing.
+pub fn subscribe_transaction_status(
+    context: RpcContext,
+    input: SubscribeTransactionStatusInput,
+    mut sink: SubscriptionSink,
+    ws_new_heads_tx: &SubscriptionBroadcaster<BlockHeader>,
+) -> Result<(), SubscriptionEmptyError> {
+    let mut new_heads = ws_new_heads_tx.0.subscribe();
+    let transaction_hash = input.transaction_hash;
+
+    sink.accept()?;
+
+    tokio::spawn(async move {
+        let mut poll =
+            tokio::time::interval_at(tokio::time::Instant::now() + POLL_INTERVAL, POLL_INTERVAL);
+        let mut new_heads_open = true;
+        let mut last_status = None;
+
+        loop {
+            if sink.is_closed() {
+                tracing::trace!("WS: transactionStatus subscription peer aborted");
+                return;
+            }
+
+            match resolve_status(context.clone(), transaction_hash).await {
+                Ok(status) => {
+                    let status = status.finality_status;
+
+                    if last_status != Some(status) {
+                        match sink.send(&status) {
+                            Ok(true) => {}
+                            Ok(false) => {
+                                tracing::trace!("WS: transactionStatus subscription peer aborted");
+                                return;
+                            }
+                            Err(error) => {
+                                tracing::trace!(
+                                    "WS: transactionStatus subscription failed {error:?}"
+                                );
+                                return;
+                            }
+                        }
+                        last_status = Some(status);
+                    }
+
+                    if is_final(status) {
+                        sink.close(SubscriptionClosed::Success);
+                        return;
+                    }
+                }
+                // Transient, so simply try again on the next tick.
+                Err(
+                    GetGatewayTransactionError::GatewayUnavailable
+                    | GetGatewayTransactionError::DatabaseBusy,
+                ) => {}
+                Err(GetGatewayTransactionError::InconsistentState) => {
+                    let error = RpcError::InconsistentState;
+                    tracing::trace!("WS: transactionStatus subscription failed {error:?}");
+                    sink.close(ErrorObject::owned(
+                        error.code(),
+                        error.to_string(),
+                        None::<()>,
+                    ));
+                    return;
+                }
+                Err(GetGatewayTransactionError::Internal(error)) => {
+                    tracing::trace!("WS: transactionStatus subscription failed {error:?}");
+                    sink.close(ErrorObject::owned(
+                        ErrorCode::InternalError.code(),
+                        ErrorCode::InternalError.message(),
+                        None::<()>,
+                    ));
+                    return;
+                }
+            }
+
+            tokio::select! {
+                _ = poll.tick() => {}
+                result = new_heads.recv(), if new_heads_open => {
+                    new_heads_open = !matches!(result, Err(RecvError::Closed));
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Returns true if the status can no longer change.
+fn is_final(status: TransactionStatus) -> bool {
+    matches!(
+        status,
+        TransactionStatus::AcceptedOnL1
+            | TransactionStatus::Rejected
+            | TransactionStatus::Reverted
+            | TransactionStatus::Aborted
+    )
+}
@@ -0,0 +1,57 @@
+//! This test was separated because the `metrics` crate uses a singleton recorder, see
+//! `transaction_status_metrics.rs`.
+
+#[tokio::test]
+async fn status_cache_lookups_are_counted() {
+    use pathfinder_common::test_utils::metrics::{FakeRecorder, ScopedRecorderGuard};
+    use pathfinder_common::{felt_bytes, TransactionHash};
+    use pathfinder_rpc::middleware::versioning::test_utils::paths;
+    use pathfinder_rpc::test_client::TestClientBuilder;
+    use pathfinder_rpc::{context::RpcContext, RpcServer};
+    use serde_json::json;
+
+    const HITS: &str = "rpc_status_cache_hits_total";
+    const MISSES: &str = "rpc_status_cache_misses_total";
+
+    let recorder = FakeRecorder::default();
+    let handle = recorder.handle();
+    // Automatically deregister the recorder
+    let _guard = ScopedRecorderGuard::new(recorder);
+
+    let context = RpcContext::for_tests();
+    let (_server_handle, address) = RpcServer::new("127.0.0.1:0".parse().unwrap(), context)
+        .run()
+        .await
+        .unwrap();
+
+    let client = TestClientBuilder::default()
+        .address(address)
+        .endpoint(paths::PATHFINDER[0].into())
+        .build()
+        .unwrap();
+
+    let get_status = || {
+        client.request::<serde_json::Value>(
+            "pathfinder_getTransactionStatus",
+            json!({ "transaction_hash": TransactionHash(felt_bytes!(b"txn 1")) }),
+        )
+    };
+
+    // Resolved from the database, which caches it.
+    get_status().await.unwrap();
+    assert_eq!(
+        handle.get_counter_value_by_label(MISSES, [("cache", "database")]),
+        1
+    );
+
+    // Served from the cache, as the L2 head did not change.
+    get_status().await.unwrap();
+    assert_eq!(
+        handle.get_counter_value_by_label(HITS, [("cache", "database")]),
+        1
+    );
+    assert_eq!(
+        handle.get_counter_value_by_label(MISSES, [("cache", "database")]),
+        1
+    );
+}
@@ -0,0 +1,50 @@
+//! This test was separated because the `metrics` crate uses a singleton recorder, see
+//! `transaction_status_metrics.rs`.
+
+/// Serves `reply` to every request.
+fn serve(reply: serde_json::Value) -> reqwest::Url {
+    let reply = reply.to_string();
+    let make_service = hyper::service::make_service_fn(move |_| {
+        let reply = reply.clone();
+        async move {
+            Ok::<_, std::convert::Infallible>(hyper::service::service_fn(move |_| {
+                let body = hyper::Body::from(reply.clone());
+                async move { Ok::<_, std::convert::Infallible>(hyper::Response::new(body)) }
+            }))
+        }
+    });
+    let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    let url = format!("http://{}", server.local_addr()).parse().unwrap();
+    tokio::spawn(server);
+    url
+}
+
+#[tokio::test]
+async fn discrepancies_are_counted() {
+    use pathfinder_common::test_utils::metrics::{FakeRecorder, ScopedRecorderGuard};
+    use pathfinder_rpc::context::RpcContext;
+    use pathfinder_rpc::status_consistency::{check, METRIC_DISCREPANCIES};
+    use serde_json::json;
+
+    let recorder = FakeRecorder::default();
+    let handle = recorder.handle();
+    // Automatically deregister the recorder
+    let _guard = ScopedRecorderGuard::new(recorder);
+
+    // Only sample the latest block's transactions, which are accepted on L2.
+    const SAMPLE_SIZE: usize = 3;
+
+    let agreeing = serve(json!({ "status": "ACCEPTED_ON_L2" }));
+    let context = RpcContext::for_tests()
+        .with_status_gateway(starknet_gateway_client::Client::with_base_url(agreeing).unwrap());
+    assert_eq!(check(&context, SAMPLE_SIZE).await.unwrap(), 0);
+
+    let disagreeing = serve(json!({ "status": "REJECTED" }));
+    let context = context
+        .with_status_gateway(starknet_gateway_client::Client::with_base_url(disagreeing).unwrap());
+    assert_eq!(check(&context, SAMPLE_SIZE).await.unwrap(), SAMPLE_SIZE);
+    assert_eq!(
+        handle.get_counter_value_by_label(METRIC_DISCREPANCIES, []),
+        SAMPLE_SIZE as u64
+    );
+}
@@ -0,0 +1,59 @@
+//! This test was separated because the `metrics` crate uses a singleton recorder, see
+//! `transaction_status_metrics.rs`.
+
+#[tokio::test]
+async fn gateway_duration_is_only_recorded_for_gateway_queries() {
+    use pathfinder_common::test_utils::metrics::{FakeRecorder, ScopedRecorderGuard};
+    use pathfinder_common::{felt_bytes, TransactionHash};
+    use pathfinder_rpc::middleware::versioning::test_utils::paths;
+    use pathfinder_rpc::test_client::TestClientBuilder;
+    use pathfinder_rpc::{context::RpcContext, RpcServer};
+    use serde_json::json;
+    use std::time::Duration;
+
+    const GATEWAY_DURATION: &str = "rpc_get_transaction_status_gateway_duration_seconds";
+    const DB_DURATION: &str = "rpc_get_transaction_status_db_duration_seconds";
+
+    let recorder = FakeRecorder::default();
+    let handle = recorder.handle();
+    // Automatically deregister the recorder
+    let _guard = ScopedRecorderGuard::new(recorder);
+
+    // Nothing listens on this port, so any gateway query fails.
+    let unreachable_gateway =
+        starknet_gateway_client::Client::with_base_url("http://127.0.0.1:1".parse().unwrap())
+            .unwrap();
+    let context = RpcContext {
+        sequencer: unreachable_gateway,
+        ..RpcContext::for_tests()
+    }
+    .with_gateway_timeout(Duration::from_millis(100))
+    .with_gateway_retry(1, Duration::ZERO);
+
+    let (_server_handle, address) = RpcServer::new("127.0.0.1:0".parse().unwrap(), context)
+        .run()
+        .await
+        .unwrap();
+
+    let client = TestClientBuilder::default()
+        .address(address)
+        .endpoint(paths::PATHFINDER[0].into())
+        .build()
+        .unwrap();
+
+    let get_status = |hash: &'static [u8]| {
+        client.request::<serde_json::Value>(
+            "pathfinder_getTransactionStatus",
+            json!({ "transaction_hash": TransactionHash(felt_bytes!(hash)) }),
+        )
+    };
+
+    // Resolved from the database.
+    get_status(b"txn 0").await.unwrap();
+    assert_eq!(handle.get_histogram_count(GATEWAY_DURATION), 0);
+    assert!(handle.get_histogram_count(DB_DURATION) > 0);
+
+    // Unknown to this node, so the gateway is queried.
+    let _ = get_status(b"unknown txn").await;
+    assert_eq!(handle.get_histogram_count(GATEWAY_DURATION), 1);
+}
@@ -0,0 +1,77 @@
+//! This test was separated because the `metrics` crate uses a singleton recorder, so keeping a test
+//! that relies on metric values in a separate binary makes more sense than using an inter-test
+//! locking mechanism which can cause weird test failures without any obvious clue to what might
+//! have caused those failures in the first place.
+
+#[tokio::test]
+async fn transaction_status_outcomes_are_counted() {
+    use pathfinder_common::test_utils::metrics::{FakeRecorder, ScopedRecorderGuard};
+    use pathfinder_common::{felt_bytes, TransactionHash};
+    use pathfinder_rpc::middleware::versioning::test_utils::paths;
+    use pathfinder_rpc::test_client::TestClientBuilder;
+    use pathfinder_rpc::{context::RpcContext, RpcServer};
+    use serde_json::json;
+    use std::time::Duration;
+
+    let recorder = FakeRecorder::default();
+    let handle = recorder.handle();
+    // Automatically deregister the recorder
+    let _guard = ScopedRecorderGuard::new(recorder);
+
+    // Nothing listens on this port, so any gateway query fails.
+    let unreachable_gateway =
+        starknet_gateway_client::Client::with_base_url("http://127.0.0.1:1".parse().unwrap())
+            .unwrap();
+    let context = RpcContext {
+        sequencer: unreachable_gateway,
+        ..RpcContext::for_tests_with_pending().await
+    }
+    .with_gateway_timeout(Duration::from_millis(100));
+
+    let rejected = TransactionHash(felt_bytes!(b"rejected txn"));
+    let mut db = context.storage.connection().unwrap();
+    let db_tx = db.transaction().unwrap();
+    db_tx
+        .insert_rejected_transaction(rejected, "Invalid nonce")
+        .unwrap();
+    db_tx.commit().unwrap();
+
+    let (_server_handle, address) = RpcServer::new("127.0.0.1:0".parse().unwrap(), context)
+        .run()
+        .await
+        .unwrap();
+
+    let client = TestClientBuilder::default()
+        .address(address)
+        .endpoint(paths::PATHFINDER[0].into())
+        .build()
+        .unwrap();
+
+    for hash in [
+        TransactionHash(felt_bytes!(b"txn 0")),
+        TransactionHash(felt_bytes!(b"txn 0")),
+        TransactionHash(felt_bytes!(b"txn 1")),
+        TransactionHash(felt_bytes!(b"pending tx hash 0")),
+        rejected,
+        TransactionHash(felt_bytes!(b"unknown txn")),
+    ] {
+        let _ = client
+            .request::<serde_json::Value>(
+                "pathfinder_getTransactionStatus",
+                json!({ "transaction_hash": hash }),
+            )
+            .await;
+    }
+
+    for (status, expected) in [
+        ("accepted_on_l1", 2),
+        ("accepted_on_l2", 1),
+        ("pending", 1),
+        ("rejected", 1),
+        ("error", 1),
+    ] {
+        let actual = handle
+            .get_counter_value_by_label("rpc_get_transaction_status_total", [("status", status)]);
+        assert_eq!(actual, expected, "status: {status}");
+    }
+}
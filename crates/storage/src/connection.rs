@@ -10,13 +10,14 @@ mod state_update;
 mod transaction;
 mod trie;
 
-// Re-export this so users don't require rusqlite as a direct dep.
-pub use rusqlite::TransactionBehavior;
+// Re-export these so users don't require rusqlite as a direct dep.
+pub use rusqlite::{InterruptHandle, TransactionBehavior};
 
 pub use event::KEY_FILTER_LIMIT as EVENT_KEY_FILTER_LIMIT;
 pub use event::*;
 
-pub use transaction::TransactionStatus;
+pub(crate) use transaction::l1_to_l2_message_hash;
+pub use transaction::{RejectedTransaction, StoredTransactionStatus, TransactionStatus};
 
 pub use trie::{ClassTrieReader, ContractTrieReader, StorageTrieReader};
 
@@ -24,9 +25,10 @@ use pathfinder_common::trie::TrieNode;
 use pathfinder_common::{
     BlockHash, BlockHeader, BlockNumber, CasmHash, ClassCommitment, ClassCommitmentLeafHash,
     ClassHash, ContractAddress, ContractNonce, ContractRoot, ContractStateHash, SierraHash,
-    StorageAddress, StorageCommitment, StorageValue, TransactionHash,
+    StorageAddress, StorageCommitment, StorageValue, TransactionHash, TransactionNonce,
 };
 use pathfinder_ethereum::EthereumStateUpdate;
+use primitive_types::H256;
 use stark_hash::Felt;
 use starknet_gateway_types::reply::transaction as gateway;
 
@@ -54,6 +56,29 @@ impl Connection {
         let tx = self.0.transaction_with_behavior(behavior)?;
         Ok(Transaction(tx))
     }
+
+    /// Returns a handle which can abort the query currently running on this connection from
+    /// another thread.
+    ///
+    /// The handle remains tied to the underlying connection once it is returned to the pool, so
+    /// it must not be used beyond this [Connection]'s lifetime.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.0.get_interrupt_handle()
+    }
+
+    /// Deletes the header of a block while keeping its transactions, which is otherwise prevented
+    /// by the foreign keys. This lets tests exercise how such an inconsistent database is handled.
+    #[cfg(any(feature = "test-utils", test))]
+    pub fn delete_block_header_only(&mut self, block_hash: BlockHash) -> anyhow::Result<()> {
+        self.0.pragma_update(None, "foreign_keys", false)?;
+        let deleted = self.0.execute(
+            "DELETE FROM starknet_blocks WHERE hash = ?",
+            rusqlite::params![&block_hash],
+        );
+        self.0.pragma_update(None, "foreign_keys", true)?;
+        anyhow::ensure!(deleted? == 1, "Block header not found");
+        Ok(())
+    }
 }
 
 pub struct Transaction<'inner>(rusqlite::Transaction<'inner>);
@@ -123,10 +148,24 @@ impl<'inner> Transaction<'inner> {
         reference::l1_l2_pointer(self)
     }
 
+    /// Records the latest L1 block observed by L1 sync.
+    pub fn update_l1_head(&self, head: u64) -> anyhow::Result<()> {
+        reference::update_l1_head(self, head)
+    }
+
+    pub fn l1_head(&self) -> anyhow::Result<Option<u64>> {
+        reference::l1_head(self)
+    }
+
     pub fn upsert_l1_state(&self, update: &EthereumStateUpdate) -> anyhow::Result<()> {
         ethereum::upsert_l1_state(self, update)
     }
 
+    /// Records whether the proof of the state update at `block` has been verified on L1.
+    pub fn set_l1_state_proven(&self, block: BlockNumber, proven: bool) -> anyhow::Result<()> {
+        ethereum::set_l1_state_proven(self, block, proven)
+    }
+
     pub fn l1_state_at_number(
         &self,
         block: BlockNumber,
@@ -148,6 +187,16 @@ impl<'inner> Transaction<'inner> {
         transaction::insert_transactions(self, block_hash, block_number, transaction_data)
     }
 
+    /// Returns the canonical block containing the transaction, ignoring a block which was
+    /// orphaned by a reorg.
+    pub fn canonical_block_for_transaction(
+        &self,
+        hash: TransactionHash,
+    ) -> anyhow::Result<Option<(BlockNumber, BlockHash)>> {
+        transaction::canonical_block_for_transaction(self, hash)
+    }
+
+    /// Same as [Self::canonical_block_for_transaction], but only returns the block's hash.
     pub fn transaction_block_hash(
         &self,
         hash: TransactionHash,
@@ -155,6 +204,110 @@ impl<'inner> Transaction<'inner> {
         transaction::transaction_block_hash(self, hash)
     }
 
+    /// Returns the transaction's finality, along with the details of its canonical block and of
+    /// the state update which accepted it on L1, or [None] if the transaction is not in storage.
+    pub fn stored_transaction_status(
+        &self,
+        hash: TransactionHash,
+    ) -> anyhow::Result<Option<StoredTransactionStatus>> {
+        transaction::stored_transaction_status(self, hash)
+    }
+
+    /// Returns the hash and finality of every transaction in the blocks `from` up to and including
+    /// `to`, ordered by block and then by index.
+    pub fn transaction_statuses_in_block_range(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> anyhow::Result<Vec<(TransactionHash, TransactionStatus)>> {
+        transaction::transaction_statuses_in_block_range(self, from, to)
+    }
+
+    /// Returns the same finality as [Self::stored_transaction_status], but as of the L1 state
+    /// recorded at or before L1 block `l1_block` instead of the latest one.
+    pub fn transaction_finality_at_l1_block(
+        &self,
+        hash: TransactionHash,
+        l1_block: u64,
+    ) -> anyhow::Result<Option<TransactionStatus>> {
+        transaction::transaction_finality_at_l1_block(self, hash, l1_block)
+    }
+
+    /// Returns the hash of the L1 handler transaction which consumed the L1 to L2 message, if any.
+    pub fn l1_handler_for_message_hash(
+        &self,
+        message_hash: H256,
+    ) -> anyhow::Result<Option<TransactionHash>> {
+        transaction::l1_handler_for_message_hash(self, message_hash)
+    }
+
+    /// Returns the hash of the accepted transaction sent by `sender` with `nonce`, if any.
+    pub fn accepted_transaction_for_nonce(
+        &self,
+        sender: ContractAddress,
+        nonce: TransactionNonce,
+    ) -> anyhow::Result<Option<TransactionHash>> {
+        transaction::accepted_transaction_for_nonce(self, sender, nonce)
+    }
+
+    /// Returns the hash of the latest accepted transaction sent by `sender`, if any.
+    pub fn latest_transaction_for_sender(
+        &self,
+        sender: ContractAddress,
+    ) -> anyhow::Result<Option<TransactionHash>> {
+        transaction::latest_transaction_for_sender(self, sender)
+    }
+
+    /// Persists a transaction rejection reported by the gateway.
+    pub fn insert_rejected_transaction(
+        &self,
+        hash: TransactionHash,
+        reason: &str,
+    ) -> anyhow::Result<()> {
+        transaction::insert_rejected_transaction(self, hash, reason)
+    }
+
+    /// Returns the rejection reason if the transaction is known to have been rejected.
+    pub fn rejected_transaction_status(
+        &self,
+        hash: TransactionHash,
+    ) -> anyhow::Result<Option<String>> {
+        transaction::rejected_transaction_status(self, hash)
+    }
+
+    /// Returns up to `limit` persisted rejections observed before the one with id `before`, most
+    /// recently observed first.
+    pub fn recent_rejected_transactions(
+        &self,
+        before: Option<u64>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<RejectedTransaction>> {
+        transaction::recent_rejected_transactions(self, before, limit)
+    }
+
+    /// Returns the number of transactions which are stored, including persisted rejections.
+    pub fn known_transaction_count(&self) -> anyhow::Result<usize> {
+        transaction::known_transaction_count(self)
+    }
+
+    /// Calls `f` with the hash of each stored transaction, including persisted rejections.
+    pub fn for_each_known_transaction_hash(
+        &self,
+        f: impl FnMut(TransactionHash),
+    ) -> anyhow::Result<()> {
+        transaction::for_each_known_transaction_hash(self, f)
+    }
+
+    /// Returns the hash of the transaction at `index` within the given block, unless the block was
+    /// reorged away.
+    pub fn transaction_hash_at(
+        &self,
+        block_hash: BlockHash,
+        index: usize,
+    ) -> anyhow::Result<Option<TransactionHash>> {
+        transaction::transaction_hash_at(self, block_hash, index)
+    }
+
     pub fn transaction(
         &self,
         hash: TransactionHash,
@@ -1,5 +1,6 @@
 use pathfinder_common::BlockNumber;
 use pathfinder_ethereum::EthereumStateUpdate;
+use primitive_types::H256;
 
 use crate::prelude::*;
 
@@ -7,44 +8,93 @@ pub(super) fn upsert_l1_state(
     tx: &Transaction<'_>,
     update: &EthereumStateUpdate,
 ) -> anyhow::Result<()> {
+    // The L1 block number and transaction hash are only replaced if the state changed, so that
+    // they keep pointing at where the state was first observed. A changed state's proof status is
+    // no longer known.
     tx.inner().execute(
-        r"INSERT OR REPLACE INTO l1_state (
+        r"INSERT INTO l1_state (
                     starknet_block_number,
                     starknet_block_hash,
-                    starknet_state_root
+                    starknet_state_root,
+                    l1_block_number,
+                    l1_tx_hash
                 ) VALUES (
                     :starknet_block_number,
                     :starknet_block_hash,
-                    :starknet_state_root
-                )",
+                    :starknet_state_root,
+                    :l1_block_number,
+                    :l1_tx_hash
+                )
+                ON CONFLICT(starknet_block_number) DO UPDATE SET
+                    proven = CASE
+                        WHEN starknet_block_hash = excluded.starknet_block_hash
+                            AND starknet_state_root = excluded.starknet_state_root
+                        THEN proven
+                        ELSE NULL
+                    END,
+                    l1_block_number = CASE
+                        WHEN starknet_block_hash = excluded.starknet_block_hash
+                            AND starknet_state_root = excluded.starknet_state_root
+                        THEN COALESCE(l1_block_number, excluded.l1_block_number)
+                        ELSE excluded.l1_block_number
+                    END,
+                    l1_tx_hash = CASE
+                        WHEN starknet_block_hash = excluded.starknet_block_hash
+                            AND starknet_state_root = excluded.starknet_state_root
+                        THEN COALESCE(l1_tx_hash, excluded.l1_tx_hash)
+                        ELSE excluded.l1_tx_hash
+                    END,
+                    starknet_block_hash = excluded.starknet_block_hash,
+                    starknet_state_root = excluded.starknet_state_root",
         named_params! {
             ":starknet_block_number": &update.block_number,
             ":starknet_block_hash": &update.block_hash,
             ":starknet_state_root": &update.state_root,
+            ":l1_block_number": &update.l1_block_number,
+            ":l1_tx_hash": &update.l1_tx_hash.as_ref().map(|hash| hash.as_bytes()),
         },
     )?;
 
     Ok(())
 }
 
+pub(super) fn set_l1_state_proven(
+    tx: &Transaction<'_>,
+    block: BlockNumber,
+    proven: bool,
+) -> anyhow::Result<()> {
+    tx.inner().execute(
+        "UPDATE l1_state SET proven = ? WHERE starknet_block_number = ?",
+        params![&proven, &block],
+    )?;
+
+    Ok(())
+}
+
 pub(super) fn l1_state_at_number(
     tx: &Transaction<'_>,
     block: BlockNumber,
 ) -> anyhow::Result<Option<EthereumStateUpdate>> {
     tx.inner()
         .query_row(
-            r"SELECT starknet_block_number, starknet_block_hash, starknet_state_root FROM l1_state 
+            r"SELECT starknet_block_number, starknet_block_hash, starknet_state_root,
+                l1_block_number, l1_tx_hash
+            FROM l1_state
             WHERE starknet_block_number = ?",
             params![&block],
             |row| {
                 let block_number = row.get_block_number(0)?;
                 let block_hash = row.get_block_hash(1)?;
                 let state_root = row.get_state_commitment(2)?;
+                let l1_block_number = row.get(3)?;
+                let l1_tx_hash = row.get_optional_blob(4)?.map(H256::from_slice);
 
                 Ok(EthereumStateUpdate {
                     state_root,
                     block_number,
                     block_hash,
+                    l1_block_number,
+                    l1_tx_hash,
                 })
             },
         )
@@ -55,7 +105,9 @@ pub(super) fn l1_state_at_number(
 pub(super) fn latest_l1_state(tx: &Transaction<'_>) -> anyhow::Result<Option<EthereumStateUpdate>> {
     tx.inner()
         .query_row(
-            r"SELECT starknet_block_number, starknet_block_hash, starknet_state_root FROM l1_state 
+            r"SELECT starknet_block_number, starknet_block_hash, starknet_state_root,
+                l1_block_number, l1_tx_hash
+            FROM l1_state
             ORDER BY starknet_block_number DESC
             LIMIT 1",
             [],
@@ -63,11 +115,15 @@ pub(super) fn latest_l1_state(tx: &Transaction<'_>) -> anyhow::Result<Option<Eth
                 let block_number = row.get_block_number(0)?;
                 let block_hash = row.get_block_hash(1)?;
                 let state_root = row.get_state_commitment(2)?;
+                let l1_block_number = row.get(3)?;
+                let l1_tx_hash = row.get_optional_blob(4)?.map(H256::from_slice);
 
                 Ok(EthereumStateUpdate {
                     state_root,
                     block_number,
                     block_hash,
+                    l1_block_number,
+                    l1_tx_hash,
                 })
             },
         )
@@ -92,6 +148,8 @@ mod tests {
                 state_root: StateCommitment(Felt::from_hex_str(&"3".repeat(i + 1)).unwrap()),
                 block_number: BlockNumber::GENESIS + i as u64,
                 block_hash: BlockHash(Felt::from_hex_str(&"F".repeat(i + 1)).unwrap()),
+                l1_block_number: Some(100 + i as u64),
+                l1_tx_hash: None,
             })
             .collect::<Vec<_>>()
             .try_into()
@@ -155,6 +213,8 @@ mod tests {
             state_root: StateCommitment(felt!("0x1234")),
             block_number: BlockNumber::new_or_panic(10),
             block_hash: BlockHash(felt!("0xabdd")),
+            l1_block_number: Some(100),
+            l1_tx_hash: None,
         };
         upsert_l1_state(&tx, &original).unwrap();
 
@@ -162,6 +222,8 @@ mod tests {
             state_root: StateCommitment(felt!("0xabcdef")),
             block_number: original.block_number,
             block_hash: BlockHash(felt!("0xccdd22")),
+            l1_block_number: Some(200),
+            l1_tx_hash: None,
         };
         upsert_l1_state(&tx, &new_value).unwrap();
 
@@ -170,4 +232,62 @@ mod tests {
             .unwrap();
         assert_eq!(result, new_value);
     }
+
+    #[test]
+    fn upsert_keeps_first_l1_block_number() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let original = EthereumStateUpdate {
+            state_root: StateCommitment(felt!("0x1234")),
+            block_number: BlockNumber::new_or_panic(10),
+            block_hash: BlockHash(felt!("0xabdd")),
+            l1_block_number: Some(100),
+            l1_tx_hash: None,
+        };
+        upsert_l1_state(&tx, &original).unwrap();
+
+        // Observing the same state again in a later L1 block.
+        let observed_again = EthereumStateUpdate {
+            l1_block_number: Some(200),
+            ..original.clone()
+        };
+        upsert_l1_state(&tx, &observed_again).unwrap();
+
+        let result = l1_state_at_number(&tx, original.block_number)
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, original);
+    }
+
+    #[test]
+    fn l1_tx_hash_round_trips() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let update = EthereumStateUpdate {
+            l1_tx_hash: Some(H256::from_low_u64_be(0xabcd)),
+            ..create_updates()[0].clone()
+        };
+        upsert_l1_state(&tx, &update).unwrap();
+
+        let result = l1_state_at_number(&tx, update.block_number)
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, update);
+
+        // Observing the same state again keeps the transaction which first submitted it.
+        let observed_again = EthereumStateUpdate {
+            l1_tx_hash: Some(H256::from_low_u64_be(0x1234)),
+            ..update.clone()
+        };
+        upsert_l1_state(&tx, &observed_again).unwrap();
+
+        let result = l1_state_at_number(&tx, update.block_number)
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, update);
+    }
 }
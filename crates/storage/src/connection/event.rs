@@ -586,8 +586,10 @@ mod tests {
                     n_steps: 0,
                     n_memory_holes: 0,
                 }),
+                execution_status: gateway_tx::ExecutionStatus::Succeeded,
                 l1_to_l2_consumed_message: None,
                 l2_to_l1_messages: Vec::new(),
+                revert_error: None,
                 transaction_hash: transactions[0].hash(),
                 transaction_index: pathfinder_common::TransactionIndex::new_or_panic(0),
             },
@@ -602,8 +604,10 @@ mod tests {
                     n_steps: 0,
                     n_memory_holes: 0,
                 }),
+                execution_status: gateway_tx::ExecutionStatus::Succeeded,
                 l1_to_l2_consumed_message: None,
                 l2_to_l1_messages: Vec::new(),
+                revert_error: None,
                 transaction_hash: transactions[1].hash(),
                 transaction_index: pathfinder_common::TransactionIndex::new_or_panic(1),
             },
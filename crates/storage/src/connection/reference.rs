@@ -23,6 +23,22 @@ pub(super) fn l1_l2_pointer(tx: &Transaction<'_>) -> anyhow::Result<Option<Block
         .map_err(|e| e.into())
 }
 
+pub(super) fn update_l1_head(tx: &Transaction<'_>, head: u64) -> anyhow::Result<()> {
+    tx.inner()
+        .execute("UPDATE refs SET l1_head = ? WHERE idx = 1", params![&head])?;
+
+    Ok(())
+}
+
+pub(super) fn l1_head(tx: &Transaction<'_>) -> anyhow::Result<Option<u64>> {
+    // This table always contains exactly one row.
+    tx.inner()
+        .query_row("SELECT l1_head FROM refs WHERE idx = 1", [], |row| {
+            row.get(0)
+        })
+        .map_err(|e| e.into())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::Storage;
@@ -57,4 +73,18 @@ mod tests {
         let result = l1_l2_pointer(&tx).unwrap();
         assert_eq!(result, None);
     }
+
+    #[test]
+    fn l1_head() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let result = super::l1_head(&tx).unwrap();
+        assert_eq!(result, None);
+
+        update_l1_head(&tx, 1234).unwrap();
+        let result = super::l1_head(&tx).unwrap();
+        assert_eq!(result, Some(1234));
+    }
 }
@@ -1,16 +1,33 @@
 //! Contains starknet transaction related code and __not__ database transaction.
 
 use anyhow::Context;
-use pathfinder_common::{BlockHash, BlockNumber, TransactionHash};
+use pathfinder_common::{
+    BlockHash, BlockNumber, ContractAddress, TransactionHash, TransactionIndex, TransactionNonce,
+};
+use primitive_types::H256;
 use starknet_gateway_types::reply::transaction as gateway;
 
 use crate::{prelude::*, BlockId};
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TransactionStatus {
     L1Accepted,
     L2Accepted,
 }
 
+/// A transaction rejection reported by the gateway, as persisted by
+/// [Transaction::insert_rejected_transaction].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RejectedTransaction {
+    /// Increases in the order the rejections were observed.
+    pub id: u64,
+    pub hash: TransactionHash,
+    /// Empty if the gateway did not report why.
+    pub reason: String,
+    /// Seconds since the Unix epoch, if the rejection was observed since this is recorded.
+    pub observed_at: Option<u64>,
+}
+
 pub(super) fn insert_transactions(
     tx: &Transaction<'_>,
     block_hash: BlockHash,
@@ -46,6 +63,18 @@ pub(super) fn insert_transactions(
         // insert events from receipt
         super::event::insert_events(tx, block_number, receipt.transaction_hash, &receipt.events)
             .context("Inserting events")?;
+
+        if let gateway::Transaction::L1Handler(l1_handler) = transaction {
+            if let Some(message_hash) = l1_to_l2_message_hash(l1_handler) {
+                tx.inner()
+                    .execute(
+                        r"INSERT OR REPLACE INTO l1_handler_messages (message_hash, transaction_hash)
+                        VALUES (?, ?)",
+                        params![message_hash.as_bytes(), &l1_handler.transaction_hash],
+                    )
+                    .context("Inserting L1 handler message")?;
+            }
+        }
     }
 
     Ok(())
@@ -218,25 +247,432 @@ pub(super) fn transaction_data_for_block(
     Ok(Some(data))
 }
 
+/// The query behind [canonical_block_for_transaction].
+///
+/// SQLite prefers the primary key's index, which requires reading the transaction's row, so the
+/// covering index is forced instead.
+const CANONICAL_BLOCK_FOR_TRANSACTION: &str = r"SELECT canonical_blocks.number, canonical_blocks.hash
+    FROM starknet_transactions INDEXED BY starknet_transactions_hash_block_hash
+    JOIN canonical_blocks ON canonical_blocks.hash = starknet_transactions.block_hash
+    WHERE starknet_transactions.hash = ?";
+
+/// Returns the block containing the transaction, as long as it is part of the canonical chain.
+///
+/// After a reorg, a transaction can transiently still refer to an orphaned block until it is
+/// included in the new chain. Such a transaction is treated as not being in storage.
+pub(super) fn canonical_block_for_transaction(
+    tx: &Transaction<'_>,
+    hash: TransactionHash,
+) -> anyhow::Result<Option<(BlockNumber, BlockHash)>> {
+    tx.inner()
+        .query_row(CANONICAL_BLOCK_FOR_TRANSACTION, params![&hash], |row| {
+            let number = row.get_block_number(0)?;
+            let hash = row.get_block_hash(1)?;
+            Ok((number, hash))
+        })
+        .optional()
+        .map_err(|e| e.into())
+}
+
+/// Same as [canonical_block_for_transaction], but only returns the block's hash.
 pub(super) fn transaction_block_hash(
     tx: &Transaction<'_>,
     hash: TransactionHash,
 ) -> anyhow::Result<Option<BlockHash>> {
+    canonical_block_for_transaction(tx, hash).map(|block| block.map(|(_, hash)| hash))
+}
+
+/// A stored transaction's finality, along with the details of its canonical block and of the
+/// state update which accepted it on L1, as returned by [stored_transaction_status].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredTransactionStatus {
+    /// Whether the transaction's block has been accepted on L1.
+    pub finality: TransactionStatus,
+    pub block_number: BlockNumber,
+    pub block_hash: BlockHash,
+    /// The position of the transaction within its block.
+    pub index: TransactionIndex,
+    /// The number of the latest canonical block.
+    pub latest_block_number: BlockNumber,
+    /// The number of L1 blocks which have confirmed the state update containing the transaction's
+    /// block.
+    ///
+    /// This is [None] if the block has not been observed on L1 yet or the L1 block numbers are
+    /// unknown.
+    pub l1_confirmations: Option<u64>,
+    /// Whether the proof of the state update containing the transaction's block has been verified
+    /// on L1.
+    ///
+    /// This is [None] if the block has not been observed on L1 yet or the state update's proof
+    /// status is not tracked.
+    pub proven: Option<bool>,
+    /// The hash of the L1 transaction which submitted the state update containing the
+    /// transaction's block.
+    ///
+    /// This is [None] if the block has not been observed on L1 yet or the L1 transaction was not
+    /// persisted.
+    pub l1_tx_hash: Option<H256>,
+}
+
+/// Returns the status of the transaction if it is in a canonical block, in a single query.
+pub(super) fn stored_transaction_status(
+    tx: &Transaction<'_>,
+    hash: TransactionHash,
+) -> anyhow::Result<Option<StoredTransactionStatus>> {
+    // The first L1 state at or after the transaction's block is the one which included it.
+    tx.inner()
+        .query_row(
+            r"SELECT
+                canonical_blocks.number <= refs.l1_l2_head,
+                canonical_blocks.number,
+                canonical_blocks.hash,
+                starknet_transactions.idx,
+                (SELECT MAX(number) FROM canonical_blocks),
+                MAX(refs.l1_head - l1_state.l1_block_number, 0),
+                l1_state.proven,
+                l1_state.l1_tx_hash
+            FROM starknet_transactions
+            JOIN canonical_blocks ON canonical_blocks.hash = starknet_transactions.block_hash
+            JOIN refs ON refs.idx = 1
+            LEFT JOIN l1_state ON l1_state.starknet_block_number = (
+                SELECT MIN(starknet_block_number) FROM l1_state
+                WHERE starknet_block_number >= canonical_blocks.number
+            )
+            WHERE starknet_transactions.hash = ?",
+            params![&hash],
+            |row| {
+                // The comparison is NULL if no block has been accepted on L1 yet.
+                let finality = match row.get::<_, Option<bool>>(0)? {
+                    Some(true) => TransactionStatus::L1Accepted,
+                    Some(false) | None => TransactionStatus::L2Accepted,
+                };
+                let index = row.get::<_, i64>(3)?;
+                let index = u64::try_from(index)
+                    .ok()
+                    .and_then(TransactionIndex::new)
+                    .ok_or(rusqlite::Error::IntegralValueOutOfRange(3, index))?;
+                Ok(StoredTransactionStatus {
+                    finality,
+                    block_number: row.get_block_number(1)?,
+                    block_hash: row.get_block_hash(2)?,
+                    index,
+                    latest_block_number: row.get_block_number(4)?,
+                    l1_confirmations: row.get(5)?,
+                    proven: row.get(6)?,
+                    l1_tx_hash: row.get_optional_blob(7)?.map(H256::from_slice),
+                })
+            },
+        )
+        .optional()
+        .context("Querying transaction's status")
+}
+
+/// Returns the hash and finality of every transaction in the canonical blocks `from` up to and
+/// including `to`, ordered by block and then by their index within the block.
+///
+/// The finality is the same as [stored_transaction_status] reports for each of the transactions.
+pub(super) fn transaction_statuses_in_block_range(
+    tx: &Transaction<'_>,
+    from: BlockNumber,
+    to: BlockNumber,
+) -> anyhow::Result<Vec<(TransactionHash, TransactionStatus)>> {
+    let mut stmt = tx
+        .inner()
+        .prepare(
+            r"SELECT starknet_transactions.hash, canonical_blocks.number <= refs.l1_l2_head
+            FROM canonical_blocks
+            JOIN starknet_transactions
+                ON starknet_transactions.block_hash = canonical_blocks.hash
+            JOIN refs ON refs.idx = 1
+            WHERE canonical_blocks.number BETWEEN ? AND ?
+            ORDER BY canonical_blocks.number, starknet_transactions.idx",
+        )
+        .context("Preparing statement")?;
+
+    let rows = stmt
+        .query_map(params![&from, &to], |row| {
+            let hash = row.get_transaction_hash(0)?;
+            // The comparison is NULL if no block has been accepted on L1 yet.
+            let l1_accepted: Option<bool> = row.get(1)?;
+            let status = match l1_accepted {
+                Some(true) => TransactionStatus::L1Accepted,
+                Some(false) | None => TransactionStatus::L2Accepted,
+            };
+            Ok((hash, status))
+        })
+        .context("Querying transaction statuses")?;
+
+    rows.collect::<Result<_, _>>()
+        .context("Iterating over transaction statuses")
+}
+
+/// Returns the same finality as [stored_transaction_status], but compares against the latest L1
+/// state which had been observed at L1 block `l1_block`.
+pub(super) fn transaction_finality_at_l1_block(
+    tx: &Transaction<'_>,
+    hash: TransactionHash,
+    l1_block: u64,
+) -> anyhow::Result<Option<TransactionStatus>> {
+    let l1_accepted: Option<Option<bool>> = tx
+        .inner()
+        .query_row(
+            r"SELECT canonical_blocks.number <= (
+                SELECT MAX(starknet_block_number) FROM l1_state WHERE l1_block_number <= ?2
+            )
+            FROM starknet_transactions
+            JOIN canonical_blocks ON canonical_blocks.hash = starknet_transactions.block_hash
+            WHERE starknet_transactions.hash = ?1",
+            params![&hash, &l1_block],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    // The comparison is NULL if no L1 state had been observed by then.
+    Ok(l1_accepted.map(|l1_accepted| match l1_accepted {
+        Some(true) => TransactionStatus::L1Accepted,
+        Some(false) | None => TransactionStatus::L2Accepted,
+    }))
+}
+
+/// Returns the hash of the L1 handler transaction which consumed the L1 to L2 message.
+pub(super) fn l1_handler_for_message_hash(
+    tx: &Transaction<'_>,
+    message_hash: H256,
+) -> anyhow::Result<Option<TransactionHash>> {
+    tx.inner()
+        .query_row(
+            "SELECT transaction_hash FROM l1_handler_messages WHERE message_hash = ?",
+            params![message_hash.as_bytes()],
+            |row| row.get_transaction_hash(0),
+        )
+        .optional()
+        .context("Querying L1 handler for message hash")
+}
+
+/// Returns the hash of the L1 to L2 message consumed by the L1 handler transaction, as computed
+/// by the Starknet core contract on L1: the keccak256 of the sender, recipient, nonce, selector,
+/// payload length and payload, each as a 32 byte word.
+///
+/// The message's sender is the first element of the calldata, followed by its payload. This is
+/// [None] if the calldata is empty.
+pub(crate) fn l1_to_l2_message_hash(transaction: &gateway::L1HandlerTransaction) -> Option<H256> {
+    use sha3::{Digest, Keccak256};
+
+    let (from_address, payload) = transaction.calldata.split_first()?;
+    let mut payload_len = [0u8; 32];
+    payload_len[24..].copy_from_slice(&(payload.len() as u64).to_be_bytes());
+
+    let mut keccak = Keccak256::default();
+    keccak.update(from_address.0.as_be_bytes());
+    keccak.update(transaction.contract_address.get().as_be_bytes());
+    keccak.update(transaction.nonce.0.as_be_bytes());
+    keccak.update(transaction.entry_point_selector.0.as_be_bytes());
+    keccak.update(payload_len);
+    for element in payload {
+        keccak.update(element.0.as_be_bytes());
+    }
+
+    Some(H256(keccak.finalize().into()))
+}
+
+/// Returns the hash of the accepted transaction which was sent by `sender` with `nonce`.
+///
+/// The transaction is looked up in the block in which the sender's nonce first exceeded `nonce`.
+pub(super) fn accepted_transaction_for_nonce(
+    tx: &Transaction<'_>,
+    sender: ContractAddress,
+    nonce: TransactionNonce,
+) -> anyhow::Result<Option<TransactionHash>> {
+    let mut stmt = tx
+        .inner()
+        .prepare_cached(
+            r"SELECT block_number, nonce FROM nonce_updates
+            WHERE contract_address = ?
+            ORDER BY block_number ASC",
+        )
+        .context("Preparing statement")?;
+
+    let mut rows = stmt
+        .query(params![&sender])
+        .context("Querying nonce updates")?;
+
+    // Nonces are compressed when stored, so they cannot be compared in SQL.
+    let mut block = None;
+    while let Some(row) = rows.next().context("Reading next row")? {
+        let contract_nonce = row.get_contract_nonce(1).context("Getting nonce")?;
+        if contract_nonce.0 > nonce.0 {
+            block = Some(row.get_block_number(0).context("Getting block number")?);
+            break;
+        }
+    }
+
+    let Some(block) = block else {
+        return Ok(None);
+    };
+
+    let transactions = transaction_data_for_block(tx, block.into())?
+        .context("Block of nonce update is missing")?;
+
+    Ok(transactions
+        .into_iter()
+        .map(|(transaction, _)| transaction)
+        .find(|t| t.contract_address() == sender && t.nonce() == Some(nonce))
+        .map(|t| t.hash()))
+}
+
+/// Returns the hash of the latest accepted transaction which was sent by `sender`.
+///
+/// The transaction is looked up in the latest block in which the sender's nonce was updated, so
+/// only transactions which are sequenced by their sender's nonce are considered.
+pub(super) fn latest_transaction_for_sender(
+    tx: &Transaction<'_>,
+    sender: ContractAddress,
+) -> anyhow::Result<Option<TransactionHash>> {
+    let block = tx
+        .inner()
+        .query_row(
+            r"SELECT block_number FROM nonce_updates
+            WHERE contract_address = ?
+            ORDER BY block_number DESC
+            LIMIT 1",
+            params![&sender],
+            |row| row.get_block_number(0),
+        )
+        .optional()
+        .context("Querying latest nonce update")?;
+
+    let Some(block) = block else {
+        return Ok(None);
+    };
+
+    let transactions = transaction_data_for_block(tx, block.into())?
+        .context("Block of nonce update is missing")?;
+
+    Ok(transactions
+        .into_iter()
+        .map(|(transaction, _)| transaction)
+        .rev()
+        .find(|t| t.contract_address() == sender && t.nonce().is_some())
+        .map(|t| t.hash()))
+}
+
+pub(super) fn transaction_hash_at(
+    tx: &Transaction<'_>,
+    block_hash: BlockHash,
+    index: usize,
+) -> anyhow::Result<Option<TransactionHash>> {
     tx.inner()
         .query_row(
-            "SELECT block_hash FROM starknet_transactions WHERE hash = ?",
+            r"SELECT starknet_transactions.hash FROM starknet_transactions
+            JOIN canonical_blocks ON canonical_blocks.hash = starknet_transactions.block_hash
+            WHERE starknet_transactions.block_hash = ? AND starknet_transactions.idx = ?",
+            params![&block_hash, &index],
+            |row| row.get_transaction_hash(0),
+        )
+        .optional()
+        .map_err(|e| e.into())
+}
+
+pub(super) fn insert_rejected_transaction(
+    tx: &Transaction<'_>,
+    hash: TransactionHash,
+    reason: &str,
+) -> anyhow::Result<()> {
+    tx.inner()
+        .execute(
+            r"INSERT OR REPLACE INTO rejected_transactions (hash, reason, observed_at)
+                VALUES (?, ?, CAST(strftime('%s', 'now') AS INTEGER))",
+            params![&hash, &reason],
+        )
+        .context("Inserting rejected transaction")?;
+
+    Ok(())
+}
+
+pub(super) fn rejected_transaction_status(
+    tx: &Transaction<'_>,
+    hash: TransactionHash,
+) -> anyhow::Result<Option<String>> {
+    tx.inner()
+        .query_row(
+            "SELECT reason FROM rejected_transactions WHERE hash = ?",
             params![&hash],
-            |row| row.get_block_hash(0),
+            |row| row.get(0),
         )
         .optional()
         .map_err(|e| e.into())
 }
 
+pub(super) fn recent_rejected_transactions(
+    tx: &Transaction<'_>,
+    before: Option<u64>,
+    limit: usize,
+) -> anyhow::Result<Vec<RejectedTransaction>> {
+    let mut stmt = tx
+        .inner()
+        .prepare(
+            r"SELECT id, hash, reason, observed_at FROM rejected_transactions
+                WHERE id < ? ORDER BY id DESC LIMIT ?",
+        )
+        .context("Preparing statement")?;
+
+    let before = before.map_or(i64::MAX, |id| id.min(i64::MAX as u64) as i64);
+    let mut rows = stmt
+        .query(params![&before, &limit])
+        .context("Executing query")?;
+
+    let mut rejections = Vec::new();
+    while let Some(row) = rows.next().context("Iterating over rows")? {
+        let id: i64 = row.get(0)?;
+        let observed_at: Option<i64> = row.get(3)?;
+
+        rejections.push(RejectedTransaction {
+            id: id as u64,
+            hash: row.get_transaction_hash(1)?,
+            reason: row.get(2)?,
+            observed_at: observed_at.map(|at| at as u64),
+        });
+    }
+
+    Ok(rejections)
+}
+
+pub(super) fn known_transaction_count(tx: &Transaction<'_>) -> anyhow::Result<usize> {
+    tx.inner()
+        .query_row(
+            "SELECT (SELECT COUNT(*) FROM starknet_transactions)
+                + (SELECT COUNT(*) FROM rejected_transactions)",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.into())
+}
+
+pub(super) fn for_each_known_transaction_hash(
+    tx: &Transaction<'_>,
+    mut f: impl FnMut(TransactionHash),
+) -> anyhow::Result<()> {
+    let mut stmt = tx
+        .inner()
+        .prepare(
+            r"SELECT hash FROM starknet_transactions
+                UNION ALL SELECT hash FROM rejected_transactions",
+        )
+        .context("Preparing statement")?;
+    let mut rows = stmt.query([]).context("Executing query")?;
+
+    while let Some(row) = rows.next().context("Iterating over rows")? {
+        f(row.get_transaction_hash(0)?);
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use pathfinder_common::{
         felt_bytes, BlockHeader, CallParam, CasmHash, ClassHash, ConstructorParam, ContractAddress,
-        ContractAddressSalt, EntryPoint, Fee, TransactionIndex, TransactionNonce,
+        ContractAddressSalt, ContractNonce, EntryPoint, Fee, TransactionIndex, TransactionNonce,
         TransactionSignatureElem, TransactionVersion,
     };
     use starknet_gateway_types::reply::transaction::{
@@ -393,8 +829,10 @@ mod tests {
                 actual_fee: None,
                 events: vec![],
                 execution_resources: None,
+                execution_status: gateway::ExecutionStatus::Succeeded,
                 l1_to_l2_consumed_message: None,
                 l2_to_l1_messages: vec![],
+                revert_error: None,
                 transaction_hash: t.hash(),
                 transaction_index: TransactionIndex::new_or_panic(i as u64),
             })
@@ -522,4 +960,597 @@ mod tests {
                 .unwrap();
         assert_eq!(invalid, None);
     }
+
+    #[test]
+    fn stored_transaction_index() {
+        let (mut db, _, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        for (i, (transaction, _)) in body.iter().enumerate() {
+            let status = super::stored_transaction_status(&tx, transaction.hash()).unwrap();
+            assert_eq!(
+                status.map(|status| status.index),
+                TransactionIndex::new(i as u64)
+            );
+        }
+    }
+
+    /// Guards against a schema change silently turning this lookup into one which reads the
+    /// transaction's row, or into a table scan.
+    #[test]
+    fn transaction_block_hash_uses_index() {
+        let (mut db, _, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let target = body.first().unwrap().0.hash();
+        let plan = tx
+            .inner()
+            .prepare(&format!(
+                "EXPLAIN QUERY PLAN {}",
+                super::CANONICAL_BLOCK_FOR_TRANSACTION
+            ))
+            .unwrap()
+            .query_map(params![&target], |row| row.get::<_, String>(3))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(plan.len(), 2, "{plan:?}");
+        assert!(
+            plan[0].starts_with(
+                "SEARCH starknet_transactions USING COVERING INDEX \
+                 starknet_transactions_hash_block_hash"
+            ),
+            "{plan:?}"
+        );
+        assert!(
+            plan[1].starts_with("SEARCH canonical_blocks USING"),
+            "{plan:?}"
+        );
+    }
+
+    #[test]
+    fn canonical_block_for_transaction() {
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let target = body.first().unwrap().0.hash();
+        let result = super::canonical_block_for_transaction(&tx, target).unwrap();
+        assert_eq!(result, Some((header.number, header.hash)));
+
+        // Reorg the block away, while its transactions still refer to it.
+        tx.inner()
+            .execute(
+                "DELETE FROM canonical_blocks WHERE number = ?",
+                params![&header.number],
+            )
+            .unwrap();
+        let canonical = BlockHeader::builder()
+            .with_number(header.number)
+            .finalize_with_hash(BlockHash(felt_bytes!(b"canonical block hash")));
+        tx.insert_block_header(&canonical).unwrap();
+
+        let result = super::canonical_block_for_transaction(&tx, target).unwrap();
+        assert_eq!(result, None);
+        assert_eq!(super::transaction_block_hash(&tx, target).unwrap(), None);
+        assert_eq!(super::stored_transaction_status(&tx, target).unwrap(), None);
+
+        // Once the transaction is included in the canonical chain, it refers to its new block.
+        tx.insert_transaction_data(canonical.hash, canonical.number, &body[..1])
+            .unwrap();
+
+        let result = super::canonical_block_for_transaction(&tx, target).unwrap();
+        assert_eq!(result, Some((canonical.number, canonical.hash)));
+        assert_eq!(
+            super::transaction_block_hash(&tx, target).unwrap(),
+            Some(canonical.hash)
+        );
+        let status = super::stored_transaction_status(&tx, target)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (status.block_number, status.block_hash, status.finality),
+            (
+                canonical.number,
+                canonical.hash,
+                TransactionStatus::L2Accepted
+            )
+        );
+    }
+
+    #[test]
+    fn stored_transaction_finality() {
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let target = body.first().unwrap().0.hash();
+
+        // Compares against the separate block hash and L1 acceptance lookups.
+        let separate = |tx: &Transaction<'_>| {
+            let block_hash = super::transaction_block_hash(tx, target).unwrap().unwrap();
+            match tx.block_is_l1_accepted(block_hash.into()).unwrap() {
+                true => TransactionStatus::L1Accepted,
+                false => TransactionStatus::L2Accepted,
+            }
+        };
+
+        let finality = |tx: &Transaction<'_>| {
+            super::stored_transaction_status(tx, target)
+                .unwrap()
+                .unwrap()
+                .finality
+        };
+
+        assert_eq!(finality(&tx), TransactionStatus::L2Accepted);
+        assert_eq!(finality(&tx), separate(&tx));
+
+        tx.update_l1_l2_pointer(Some(header.number)).unwrap();
+        assert_eq!(finality(&tx), TransactionStatus::L1Accepted);
+        assert_eq!(finality(&tx), separate(&tx));
+    }
+
+    #[test]
+    fn transaction_statuses_in_block_range() {
+        // Spread the transactions across three blocks, two each.
+        let (_, _, body) = setup();
+        let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let mut header =
+            BlockHeader::builder().finalize_with_hash(BlockHash(felt_bytes!(b"block 0")));
+        for (i, chunk) in body.chunks(2).take(3).enumerate() {
+            if i > 0 {
+                header = header
+                    .child_builder()
+                    .finalize_with_hash(BlockHash(felt_bytes!(format!("block {i}").as_bytes())));
+            }
+            tx.insert_block_header(&header).unwrap();
+            tx.insert_transaction_data(header.hash, header.number, chunk)
+                .unwrap();
+        }
+        tx.update_l1_l2_pointer(Some(BlockNumber::GENESIS)).unwrap();
+
+        let hash = |idx: usize| body[idx].0.hash();
+        let statuses = |from: u64, to: u64| {
+            super::transaction_statuses_in_block_range(
+                &tx,
+                BlockNumber::new_or_panic(from),
+                BlockNumber::new_or_panic(to),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            statuses(0, 2),
+            vec![
+                (hash(0), TransactionStatus::L1Accepted),
+                (hash(1), TransactionStatus::L1Accepted),
+                (hash(2), TransactionStatus::L2Accepted),
+                (hash(3), TransactionStatus::L2Accepted),
+                (hash(4), TransactionStatus::L2Accepted),
+                (hash(5), TransactionStatus::L2Accepted),
+            ]
+        );
+        assert_eq!(
+            statuses(1, 1),
+            vec![
+                (hash(2), TransactionStatus::L2Accepted),
+                (hash(3), TransactionStatus::L2Accepted),
+            ]
+        );
+        // Blocks beyond the head have no transactions.
+        assert_eq!(statuses(3, 10), vec![]);
+    }
+
+    #[test]
+    fn transaction_finality_at_l1_block() {
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let target = body.first().unwrap().0.hash();
+        let child = header
+            .child_builder()
+            .finalize_with_hash(BlockHash(felt_bytes!(b"child hash")));
+        tx.insert_block_header(&child).unwrap();
+
+        // The transaction's block reaches L1 at L1 block 200, and its child at 300.
+        for (header, l1_block_number) in [(&header, 200), (&child, 300)] {
+            tx.upsert_l1_state(&pathfinder_ethereum::EthereumStateUpdate {
+                state_root: header.state_commitment,
+                block_number: header.number,
+                block_hash: header.hash,
+                l1_block_number: Some(l1_block_number),
+                l1_tx_hash: None,
+            })
+            .unwrap();
+        }
+        let finality = |l1_block| super::transaction_finality_at_l1_block(&tx, target, l1_block);
+
+        assert_eq!(finality(150).unwrap(), Some(TransactionStatus::L2Accepted));
+        assert_eq!(finality(200).unwrap(), Some(TransactionStatus::L1Accepted));
+        assert_eq!(finality(350).unwrap(), Some(TransactionStatus::L1Accepted));
+
+        let invalid = TransactionHash(felt_bytes!(b"invalid hash"));
+        let invalid = super::transaction_finality_at_l1_block(&tx, invalid, 350).unwrap();
+        assert_eq!(invalid, None);
+    }
+
+    #[test]
+    fn stored_transaction_l1_confirmations() {
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let target = body.first().unwrap().0.hash();
+        let l1_confirmations = |tx: &Transaction<'_>| {
+            super::stored_transaction_status(tx, target)
+                .unwrap()
+                .unwrap()
+                .l1_confirmations
+        };
+
+        // Not yet on L1.
+        assert_eq!(l1_confirmations(&tx), None);
+
+        tx.upsert_l1_state(&pathfinder_ethereum::EthereumStateUpdate {
+            state_root: header.state_commitment,
+            block_number: header.number,
+            block_hash: header.hash,
+            l1_block_number: Some(100),
+            l1_tx_hash: None,
+        })
+        .unwrap();
+        tx.update_l1_head(103).unwrap();
+
+        assert_eq!(l1_confirmations(&tx), Some(3));
+    }
+
+    #[test]
+    fn stored_transaction_proven() {
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let target = body.first().unwrap().0.hash();
+        let proven = |tx: &Transaction<'_>| {
+            super::stored_transaction_status(tx, target)
+                .unwrap()
+                .unwrap()
+                .proven
+        };
+
+        // Not yet on L1.
+        assert_eq!(proven(&tx), None);
+
+        let update = pathfinder_ethereum::EthereumStateUpdate {
+            state_root: header.state_commitment,
+            block_number: header.number,
+            block_hash: header.hash,
+            l1_block_number: Some(100),
+            l1_tx_hash: None,
+        };
+        tx.upsert_l1_state(&update).unwrap();
+
+        // On L1, but the proof status is not tracked.
+        assert_eq!(proven(&tx), None);
+
+        tx.set_l1_state_proven(header.number, false).unwrap();
+        assert_eq!(proven(&tx), Some(false));
+
+        tx.set_l1_state_proven(header.number, true).unwrap();
+        assert_eq!(proven(&tx), Some(true));
+
+        // Observing the same state again keeps its proof status.
+        tx.upsert_l1_state(&update).unwrap();
+        assert_eq!(proven(&tx), Some(true));
+    }
+
+    #[test]
+    fn stored_transaction_l1_tx_hash() {
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let target = body.first().unwrap().0.hash();
+        let l1_tx_hash = |tx: &Transaction<'_>| {
+            super::stored_transaction_status(tx, target)
+                .unwrap()
+                .unwrap()
+                .l1_tx_hash
+        };
+
+        // Not yet on L1.
+        assert_eq!(l1_tx_hash(&tx), None);
+
+        let mut update = pathfinder_ethereum::EthereumStateUpdate {
+            state_root: header.state_commitment,
+            block_number: header.number,
+            block_hash: header.hash,
+            l1_block_number: Some(100),
+            l1_tx_hash: None,
+        };
+        tx.upsert_l1_state(&update).unwrap();
+
+        // On L1, but the L1 transaction is not known.
+        assert_eq!(l1_tx_hash(&tx), None);
+
+        let hash = H256::from_low_u64_be(0xabcdef);
+        update.l1_tx_hash = Some(hash);
+        tx.upsert_l1_state(&update).unwrap();
+        assert_eq!(l1_tx_hash(&tx), Some(hash));
+    }
+
+    #[test]
+    fn stored_transaction_status() {
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let target = body[1].0.hash();
+        let child = header
+            .child_builder()
+            .finalize_with_hash(BlockHash(felt_bytes!(b"child hash")));
+        tx.insert_block_header(&child).unwrap();
+
+        let l2_accepted = StoredTransactionStatus {
+            finality: TransactionStatus::L2Accepted,
+            block_number: header.number,
+            block_hash: header.hash,
+            index: TransactionIndex::new_or_panic(1),
+            latest_block_number: child.number,
+            l1_confirmations: None,
+            proven: None,
+            l1_tx_hash: None,
+        };
+        let result = super::stored_transaction_status(&tx, target).unwrap();
+        assert_eq!(result, Some(l2_accepted.clone()));
+
+        let l1_tx_hash = H256::from_low_u64_be(0xabcdef);
+        tx.upsert_l1_state(&pathfinder_ethereum::EthereumStateUpdate {
+            state_root: header.state_commitment,
+            block_number: header.number,
+            block_hash: header.hash,
+            l1_block_number: Some(100),
+            l1_tx_hash: Some(l1_tx_hash),
+        })
+        .unwrap();
+        tx.set_l1_state_proven(header.number, true).unwrap();
+        tx.update_l1_head(103).unwrap();
+        tx.update_l1_l2_pointer(Some(header.number)).unwrap();
+
+        let result = super::stored_transaction_status(&tx, target).unwrap();
+        assert_eq!(
+            result,
+            Some(StoredTransactionStatus {
+                finality: TransactionStatus::L1Accepted,
+                l1_confirmations: Some(3),
+                proven: Some(true),
+                l1_tx_hash: Some(l1_tx_hash),
+                ..l2_accepted
+            })
+        );
+
+        let invalid =
+            super::stored_transaction_status(&tx, TransactionHash(felt_bytes!(b"invalid hash")))
+                .unwrap();
+        assert_eq!(invalid, None);
+    }
+
+    #[test]
+    fn l1_handler_for_message_hash() {
+        let (mut db, _, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let l1_handler = body
+            .iter()
+            .find_map(|(transaction, _)| match transaction {
+                gateway::Transaction::L1Handler(l1_handler) => Some(l1_handler),
+                _ => None,
+            })
+            .unwrap();
+        let message_hash = l1_to_l2_message_hash(l1_handler).unwrap();
+
+        let result = super::l1_handler_for_message_hash(&tx, message_hash).unwrap();
+        assert_eq!(result, Some(l1_handler.transaction_hash));
+
+        let invalid =
+            super::l1_handler_for_message_hash(&tx, H256::from_low_u64_be(0xabcdef)).unwrap();
+        assert_eq!(invalid, None);
+    }
+
+    #[test]
+    fn accepted_transaction_for_nonce() {
+        use crate::types::state_update::{Nonce, StateDiff};
+
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let gateway::Transaction::Invoke(gateway::InvokeTransaction::V1(invoke)) = &body[6].0
+        else {
+            panic!("Expected an invoke v1 transaction");
+        };
+
+        // The sender's nonce has not been updated yet.
+        let result =
+            super::accepted_transaction_for_nonce(&tx, invoke.sender_address, invoke.nonce)
+                .unwrap();
+        assert_eq!(result, None);
+
+        tx.insert_canonical_state_diff(
+            header.number,
+            &StateDiff {
+                nonces: vec![Nonce {
+                    contract_address: invoke.sender_address,
+                    nonce: ContractNonce(felt_bytes!(b"invoke v1 tx nonce + 1")),
+                }],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let result =
+            super::accepted_transaction_for_nonce(&tx, invoke.sender_address, invoke.nonce)
+                .unwrap();
+        assert_eq!(result, Some(invoke.transaction_hash));
+
+        let other_nonce = TransactionNonce(felt_bytes!(b"other nonce"));
+        let result =
+            super::accepted_transaction_for_nonce(&tx, invoke.sender_address, other_nonce).unwrap();
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn latest_transaction_for_sender() {
+        use crate::types::state_update::{Nonce, StateDiff};
+
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let gateway::Transaction::Invoke(gateway::InvokeTransaction::V1(invoke)) = &body[6].0
+        else {
+            panic!("Expected an invoke v1 transaction");
+        };
+
+        let second =
+            gateway::Transaction::Invoke(gateway::InvokeTransaction::V1(InvokeTransactionV1 {
+                nonce: TransactionNonce(felt_bytes!(b"invoke v1 tx nonce + 1")),
+                transaction_hash: TransactionHash(felt_bytes!(b"second invoke v1 tx hash")),
+                ..invoke.clone()
+            }));
+        let receipt = gateway::Receipt {
+            transaction_hash: second.hash(),
+            ..body[6].1.clone()
+        };
+        let next = header
+            .child_builder()
+            .finalize_with_hash(BlockHash(felt_bytes!(b"next block hash")));
+        tx.insert_block_header(&next).unwrap();
+        tx.insert_transaction_data(next.hash, next.number, &[(second.clone(), receipt)])
+            .unwrap();
+
+        // The sender's nonce has not been updated yet.
+        let result = super::latest_transaction_for_sender(&tx, invoke.sender_address).unwrap();
+        assert_eq!(result, None);
+
+        for (block, nonce) in [
+            (header.number, felt_bytes!(b"invoke v1 tx nonce + 1")),
+            (next.number, felt_bytes!(b"invoke v1 tx nonce + 2")),
+        ] {
+            tx.insert_canonical_state_diff(
+                block,
+                &StateDiff {
+                    nonces: vec![Nonce {
+                        contract_address: invoke.sender_address,
+                        nonce: ContractNonce(nonce),
+                    }],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        }
+
+        let result = super::latest_transaction_for_sender(&tx, invoke.sender_address).unwrap();
+        assert_eq!(result, Some(second.hash()));
+
+        let other_sender = ContractAddress::new_or_panic(felt_bytes!(b"other sender"));
+        let result = super::latest_transaction_for_sender(&tx, other_sender).unwrap();
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn transaction_hash_at() {
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let idx = 3;
+        let result = super::transaction_hash_at(&tx, header.hash, idx).unwrap();
+        assert_eq!(result, Some(body[idx].0.hash()));
+
+        let invalid_index = super::transaction_hash_at(&tx, header.hash, body.len()).unwrap();
+        assert_eq!(invalid_index, None);
+
+        let invalid_block =
+            super::transaction_hash_at(&tx, BlockHash(felt_bytes!(b"invalid")), idx).unwrap();
+        assert_eq!(invalid_block, None);
+
+        // Reorg the block away, while its transactions still refer to it.
+        tx.inner()
+            .execute(
+                "DELETE FROM canonical_blocks WHERE number = ?",
+                params![&header.number],
+            )
+            .unwrap();
+        let orphaned = super::transaction_hash_at(&tx, header.hash, idx).unwrap();
+        assert_eq!(orphaned, None);
+    }
+
+    #[test]
+    fn rejected_transaction() {
+        let (mut db, _, _) = setup();
+        let tx = db.transaction().unwrap();
+
+        let hash = TransactionHash(felt_bytes!(b"rejected tx hash"));
+        assert_eq!(super::rejected_transaction_status(&tx, hash).unwrap(), None);
+
+        super::insert_rejected_transaction(&tx, hash, "reason").unwrap();
+        assert_eq!(
+            super::rejected_transaction_status(&tx, hash).unwrap(),
+            Some("reason".to_owned())
+        );
+
+        // Re-inserting replaces the reason.
+        super::insert_rejected_transaction(&tx, hash, "other reason").unwrap();
+        assert_eq!(
+            super::rejected_transaction_status(&tx, hash).unwrap(),
+            Some("other reason".to_owned())
+        );
+    }
+
+    #[test]
+    fn recent_rejected_transactions() {
+        let (mut db, _, _) = setup();
+        let tx = db.transaction().unwrap();
+
+        let hashes = [
+            TransactionHash(felt_bytes!(b"rejected tx 0")),
+            TransactionHash(felt_bytes!(b"rejected tx 1")),
+            TransactionHash(felt_bytes!(b"rejected tx 2")),
+        ];
+        for hash in hashes {
+            super::insert_rejected_transaction(&tx, hash, "reason").unwrap();
+        }
+        // Observing a rejection again makes it the most recent one.
+        super::insert_rejected_transaction(&tx, hashes[0], "other reason").unwrap();
+
+        let all = super::recent_rejected_transactions(&tx, None, 10).unwrap();
+        let all_hashes = all.iter().map(|r| r.hash).collect::<Vec<_>>();
+        assert_eq!(all_hashes, vec![hashes[0], hashes[2], hashes[1]]);
+        assert_eq!(all[0].reason, "other reason");
+        assert!(all.iter().all(|r| r.observed_at.is_some()));
+
+        let page = super::recent_rejected_transactions(&tx, Some(all[0].id), 1).unwrap();
+        assert_eq!(page, vec![all[1].clone()]);
+        let page = super::recent_rejected_transactions(&tx, Some(all[2].id), 1).unwrap();
+        assert_eq!(page, vec![]);
+    }
+
+    #[test]
+    fn known_transaction_hashes() {
+        let (mut db, _, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let rejected = TransactionHash(felt_bytes!(b"rejected tx hash"));
+        super::insert_rejected_transaction(&tx, rejected, "reason").unwrap();
+
+        let mut expected = body
+            .iter()
+            .map(|(t, _)| t.hash())
+            .collect::<std::collections::HashSet<_>>();
+        expected.insert(rejected);
+
+        let mut hashes = std::collections::HashSet::new();
+        super::for_each_known_transaction_hash(&tx, |hash| {
+            hashes.insert(hash);
+        })
+        .unwrap();
+        assert_eq!(hashes, expected);
+
+        assert_eq!(super::known_transaction_count(&tx).unwrap(), expected.len());
+    }
 }
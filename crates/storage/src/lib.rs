@@ -16,6 +16,7 @@ pub mod types;
 
 use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 pub use connection::*;
@@ -91,6 +92,22 @@ struct Inner {
     /// Uses [`Arc`] to allow _shallow_ [Storage] cloning
     database_path: Arc<PathBuf>,
     pool: Pool<SqliteConnectionManager>,
+    /// Serves [ConnectionKind::Replica] connections, if configured.
+    replica: Option<Pool<SqliteConnectionManager>>,
+    /// The number of connections taken from either pool so far.
+    acquired: Arc<AtomicU64>,
+}
+
+/// The kind of [Connection] requested from [Storage::connection_of_kind].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionKind {
+    /// A connection to the database which sync writes to.
+    Primary,
+    /// A read-only connection to the read replica configured by [Storage::with_replica], or to the
+    /// primary database if there is none.
+    ///
+    /// The replica may lag behind the primary, so data missing from it may still be in the primary.
+    Replica,
 }
 
 pub struct StorageManager(PathBuf);
@@ -105,6 +122,8 @@ impl StorageManager {
         Ok(Storage(Inner {
             database_path: Arc::new(self.0.clone()),
             pool,
+            replica: None,
+            acquired: Arc::default(),
         }))
     }
 }
@@ -136,9 +155,74 @@ impl Storage {
     /// Returns a new Sqlite [Connection] to the database.
     pub fn connection(&self) -> anyhow::Result<Connection> {
         let conn = self.0.pool.get()?;
+        self.0.acquired.fetch_add(1, Ordering::Relaxed);
         Ok(Connection::from_inner(conn))
     }
 
+    /// Opens a read replica of the database at `database_path`, to be passed to
+    /// [Storage::with_replica].
+    ///
+    /// The replica is kept up to date by an external replication tool, so it is opened read-only
+    /// and its schema is not migrated.
+    pub fn open_replica(database_path: PathBuf, capacity: NonZeroU32) -> anyhow::Result<Self> {
+        use rusqlite::OpenFlags;
+
+        let pool_manager = SqliteConnectionManager::file(&database_path)
+            .with_flags(
+                OpenFlags::SQLITE_OPEN_READ_ONLY
+                    | OpenFlags::SQLITE_OPEN_URI
+                    | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )
+            .with_init(setup_connection);
+        let pool = Pool::builder()
+            .max_size(capacity.get())
+            .build(pool_manager)
+            .context("Creating read replica connection pool")?;
+
+        Ok(Storage(Inner {
+            database_path: Arc::new(database_path),
+            pool,
+            replica: None,
+            acquired: Arc::default(),
+        }))
+    }
+
+    /// Serves [ConnectionKind::Replica] connections from `replica` instead of this database.
+    pub fn with_replica(self, replica: Storage) -> Self {
+        Self(Inner {
+            replica: Some(replica.0.pool),
+            ..self.0
+        })
+    }
+
+    /// Returns true if a read replica was configured by [Storage::with_replica].
+    pub fn has_replica(&self) -> bool {
+        self.0.replica.is_some()
+    }
+
+    /// Returns a new Sqlite [Connection] of the given kind.
+    pub fn connection_of_kind(&self, kind: ConnectionKind) -> anyhow::Result<Connection> {
+        match (kind, &self.0.replica) {
+            (ConnectionKind::Replica, Some(replica)) => {
+                let conn = replica.get()?;
+                self.0.acquired.fetch_add(1, Ordering::Relaxed);
+                Ok(Connection::from_inner(conn))
+            }
+            _ => self.connection(),
+        }
+    }
+
+    /// Returns the number of connections which are idle in the pool.
+    pub fn idle_connections(&self) -> u32 {
+        self.0.pool.state().idle_connections
+    }
+
+    /// Returns the number of connections of any kind which have been taken from this storage so
+    /// far, e.g. to check whether a query was answered without the database.
+    pub fn acquired_connections(&self) -> u64 {
+        self.0.acquired.load(Ordering::Relaxed)
+    }
+
     /// Convenience function for tests to create an in-memory database.
     /// Equivalent to [Storage::migrate] with an in-memory backed database.
     // No longer cfg(test) because needed in benchmarks
@@ -172,6 +256,20 @@ impl Storage {
     }
 }
 
+/// Returns true if the error was caused by the database being busy or locked by another
+/// connection, in which case the operation may succeed if retried later.
+pub fn is_busy(error: &anyhow::Error) -> bool {
+    use rusqlite::ErrorCode;
+
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<rusqlite::Error>(),
+            Some(rusqlite::Error::SqliteFailure(e, _))
+                if matches!(e.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+        )
+    })
+}
+
 fn setup_journal_mode(
     connection: &mut rusqlite::Connection,
     journal_mode: JournalMode,
@@ -374,6 +472,65 @@ mod tests {
         migrate_database(&mut conn).unwrap_err();
     }
 
+    #[test]
+    fn replica_connections() {
+        let primary = Storage::in_memory().unwrap();
+        let replica = Storage::in_memory().unwrap();
+
+        let mut db = replica.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        tx.insert_rejected_transaction(pathfinder_common::TransactionHash(felt!("0x1")), "")
+            .unwrap();
+        tx.commit().unwrap();
+
+        let rejected = |storage: &Storage, kind| {
+            let mut db = storage.connection_of_kind(kind).unwrap();
+            let tx = db.transaction().unwrap();
+            tx.rejected_transaction_status(pathfinder_common::TransactionHash(felt!("0x1")))
+                .unwrap()
+                .is_some()
+        };
+
+        // Without a replica, all connections are to the primary.
+        assert!(!rejected(&primary, ConnectionKind::Replica));
+
+        let primary = primary.with_replica(replica);
+        assert!(primary.has_replica());
+        assert!(rejected(&primary, ConnectionKind::Replica));
+        assert!(!rejected(&primary, ConnectionKind::Primary));
+    }
+
+    #[test]
+    fn acquired_connections() {
+        let primary = Storage::in_memory().unwrap();
+        let replica = Storage::in_memory().unwrap();
+        assert_eq!(primary.acquired_connections(), 0);
+
+        drop(primary.connection().unwrap());
+        assert_eq!(primary.acquired_connections(), 1);
+
+        // Clones share the count, which includes replica connections.
+        let primary = primary.clone().with_replica(replica);
+        drop(primary.connection_of_kind(ConnectionKind::Replica).unwrap());
+        drop(primary.connection_of_kind(ConnectionKind::Primary).unwrap());
+        assert_eq!(primary.acquired_connections(), 3);
+    }
+
+    #[test]
+    fn busy_errors() {
+        use rusqlite::ffi;
+
+        let sqlite_error = |code| {
+            anyhow::Error::new(rusqlite::Error::SqliteFailure(ffi::Error::new(code), None))
+                .context("Opening database connection")
+        };
+
+        assert!(is_busy(&sqlite_error(ffi::SQLITE_BUSY)));
+        assert!(is_busy(&sqlite_error(ffi::SQLITE_LOCKED)));
+        assert!(!is_busy(&sqlite_error(ffi::SQLITE_CORRUPT)));
+        assert!(!is_busy(&anyhow::anyhow!("Some other error")));
+    }
+
     #[test]
     fn foreign_keys_are_enforced() {
         let conn = rusqlite::Connection::open_in_memory().unwrap();
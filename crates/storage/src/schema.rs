@@ -5,6 +5,13 @@ mod revision_0032;
 mod revision_0033;
 mod revision_0034;
 mod revision_0035;
+mod revision_0036;
+mod revision_0037;
+mod revision_0038;
+mod revision_0039;
+mod revision_0040;
+mod revision_0041;
+mod revision_0042;
 
 pub(crate) use base::base_schema;
 
@@ -19,6 +26,13 @@ pub fn migrations() -> &'static [MigrationFn] {
         revision_0033::migrate,
         revision_0034::migrate,
         revision_0035::migrate,
+        revision_0036::migrate,
+        revision_0037::migrate,
+        revision_0038::migrate,
+        revision_0039::migrate,
+        revision_0040::migrate,
+        revision_0041::migrate,
+        revision_0042::migrate,
     ]
 }
 
@@ -0,0 +1,19 @@
+use anyhow::Context;
+use rusqlite::Transaction;
+
+/// Adds a table which persists the transaction rejections reported by the gateway.
+///
+/// This lets the node answer status queries for rejected transactions even once the gateway
+/// no longer knows about them.
+pub(crate) fn migrate(tx: &Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"CREATE TABLE rejected_transactions (
+            hash    BLOB PRIMARY KEY NOT NULL,
+            reason  TEXT NOT NULL
+        )",
+        [],
+    )
+    .context("Creating rejected_transactions table")?;
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use anyhow::Context;
+use rusqlite::Transaction;
+
+/// Tracks the L1 block at which each Starknet state update was first observed, along with the
+/// latest observed L1 block.
+///
+/// Together these let the node report how many L1 blocks have confirmed a state update.
+pub(crate) fn migrate(tx: &Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        "ALTER TABLE l1_state ADD COLUMN l1_block_number INTEGER",
+        [],
+    )
+    .context("Adding l1_block_number column to l1_state")?;
+
+    tx.execute("ALTER TABLE refs ADD COLUMN l1_head INTEGER", [])
+        .context("Adding l1_head column to refs")?;
+
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use anyhow::Context;
+use rusqlite::Transaction;
+
+/// Tracks whether the proof of each Starknet state update has been verified on L1, as opposed to
+/// the state update merely having been committed.
+///
+/// This is NULL for state updates whose proof status is not tracked.
+pub(crate) fn migrate(tx: &Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute("ALTER TABLE l1_state ADD COLUMN proven INTEGER", [])
+        .context("Adding proven column to l1_state")?;
+
+    Ok(())
+}
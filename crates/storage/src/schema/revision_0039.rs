@@ -0,0 +1,38 @@
+use anyhow::Context;
+use rusqlite::Transaction;
+
+/// Records when each transaction rejection was observed, and gives each rejection an id which
+/// increases in the order they were observed.
+///
+/// The id lets rejections be paginated stably, as it survives a `VACUUM` unlike an implicit rowid.
+/// Rejections persisted before this migration keep their order, but have no observation time.
+pub(crate) fn migrate(tx: &Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"CREATE TABLE rejected_transactions_new (
+            id          INTEGER PRIMARY KEY,
+            hash        BLOB UNIQUE NOT NULL,
+            reason      TEXT NOT NULL,
+            observed_at INTEGER
+        )",
+        [],
+    )
+    .context("Creating rejected_transactions_new table")?;
+
+    tx.execute(
+        r"INSERT INTO rejected_transactions_new (hash, reason)
+            SELECT hash, reason FROM rejected_transactions ORDER BY rowid",
+        [],
+    )
+    .context("Copying rejected transactions")?;
+
+    tx.execute("DROP TABLE rejected_transactions", [])
+        .context("Dropping rejected_transactions table")?;
+
+    tx.execute(
+        "ALTER TABLE rejected_transactions_new RENAME TO rejected_transactions",
+        [],
+    )
+    .context("Renaming rejected_transactions_new table")?;
+
+    Ok(())
+}
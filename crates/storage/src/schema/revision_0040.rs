@@ -0,0 +1,13 @@
+use anyhow::Context;
+use rusqlite::Transaction;
+
+/// Records the L1 transaction which submitted each Starknet state update.
+///
+/// This is NULL for state updates whose L1 transaction is not known, including all of those
+/// persisted before this migration.
+pub(crate) fn migrate(tx: &Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute("ALTER TABLE l1_state ADD COLUMN l1_tx_hash BLOB", [])
+        .context("Adding l1_tx_hash column to l1_state")?;
+
+    Ok(())
+}
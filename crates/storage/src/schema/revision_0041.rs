@@ -0,0 +1,76 @@
+use anyhow::Context;
+use rusqlite::{params, Transaction};
+use starknet_gateway_types::reply::transaction as gateway;
+
+/// Maps the L1 to L2 messages consumed by L1 handler transactions to those transactions, so that
+/// their status can be looked up by message hash.
+///
+/// This indexes all L1 handler transactions persisted before this migration, which requires
+/// reading every stored transaction.
+pub(crate) fn migrate(tx: &Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"CREATE TABLE l1_handler_messages (
+    message_hash     BLOB PRIMARY KEY,
+    transaction_hash BLOB NOT NULL
+)",
+        [],
+    )
+    .context("Creating l1_handler_messages table")?;
+
+    let total: usize = tx
+        .query_row("SELECT count(1) FROM starknet_transactions", [], |row| {
+            row.get(0)
+        })
+        .context("Counting number of rows in starknet_transactions table")?;
+
+    tracing::info!(rows=%total, "Indexing L1 handler messages - this may take a while, please be patient. Progress will be logged regularly.");
+
+    let mut query_stmt = tx
+        .prepare("SELECT tx FROM starknet_transactions")
+        .context("Preparing transaction query statement")?;
+    let mut insert_stmt = tx
+        .prepare(
+            "INSERT OR REPLACE INTO l1_handler_messages (message_hash, transaction_hash) VALUES (?, ?)",
+        )
+        .context("Preparing L1 handler message insert statement")?;
+
+    let mut rows = query_stmt.query([]).context("Querying for transactions")?;
+
+    let mut timer = std::time::Instant::now();
+
+    let mut processed = 0usize;
+    while let Some(row) = rows.next().context("Fetching next transaction")? {
+        processed += 1;
+        if timer.elapsed() > std::time::Duration::from_secs(10) {
+            tracing::info!(%processed, %total, "Indexing L1 handler messages");
+            timer = std::time::Instant::now();
+        }
+
+        let Some(transaction) = row
+            .get_ref_unwrap(0)
+            .as_blob_or_null()
+            .context("Getting transaction bytes")?
+        else {
+            continue;
+        };
+        let transaction = zstd::decode_all(transaction).context("Decompressing transaction")?;
+        let transaction: gateway::Transaction =
+            serde_json::from_slice(&transaction).context("Deserializing transaction")?;
+
+        let gateway::Transaction::L1Handler(l1_handler) = transaction else {
+            continue;
+        };
+        let Some(message_hash) = crate::connection::l1_to_l2_message_hash(&l1_handler) else {
+            continue;
+        };
+
+        insert_stmt
+            .execute(params![
+                message_hash.as_bytes(),
+                l1_handler.transaction_hash.0.as_be_bytes()
+            ])
+            .context("Inserting L1 handler message")?;
+    }
+
+    Ok(())
+}
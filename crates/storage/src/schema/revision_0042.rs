@@ -0,0 +1,16 @@
+use anyhow::Context;
+use rusqlite::Transaction;
+
+/// Adds a covering index for looking up a transaction's block by the transaction's hash, so that
+/// transaction status queries do not need to read the transaction's row.
+pub(crate) fn migrate(tx: &Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Indexing transaction block hashes - this may take a while");
+
+    tx.execute(
+        "CREATE INDEX starknet_transactions_hash_block_hash ON starknet_transactions(hash, block_hash)",
+        [],
+    )
+    .context("Creating starknet_transactions_hash_block_hash index")?;
+
+    Ok(())
+}
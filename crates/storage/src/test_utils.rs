@@ -133,8 +133,10 @@ pub(crate) fn create_transactions_and_receipts(
                 n_steps: i as u64 + 987,
                 n_memory_holes: i as u64 + 1177,
             }),
+            execution_status: transaction::ExecutionStatus::Succeeded,
             l1_to_l2_consumed_message: None,
             l2_to_l1_messages: Vec::new(),
+            revert_error: None,
             transaction_hash: tx.hash(),
             transaction_index: TransactionIndex::new_or_panic(i as u64 + 2311),
         };
@@ -107,7 +107,7 @@ except ModuleNotFoundError:
 
 
 # used from tests, and the query which asserts that the schema is of expected version.
-EXPECTED_SCHEMA_REVISION = 35
+EXPECTED_SCHEMA_REVISION = 42
 EXPECTED_CAIRO_VERSION = "0.11.2a0"
 
 # this is set by pathfinder automatically when #[cfg(debug_assertions)]